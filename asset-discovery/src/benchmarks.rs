@@ -0,0 +1,817 @@
+use crate::{
+    effects, has_pallet_constant, legacy, split_tokens,
+    types::{normalize, TypeAliases},
+    Asset, AssetCategory, AssetInventory, Finding, ParsedFile,
+};
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::quote;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use syn::{visit::Visit, Type};
+
+// ----------------------------------------------Benchmark Coverage-------------------------------------------------
+// Weights are only as good as the benchmarks behind them. A dispatchable without a benchmark runs on a guessed
+// weight, a component range that stops short of the parameter's bound underestimates the largest call, and a
+// setup that leaves storage empty never measures the branch that only runs on populated storage. Benchmarks
+// written with `#[benchmarks]` and with the FRAME v1 `benchmarks!` macro are mapped to the dispatchables they
+// call and checked for all three.
+
+// Containers whose last generic argument bounds their length
+const BOUNDED_TYPES: [&str; 5] = [
+    "BoundedVec",
+    "WeakBoundedVec",
+    "BoundedBTreeMap",
+    "BoundedBTreeSet",
+    "BoundedSlice",
+];
+// Storage methods that read state, used in branch conditions
+const READ_METHODS: [&str; 7] = [
+    "get",
+    "try_get",
+    "contains_key",
+    "exists",
+    "decode_len",
+    "decode_non_dedup_len",
+    "count",
+];
+// Macros declaring FRAME v1 benchmarks
+const BENCHMARK_MACROS: [&str; 3] = ["benchmarks", "benchmarks_instance", "benchmarks_instance_pallet"];
+
+/// Dispatchable as far as its benchmarks are concerned
+struct Dispatchable {
+    bounded: Vec<(String, String)>, // (parameter, bound), e.g. ("items", "MaxItems") or ("name", "64")
+    guards: Vec<Guard>,             // branches that are more expensive on populated storage
+    file: String,
+    line: usize,
+}
+
+/// Branch condition reading storage, where the side taken on populated storage does more work
+struct Guard {
+    storage: BTreeSet<String>,
+    line: usize,
+}
+
+/// Benchmark of either syntax
+struct Benchmark {
+    name: String,
+    syntax: &'static str,                      // "v2" for `#[benchmark]`, "v1" for `benchmarks!`
+    components: Vec<(String, String, String)>, // (component, low, high)
+    calls: BTreeSet<String>,                   // functions called by the measured call or block
+    setup_writes: BTreeSet<String>,            // storage written directly by the setup
+    setup_calls: BTreeSet<String>,             // functions called by the setup
+    text: String,                              // tokens of the whole benchmark, to spot bounds used directly
+    file: String,
+    line: usize,
+}
+
+/// Visitor collecting dispatchables, benchmarks and what every function writes
+struct BenchmarkVisitor<'a> {
+    aliases: &'a TypeAliases,
+    file: String,                                                       // file currently visited
+    dispatchables: BTreeMap<String, Dispatchable>,                      // (dispatchable name, dispatchable)
+    functions: HashMap<String, (BTreeSet<String>, BTreeSet<String>)>,   // (function name, (writes, callees))
+    benchmarks: Vec<Benchmark>,
+}
+
+impl<'ast> Visit<'ast> for BenchmarkVisitor<'_> {
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let is_call = node.attrs.iter().any(|attr| has_pallet_constant("pallet::call".to_string(), attr));
+        for item in &node.items {
+            let syn::ImplItem::Fn(method) = item else {
+                continue;
+            };
+            let name = method.sig.ident.to_string();
+            self.functions.insert(name.clone(), function_effects(&method.block));
+            if is_call {
+                let inputs = typed_inputs(&method.sig);
+                let line = method.sig.ident.span().start().line;
+                let dispatchable = self.dispatchable(inputs, &method.block, line);
+                self.dispatchables.insert(name, dispatchable);
+            }
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+
+    // `#[benchmark] fn transfer(n: Linear<1, 100>) { .. }`, other functions are setup helpers
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        let is_benchmark = node
+            .attrs
+            .iter()
+            .any(|attr| attr.path().segments.last().is_some_and(|seg| seg.ident == "benchmark"));
+        if is_benchmark {
+            self.benchmark_v2(node);
+        } else {
+            self.functions.insert(node.sig.ident.to_string(), function_effects(&node.block));
+        }
+        syn::visit::visit_item_fn(self, node);
+    }
+
+    fn visit_item_macro(&mut self, node: &'ast syn::ItemMacro) {
+        match node.mac.path.segments.last() {
+            Some(seg) if BENCHMARK_MACROS.iter().any(|name| seg.ident == name) => {
+                self.benchmarks_v1(node.mac.tokens.clone())
+            }
+            Some(seg) if seg.ident == "decl_module" => self.decl_module(node.mac.tokens.clone()),
+            _ => {}
+        }
+        syn::visit::visit_item_macro(self, node);
+    }
+}
+
+impl BenchmarkVisitor<'_> {
+    fn dispatchable(&self, inputs: Vec<(String, Type)>, block: &syn::Block, line: usize) -> Dispatchable {
+        let bounded = inputs
+            .into_iter()
+            .filter_map(|(name, ty)| {
+                let mut finder = BoundFinder::default();
+                finder.visit_type(&self.aliases.expand(ty));
+                finder.bound.map(|bound| (name, bound))
+            })
+            .collect();
+        let mut guards = GuardFinder::default();
+        guards.visit_block(block);
+        Dispatchable {
+            bounded,
+            guards: guards.guards,
+            file: self.file.clone(),
+            line,
+        }
+    }
+
+    fn benchmark_v2(&mut self, node: &syn::ItemFn) {
+        let name = node.sig.ident.to_string();
+        let components = typed_inputs(&node.sig)
+            .into_iter()
+            .filter_map(|(component, ty)| {
+                let Type::Path(path) = ty else {
+                    return None;
+                };
+                let segment = path.path.segments.last()?;
+                let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+                    return None;
+                };
+                let range: Vec<String> = args.args.iter().map(|arg| range_end(quote!(#arg))).collect();
+                match range.as_slice() {
+                    [low, high] if segment.ident == "Linear" => Some((component, low.clone(), high.clone())),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        // Everything before `#[extrinsic_call]` or `#[block]` is setup
+        let stmts = &node.block.stmts;
+        let measured = stmts.iter().position(|stmt| {
+            stmt_attributes(stmt).iter().any(|attr| attr.path().is_ident("extrinsic_call") || attr.path().is_ident("block"))
+        });
+        let mut calls = BTreeSet::new();
+        if let Some(syn::Stmt::Expr(expr, _)) = measured.map(|position| &stmts[position]) {
+            match expr {
+                // `_(RawOrigin::Signed(caller), ..)` calls the dispatchable named like the benchmark
+                syn::Expr::Call(call) => match &*call.func {
+                    syn::Expr::Infer(_) => {
+                        calls.insert(name.clone());
+                    }
+                    syn::Expr::Path(path) => calls.extend(path.path.segments.last().map(|seg| seg.ident.to_string())),
+                    _ => {}
+                },
+                other => {
+                    let mut callees = Callees::default();
+                    callees.visit_expr(other);
+                    calls.extend(callees.names);
+                }
+            }
+        }
+
+        let setup = syn::Block {
+            brace_token: Default::default(),
+            stmts: stmts[..measured.unwrap_or(stmts.len())].to_vec(),
+        };
+        let (setup_writes, setup_calls) = function_effects(&setup);
+        self.benchmarks.push(Benchmark {
+            name,
+            syntax: "v2",
+            components,
+            calls,
+            setup_writes,
+            setup_calls,
+            text: quote!(#node).to_string(),
+            file: self.file.clone(),
+            line: node.sig.ident.span().start().line,
+        });
+    }
+
+    // benchmarks! {
+    //     transfer {
+    //         let n in 1 .. T::MaxItems::get();
+    //         let caller: T::AccountId = whitelisted_caller();
+    //     }: _(RawOrigin::Signed(caller), n)
+    //     verify { .. }
+    // }
+    fn benchmarks_v1(&mut self, tokens: TokenStream) {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        for (i, token) in tokens.iter().enumerate() {
+            let (TokenTree::Ident(name), Some(TokenTree::Group(body)), Some(TokenTree::Punct(colon))) =
+                (token, tokens.get(i + 1), tokens.get(i + 2))
+            else {
+                continue;
+            };
+            if body.delimiter() != Delimiter::Brace || colon.as_char() != ':' || colon.spacing() == Spacing::Joint {
+                continue;
+            }
+
+            let mut calls = BTreeSet::new();
+            match (tokens.get(i + 3), tokens.get(i + 4)) {
+                (Some(TokenTree::Ident(call)), Some(TokenTree::Group(_))) if call == "_" => {
+                    calls.insert(name.to_string());
+                }
+                (Some(TokenTree::Ident(call)), Some(TokenTree::Group(_))) => {
+                    calls.insert(call.to_string());
+                }
+                (Some(TokenTree::Group(block)), _) if block.delimiter() == Delimiter::Brace => {
+                    if let Ok(block) = syn::parse2::<syn::Block>(TokenTree::Group(block.clone()).into()) {
+                        let mut callees = Callees::default();
+                        callees.visit_block(&block);
+                        calls.extend(callees.names);
+                    }
+                }
+                _ => {}
+            }
+
+            // `let n in 1 .. T::MaxItems::get();` declares a component, every other statement is setup
+            let mut components = Vec::new();
+            let mut setup = TokenStream::new();
+            for stmt in split_tokens(body.stream(), ';') {
+                match component(&stmt) {
+                    Some(component) => components.push(component),
+                    None => {
+                        setup.extend(stmt);
+                        setup.extend(quote!(;));
+                    }
+                }
+            }
+            let (setup_writes, setup_calls) = syn::parse2::<syn::Block>(quote!({ #setup }))
+                .map(|block| function_effects(&block))
+                .unwrap_or_default();
+
+            let end = tokens.len().min(i + 5);
+            let text: TokenStream = tokens[i..end].iter().cloned().collect();
+            self.benchmarks.push(Benchmark {
+                name: name.to_string(),
+                syntax: "v1",
+                components,
+                calls,
+                setup_writes,
+                setup_calls,
+                text: text.to_string(),
+                file: self.file.clone(),
+                line: name.span().start().line,
+            });
+        }
+    }
+
+    // Public functions of a FRAME v1 `decl_module!` are its dispatchables
+    fn decl_module(&mut self, tokens: TokenStream) {
+        for function in legacy::module_fns(tokens) {
+            self.functions.insert(function.name.clone(), function_effects(&function.block));
+            if function.public {
+                let dispatchable = self.dispatchable(function.params, &function.block, function.line);
+                self.dispatchables.insert(function.name, dispatchable);
+            }
+        }
+    }
+
+    // Storage written by functions called from the setup, following calls between the pallet's own functions
+    fn writes_through(&self, calls: &BTreeSet<String>) -> BTreeSet<String> {
+        let mut writes = BTreeSet::new();
+        let mut visited = BTreeSet::new();
+        let mut pending: Vec<&String> = calls.iter().collect();
+        while let Some(call) = pending.pop() {
+            if !visited.insert(call) {
+                continue;
+            }
+            if let Some((function_writes, callees)) = self.functions.get(call) {
+                writes.extend(function_writes.iter().cloned());
+                pending.extend(callees);
+            }
+        }
+        writes
+    }
+}
+
+/// Visitor finding the bound of a bounded container, `BoundedVec<u8, T::MaxLength>` is bounded by `MaxLength`
+#[derive(Default)]
+struct BoundFinder {
+    bound: Option<String>,
+}
+
+impl<'ast> Visit<'ast> for BoundFinder {
+    fn visit_path_segment(&mut self, node: &'ast syn::PathSegment) {
+        if self.bound.is_none() && BOUNDED_TYPES.iter().any(|name| node.ident == name) {
+            if let syn::PathArguments::AngleBracketed(args) = &node.arguments {
+                self.bound = args.args.last().and_then(bound_name);
+            }
+        }
+        syn::visit::visit_path_segment(self, node);
+    }
+}
+
+/// Visitor collecting branch conditions on storage whose populated side does more work
+#[derive(Default)]
+struct GuardFinder {
+    guards: Vec<Guard>,
+}
+
+impl<'ast> Visit<'ast> for GuardFinder {
+    // `if let Some(item) = Items::<T>::get(id) { .. }` or `if !Items::<T>::contains_key(id) { .. }`
+    fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+        let reads = storage_reads(&node.cond);
+        if !reads.is_empty() {
+            let then_cost = cost(|visitor| visitor.visit_block(&node.then_branch));
+            let else_cost = node
+                .else_branch
+                .as_ref()
+                .map_or(0, |(_, expr)| cost(|visitor| visitor.visit_expr(expr)));
+            let negated = matches!(&*node.cond, syn::Expr::Unary(unary) if matches!(unary.op, syn::UnOp::Not(_)));
+            let (populated, empty) = if negated { (else_cost, then_cost) } else { (then_cost, else_cost) };
+            if populated > empty {
+                self.guards.push(Guard {
+                    storage: reads,
+                    line: node.if_token.span.start().line,
+                });
+            }
+        }
+        syn::visit::visit_expr_if(self, node);
+    }
+
+    // `match Items::<T>::get(id) { Some(item) => .., None => .. }`
+    fn visit_expr_match(&mut self, node: &'ast syn::ExprMatch) {
+        let reads = storage_reads(&node.expr);
+        if !reads.is_empty() {
+            let (mut populated, mut empty) = (0, 0);
+            for arm in &node.arms {
+                let arm_cost = cost(|visitor| visitor.visit_expr(&arm.body));
+                if is_empty_pattern(&arm.pat) {
+                    empty = empty.max(arm_cost);
+                } else {
+                    populated = populated.max(arm_cost);
+                }
+            }
+            if populated > empty {
+                self.guards.push(Guard {
+                    storage: reads,
+                    line: node.match_token.span.start().line,
+                });
+            }
+        }
+        syn::visit::visit_expr_match(self, node);
+    }
+}
+
+/// Visitor collecting the last path segment of every called function
+#[derive(Default)]
+struct Callees {
+    names: BTreeSet<String>,
+}
+
+impl<'ast> Visit<'ast> for Callees {
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(path) = &*node.func {
+            self.names.extend(path.path.segments.last().map(|seg| seg.ident.to_string()));
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+}
+
+/// Visitor counting calls and loops, a rough measure of the work done by a branch
+#[derive(Default)]
+struct Cost {
+    work: usize,
+}
+
+impl<'ast> Visit<'ast> for Cost {
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        self.work += 1;
+        syn::visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        self.work += 1;
+        syn::visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.work += 1;
+        syn::visit::visit_expr_for_loop(self, node);
+    }
+
+    fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+        self.work += 1;
+        syn::visit::visit_expr_while(self, node);
+    }
+}
+
+/// Map benchmarks to dispatchables and add findings for missing benchmarks, component ranges short of a
+/// parameter bound and setups that never reach the expensive branch of the call
+///
+/// Reads the Storage assets, so it runs after they were created. Sources without any benchmark are assumed to
+/// keep them elsewhere and are left alone.
+pub(crate) fn collect(files: &[ParsedFile], inventory: &mut AssetInventory) {
+    let aliases = TypeAliases::collect(files);
+    let mut visitor = BenchmarkVisitor {
+        aliases: &aliases,
+        file: String::new(),
+        dispatchables: BTreeMap::new(),
+        functions: HashMap::new(),
+        benchmarks: Vec::new(),
+    };
+    for file in files {
+        visitor.file = file.path.clone();
+        visitor.visit_file(&file.syntax_tree);
+    }
+    if visitor.benchmarks.is_empty() {
+        return;
+    }
+
+    let storage: BTreeSet<&str> = inventory
+        .assets
+        .iter()
+        .filter(|asset| matches!(asset.category, AssetCategory::Storage(..)))
+        .map(|asset| asset.name.as_str())
+        .collect();
+
+    let mut findings = Vec::new();
+    let mut assets = Vec::new();
+    let mut covered: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for benchmark in &visitor.benchmarks {
+        let targets: Vec<&str> = benchmark
+            .calls
+            .iter()
+            .filter(|call| visitor.dispatchables.contains_key(*call))
+            .map(String::as_str)
+            .collect();
+        let mut setup_writes = visitor.writes_through(&benchmark.setup_calls);
+        setup_writes.extend(benchmark.setup_writes.iter().cloned());
+
+        for target in &targets {
+            covered.entry(target).or_default().push(&benchmark.name);
+            let dispatchable = &visitor.dispatchables[*target];
+            let finding = |rule: &str, message: String| Finding {
+                rule: rule.to_string(),
+                asset: benchmark.name.clone(),
+                message,
+                file: Some(benchmark.file.clone()),
+                line: Some(benchmark.line),
+            };
+
+            for (param, bound) in &dispatchable.bounded {
+                let reaches_bound = |high: &str| high == bound || contains_word(high, bound);
+                let message = if benchmark.components.is_empty() {
+                    // Without components the benchmark has to build the largest input itself
+                    (!contains_word(&benchmark.text, bound)).then(|| {
+                        format!(
+                            "`{}` has no component for `{}` of `{}`, which is bounded by `{}`",
+                            benchmark.name, param, target, bound
+                        )
+                    })
+                } else if !benchmark.components.iter().any(|(_, _, high)| reaches_bound(high)) {
+                    let ranges: Vec<String> = benchmark
+                        .components
+                        .iter()
+                        .map(|(component, low, high)| format!("{} in {}..{}", component, low, high))
+                        .collect();
+                    Some(format!(
+                        "no component of `{}` ({}) ranges up to `{}`, the bound of `{}` in `{}`",
+                        benchmark.name,
+                        ranges.join(", "),
+                        bound,
+                        param,
+                        target
+                    ))
+                } else {
+                    None
+                };
+                findings.extend(message.map(|message| finding("benchmark-range-mismatch", message)));
+            }
+
+            for guard in &dispatchable.guards {
+                let guarded: Vec<&str> = guard
+                    .storage
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|item| storage.contains(item))
+                    .collect();
+                if guarded.is_empty() || guarded.iter().any(|item| setup_writes.contains(*item)) {
+                    continue;
+                }
+                findings.push(finding(
+                    "benchmark-misses-worst-case",
+                    format!(
+                        "the setup of `{}` never writes `{}`, so the more expensive branch of `{}` at line {} is not measured",
+                        benchmark.name,
+                        guarded.join("`, `"),
+                        target,
+                        guard.line
+                    ),
+                ));
+            }
+        }
+
+        let category = AssetCategory::Benchmark(benchmark.name.clone(), targets.join(","));
+        let mut asset = Asset::new("none", &benchmark.name, category)
+            .with_attribute("syntax", benchmark.syntax)
+            .with_attribute("line", benchmark.line.to_string());
+        if !benchmark.components.is_empty() {
+            let components: Vec<String> = benchmark
+                .components
+                .iter()
+                .map(|(component, low, high)| format!("{}={}..{}", component, low, high))
+                .collect();
+            asset = asset.with_attribute("components", components.join(","));
+        }
+        assets.push(asset);
+    }
+
+    for (name, dispatchable) in &visitor.dispatchables {
+        if !covered.contains_key(name.as_str()) {
+            findings.push(Finding {
+                rule: "missing-benchmark".to_string(),
+                asset: name.clone(),
+                message: format!("dispatchable `{}` has no benchmark, its weight is not measured", name),
+                file: Some(dispatchable.file.clone()),
+                line: Some(dispatchable.line),
+            });
+        }
+    }
+
+    // Dispatchables list the benchmarks measuring them
+    for asset in &mut inventory.assets {
+        if let (AssetCategory::PublicFunction(..), Some(benchmarks)) =
+            (&asset.category, covered.get(asset.name.as_str()))
+        {
+            asset.attributes.insert("benchmarks".to_string(), benchmarks.join(","));
+        }
+    }
+    inventory.assets.extend(assets);
+    inventory.findings.extend(findings);
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+// Storage written and functions called by a block
+fn function_effects(block: &syn::Block) -> (BTreeSet<String>, BTreeSet<String>) {
+    let mut callees = Callees::default();
+    callees.visit_block(block);
+    (effects::analyse(block).writes, callees.names)
+}
+
+// Parameters with a plain name, `origin: OriginFor<T>` included
+fn typed_inputs(sig: &syn::Signature) -> Vec<(String, Type)> {
+    sig.inputs
+        .iter()
+        .filter_map(|input| match input {
+            syn::FnArg::Typed(pat_type) => match &*pat_type.pat {
+                syn::Pat::Ident(ident) => Some((ident.ident.to_string(), (*pat_type.ty).clone())),
+                _ => None,
+            },
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect()
+}
+
+// `T::MaxItems` is bounded by `MaxItems`, `ConstU32<64>` by `64`
+fn bound_name(arg: &syn::GenericArgument) -> Option<String> {
+    let syn::GenericArgument::Type(Type::Path(path)) = arg else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if segment.ident.to_string().starts_with("Const") => {
+            let value = args.args.first()?;
+            Some(range_end(quote!(#value)))
+        }
+        _ => Some(segment.ident.to_string()),
+    }
+}
+
+// `{ T::MaxItems::get() }` to `T::MaxItems::get()`, `1_000` to `1000`
+fn range_end(tokens: TokenStream) -> String {
+    let end = normalize(&tokens.to_string());
+    let end = end.trim_start_matches('{').trim_end_matches('}').trim();
+    if end.chars().all(|c| c.is_ascii_digit() || c == '_') {
+        end.replace('_', "")
+    } else {
+        end.to_string()
+    }
+}
+
+// `let n in 1 .. T::MaxItems::get()` to ("n", "1", "T::MaxItems::get()")
+fn component(stmt: &[TokenTree]) -> Option<(String, String, String)> {
+    let [TokenTree::Ident(keyword), TokenTree::Ident(name), TokenTree::Ident(in_keyword), range @ ..] = stmt else {
+        return None;
+    };
+    if keyword != "let" || in_keyword != "in" {
+        return None;
+    }
+    let dots = range.windows(2).position(|pair| {
+        matches!(pair, [TokenTree::Punct(first), TokenTree::Punct(second)]
+            if first.as_char() == '.' && second.as_char() == '.')
+    })?;
+    let low: TokenStream = range[..dots].iter().cloned().collect();
+    let high: TokenStream = range[dots + 2..].iter().cloned().collect();
+    Some((name.to_string(), range_end(low), range_end(high)))
+}
+
+// Storage items read in an expression, `Items::<T>::get(id)` or `<Items<T>>::contains_key(id)`
+fn storage_reads(expr: &syn::Expr) -> BTreeSet<String> {
+    #[derive(Default)]
+    struct Reads {
+        storage: BTreeSet<String>,
+    }
+
+    impl<'ast> Visit<'ast> for Reads {
+        fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+            if let syn::Expr::Path(path) = &*node.func {
+                let segments: Vec<String> = path.path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+                if segments.last().is_some_and(|method| READ_METHODS.contains(&method.as_str())) {
+                    let storage = match &path.qself {
+                        Some(qself) => match &*qself.ty {
+                            Type::Path(ty) => ty.path.segments.last().map(|seg| seg.ident.to_string()),
+                            _ => None,
+                        },
+                        None => segments.len().checked_sub(2).map(|i| segments[i].clone()),
+                    };
+                    self.storage.extend(storage);
+                }
+            }
+            syn::visit::visit_expr_call(self, node);
+        }
+    }
+
+    let mut reads = Reads::default();
+    reads.visit_expr(expr);
+    reads.storage
+}
+
+fn cost(visit: impl FnOnce(&mut Cost)) -> usize {
+    let mut cost = Cost::default();
+    visit(&mut cost);
+    cost.work
+}
+
+// `None`, `Err(_)` and `_` are the arms taken on empty storage
+fn is_empty_pattern(pat: &syn::Pat) -> bool {
+    match pat {
+        syn::Pat::Ident(ident) => ident.ident == "None",
+        syn::Pat::Path(path) => path.path.is_ident("None"),
+        syn::Pat::TupleStruct(tuple) => tuple.path.is_ident("Err"),
+        syn::Pat::Wild(_) => true,
+        _ => false,
+    }
+}
+
+fn stmt_attributes(stmt: &syn::Stmt) -> &[syn::Attribute] {
+    match stmt {
+        syn::Stmt::Local(local) => &local.attrs,
+        syn::Stmt::Macro(mac) => &mac.attrs,
+        syn::Stmt::Expr(syn::Expr::Call(call), _) => &call.attrs,
+        syn::Stmt::Expr(syn::Expr::Block(block), _) => &block.attrs,
+        _ => &[],
+    }
+}
+
+// Whole identifier match, so `MaxItems` does not match `MaxItemsPerBlock`
+fn contains_word(text: &str, word: &str) -> bool {
+    text.match_indices(word).any(|(start, matched)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + matched.len()..].chars().next();
+        let is_boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric() && c != '_');
+        is_boundary(before) && is_boundary(after)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{inventory_of_files, AssetCategory, AssetInventory};
+
+    const PALLET: &str = r#"
+        #[frame_support::pallet]
+        pub mod pallet {
+            #[pallet::storage]
+            pub type Items<T: Config> =
+                StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<u32, T::MaxItems>>;
+
+            #[pallet::call]
+            impl<T: Config> Pallet<T> {
+                pub fn store(origin: OriginFor<T>, items: BoundedVec<u32, T::MaxItems>) -> DispatchResult {
+                    let who = ensure_signed(origin)?;
+                    if let Some(old) = Items::<T>::get(&who) {
+                        for item in old {
+                            Self::release(item);
+                        }
+                    }
+                    Items::<T>::insert(&who, items);
+                    Ok(())
+                }
+
+                pub fn clear(origin: OriginFor<T>) -> DispatchResult {
+                    let who = ensure_signed(origin)?;
+                    Items::<T>::remove(&who);
+                    Ok(())
+                }
+
+                pub fn freeze(origin: OriginFor<T>) -> DispatchResult {
+                    ensure_root(origin)?;
+                    Ok(())
+                }
+            }
+        }
+    "#;
+
+    fn findings(inventory: &AssetInventory) -> Vec<(&str, &str)> {
+        inventory
+            .findings
+            .iter()
+            .filter(|finding| finding.rule.contains("benchmark"))
+            .map(|finding| (finding.rule.as_str(), finding.asset.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn short_ranges_and_empty_setups_are_reported() {
+        let benchmarking = r#"
+            fn fill<T: Config>(who: &T::AccountId) {
+                Items::<T>::insert(who, BoundedVec::default());
+            }
+
+            #[benchmarks]
+            mod benchmarks {
+                #[benchmark]
+                fn store(n: Linear<1, 10>) {
+                    let caller: T::AccountId = whitelisted_caller();
+                    #[extrinsic_call]
+                    _(RawOrigin::Signed(caller), items(n));
+                }
+
+                #[benchmark]
+                fn store_populated(n: Linear<1, { T::MaxItems::get() }>) {
+                    let caller: T::AccountId = whitelisted_caller();
+                    fill::<T>(&caller);
+                    #[extrinsic_call]
+                    store(RawOrigin::Signed(caller), items(n));
+                }
+            }
+
+            benchmarks! {
+                clear {
+                    let caller: T::AccountId = whitelisted_caller();
+                }: _(RawOrigin::Signed(caller))
+            }
+        "#;
+        let inventory = inventory_of_files(&[("src/lib.rs", PALLET), ("src/benchmarking.rs", benchmarking)]);
+
+        assert_eq!(
+            findings(&inventory),
+            [
+                ("benchmark-range-mismatch", "store"),
+                ("benchmark-misses-worst-case", "store"),
+                ("missing-benchmark", "freeze"),
+            ]
+        );
+        let benchmarks: Vec<(&str, &str, &str)> = inventory
+            .assets
+            .iter()
+            .filter_map(|asset| match &asset.category {
+                AssetCategory::Benchmark(name, targets) => {
+                    Some((name.as_str(), targets.as_str(), asset.attributes["syntax"].as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            benchmarks,
+            [("store", "store", "v2"), ("store_populated", "store", "v2"), ("clear", "clear", "v1")]
+        );
+        let store = inventory.assets.iter().find(|asset| asset.name == "store").unwrap();
+        assert_eq!(store.attributes["benchmarks"], "store,store_populated");
+    }
+
+    #[test]
+    fn a_component_up_to_the_bound_covers_the_worst_case() {
+        let benchmarking = r#"
+            benchmarks! {
+                store {
+                    let n in 1 .. T::MaxItems::get();
+                    let caller: T::AccountId = whitelisted_caller();
+                    Items::<T>::insert(&caller, BoundedVec::default());
+                }: _(RawOrigin::Signed(caller), items(n))
+
+                clear {}: _(RawOrigin::Signed(whitelisted_caller()))
+                freeze {}: _(RawOrigin::Root)
+            }
+        "#;
+        let inventory = inventory_of_files(&[("src/lib.rs", PALLET), ("src/benchmarking.rs", benchmarking)]);
+        assert!(findings(&inventory).is_empty());
+
+        // Pallets keeping their benchmarks in another crate are left alone
+        assert!(findings(&inventory_of_files(&[("src/lib.rs", PALLET)])).is_empty());
+    }
+}
@@ -0,0 +1,441 @@
+use crate::{
+    parse_source, parser, read_sources, AppError, Asset, AssetCategory, OutputFormat, ParsedFile, SourceFile,
+};
+use proc_macro2::{TokenStream, TokenTree};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use syn::visit::Visit;
+
+// ----------------------------------------------Bulk Scanning------------------------------------------------------
+// Audits of a whole runtime or of `substrate/frame` span dozens of pallets. Every crate below the workspace root
+// that declares a pallet is analysed on its own thread, gets its own inventory and one row in a summary report
+// ranking the pallets by how much attack surface they expose.
+
+// Attributes and macros declaring a pallet, looked for in the raw source before anything is parsed
+const PALLET_MARKERS: [&str; 4] = ["#[frame_support::pallet", "#[frame::pallet", "#[pallet]", "decl_module!"];
+// Macros that abort the block when reached
+const PANIC_MACROS: [&str; 7] = ["panic", "unreachable", "todo", "unimplemented", "assert", "assert_eq", "assert_ne"];
+// Files that only exist for tests and benchmarks, their panics never reach a block
+const NON_RUNTIME_FILES: [&str; 4] = ["tests.rs", "mock.rs", "benchmarking.rs", "weights.rs"];
+
+/// Per-pallet counts used to prioritise audit work
+#[derive(Debug, Serialize)]
+struct PalletSummary {
+    pallet: String,
+    path: String,
+    dispatchables: usize,
+    storage_items: usize,
+    // Dispatchables reachable without a signed origin, i.e. checked with `ensure_none`
+    unsigned_entry_points: usize,
+    // `panic!`-like macros, `unwrap()` and `expect()` outside of test code
+    panic_sites: usize,
+    findings: usize,
+    unparsed_regions: usize,
+    // Set when the crate could not be analysed, the counts are then zero
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Aggregate report of a bulk scan
+#[derive(Debug, Serialize)]
+struct BulkReport {
+    workspace: String,
+    pallets: Vec<PalletSummary>,
+}
+
+/// Discover every pallet crate below `root`, analyse them in parallel and write one inventory per pallet
+/// plus the aggregate report into `output_dir`
+pub(crate) fn run(root: &Path, format: OutputFormat, output_dir: Option<&Path>) -> Result<(), AppError> {
+    // SARIF describes findings, the summary has none of its own
+    if matches!(format, OutputFormat::Sarif) {
+        return Err(AppError::InvalidInput(
+            "The bulk summary cannot be written as SARIF, pick another format".to_string(),
+        ));
+    }
+    let output_dir = output_dir.map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("./asset-inventories"));
+    fs::create_dir_all(&output_dir)?;
+
+    let mut crates = Vec::new();
+    collect_crates(root, &mut crates)?;
+    crates.sort();
+    let candidates: Vec<(String, PathBuf)> = crates
+        .into_iter()
+        .filter(|dir| mentions_pallet(dir))
+        .map(|dir| (crate_name(&dir), dir))
+        .collect();
+
+    // Syntax trees are not `Send`, so each crate is read, parsed and analysed on the thread that owns it
+    let mut summaries: Vec<PalletSummary> = candidates
+        .par_iter()
+        .filter_map(|(name, dir)| match scan_pallet(name, dir, format, &output_dir) {
+            Ok(summary) => summary,
+            Err(e) => Some(PalletSummary {
+                pallet: name.clone(),
+                path: dir.display().to_string(),
+                dispatchables: 0,
+                storage_items: 0,
+                unsigned_entry_points: 0,
+                panic_sites: 0,
+                findings: 0,
+                unparsed_regions: 0,
+                error: Some(e.to_string()),
+            }),
+        })
+        .collect();
+    if summaries.is_empty() {
+        return Err(AppError::InvalidInput(format!("No pallet crates found below {}", root.display())));
+    }
+    println!("Scanned {} pallet crates below {}", summaries.len(), root.display());
+
+    // Most exposed pallets first
+    summaries.sort_by(|a, b| {
+        (b.unsigned_entry_points, b.dispatchables, b.panic_sites, b.storage_items)
+            .cmp(&(a.unsigned_entry_points, a.dispatchables, a.panic_sites, a.storage_items))
+            .then_with(|| a.pallet.cmp(&b.pallet))
+    });
+
+    let report = BulkReport {
+        workspace: root.display().to_string(),
+        pallets: summaries,
+    };
+    print!("{}", markdown_report(&report));
+
+    let (extension, content) = match format {
+        OutputFormat::Markdown => ("md", markdown_report(&report)),
+        OutputFormat::Csv => ("csv", csv_report(&report)?),
+        OutputFormat::Yaml => (
+            "yaml",
+            serde_yaml::to_string(&report).map_err(|e| AppError::FormatError(e.to_string()))?,
+        ),
+        OutputFormat::Json => ("JSON", serde_json::to_string(&report)?),
+        OutputFormat::PrettyJson => ("JSON", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Sarif => unreachable!("rejected before scanning"),
+    };
+    fs::write(output_dir.join(format!("summary.{}", extension)), content)?;
+    Ok(())
+}
+
+// Analyse one crate and write its inventory, `None` when the crate turns out not to declare a pallet
+fn scan_pallet(
+    name: &str,
+    dir: &Path,
+    format: OutputFormat,
+    output_dir: &Path,
+) -> Result<Option<PalletSummary>, AppError> {
+    let files: Vec<ParsedFile> = crate_sources(dir)?.iter().map(parse_source).collect();
+
+    let mut counter = SurfaceCounter::default();
+    for file in files.iter().filter(|file| !is_non_runtime_file(&file.path)) {
+        counter.visit_file(&file.syntax_tree);
+    }
+    if !counter.declares_pallet {
+        return Ok(None);
+    }
+
+    let mut inventory = parser(&files)?;
+    inventory.source = Some(dir.display().to_string());
+    let dispatchables: Vec<&Asset> = inventory
+        .assets
+        .iter()
+        .filter(|asset| asset.attributes.get("dispatchable").is_some_and(|value| value == "yes"))
+        .collect();
+
+    let writer = format.writer();
+    let inventory_path = output_dir.join(format!("{}.{}", name, writer.extension()));
+    fs::write(inventory_path, writer.write(&inventory)?)?;
+
+    Ok(Some(PalletSummary {
+        pallet: name.to_string(),
+        path: dir.display().to_string(),
+        dispatchables: dispatchables.len(),
+        storage_items: inventory
+            .assets
+            .iter()
+            .filter(|asset| matches!(asset.category, AssetCategory::Storage(..)))
+            .count(),
+        unsigned_entry_points: dispatchables
+            .iter()
+            .filter(|asset| asset.attributes.get("origin").is_some_and(|origin| origin == "none"))
+            .count(),
+        panic_sites: counter.panic_sites,
+        findings: inventory.findings.len(),
+        unparsed_regions: inventory.unparsed.len(),
+        error: None,
+    }))
+}
+
+/// Visitor looking for a pallet declaration and counting panic sites, skipping `#[cfg(test)]` modules and
+/// `#[test]` functions. Everything else is read from the inventory.
+#[derive(Default)]
+struct SurfaceCounter {
+    declares_pallet: bool, // a `#[pallet]` module or a `decl_module!` invocation was seen
+    panic_sites: usize,    // panicking macros, `unwrap()` and `expect()`
+}
+
+impl<'ast> Visit<'ast> for SurfaceCounter {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        // `#[pallet]`, `#[frame_support::pallet]`, `#[frame::pallet(dev_mode)]`
+        let is_pallet = node
+            .attrs
+            .iter()
+            .any(|attr| attr.path().segments.last().is_some_and(|seg| seg.ident == "pallet"));
+        self.declares_pallet |= is_pallet;
+        if !node.attrs.iter().any(is_test_attribute) {
+            syn::visit::visit_item_mod(self, node);
+        }
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        if !node.attrs.iter().any(is_test_attribute) {
+            syn::visit::visit_item_fn(self, node);
+        }
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if node.method == "unwrap" || node.method == "expect" {
+            self.panic_sites += 1;
+        }
+        syn::visit::visit_expr_method_call(self, node);
+    }
+
+    // Macro bodies are opaque to syn: `panic!` itself is counted here, `decl_module!` and friends are scanned
+    // token by token
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        match node.path.segments.last() {
+            Some(seg) if PANIC_MACROS.iter().any(|name| seg.ident == name) => self.panic_sites += 1,
+            Some(seg) => {
+                self.declares_pallet |= seg.ident == "decl_module";
+                self.scan_tokens(node.tokens.clone());
+            }
+            None => {}
+        }
+    }
+}
+
+impl SurfaceCounter {
+    fn scan_tokens(&mut self, tokens: TokenStream) {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        for (i, token) in tokens.iter().enumerate() {
+            match token {
+                TokenTree::Group(group) => self.scan_tokens(group.stream()),
+                TokenTree::Ident(ident) => {
+                    let next = tokens.get(i + 1);
+                    let is_call = matches!(next, Some(TokenTree::Group(_)));
+                    let is_macro = matches!(next, Some(TokenTree::Punct(p)) if p.as_char() == '!');
+                    let is_method = i > 0 && matches!(&tokens[i - 1], TokenTree::Punct(p) if p.as_char() == '.');
+
+                    let panics = (ident == "unwrap" || ident == "expect") && is_method && is_call
+                        || is_macro && PANIC_MACROS.iter().any(|name| ident == name);
+                    if panics {
+                        self.panic_sites += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// ----------------------------------------------Report Formats-----------------------------------------------------
+
+fn markdown_report(report: &BulkReport) -> String {
+    let mut markdown = format!("# Pallet Summary\n\nWorkspace: `{}`\n\n", report.workspace);
+    markdown.push_str("| Pallet | Dispatchables | Storage Items | Unsigned Entry Points | Panic Sites | Findings | Unparsed Regions |\n");
+    markdown.push_str("|---|---|---|---|---|---|---|\n");
+    for pallet in &report.pallets {
+        let name = match &pallet.error {
+            Some(error) => format!("{} (failed: {})", pallet.pallet, error.replace('|', "\\|")),
+            None => pallet.pallet.clone(),
+        };
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} |\n",
+            name,
+            pallet.dispatchables,
+            pallet.storage_items,
+            pallet.unsigned_entry_points,
+            pallet.panic_sites,
+            pallet.findings,
+            pallet.unparsed_regions,
+        ));
+    }
+    markdown
+}
+
+fn csv_report(report: &BulkReport) -> Result<String, AppError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for pallet in &report.pallets {
+        writer.serialize(pallet).map_err(|e| AppError::FormatError(e.to_string()))?;
+    }
+    let bytes = writer.into_inner().map_err(|e| AppError::FormatError(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| AppError::FormatError(e.to_string()))
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+// Recursively collect crate directories, skipping build output and hidden directories
+fn collect_crates(dir: &Path, crates: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    if dir.join("Cargo.toml").is_file() && dir.join("src").is_dir() {
+        crates.push(dir.to_path_buf());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden_or_target = path
+            .file_name()
+            .is_some_and(|name| name == "target" || name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !hidden_or_target {
+            collect_crates(&path, crates)?;
+        }
+    }
+    Ok(())
+}
+
+// Sources of a crate: `src/` and the integration tests in `tests/`, which the coverage pass reads
+fn crate_sources(dir: &Path) -> Result<Vec<SourceFile>, AppError> {
+    let mut sources = read_sources(&dir.join("src"))?;
+    let tests = dir.join("tests");
+    if tests.is_dir() {
+        // Nested crates below `tests/`, e.g. UI test crates, are scanned on their own
+        let own_tests = read_sources(&tests)?
+            .into_iter()
+            .filter(|source| crate_root(dir, &source.path) == dir);
+        sources.extend(own_tests);
+    }
+    Ok(sources)
+}
+
+// Closest directory with a `Cargo.toml` between the file and `root`, or `root` itself
+pub(crate) fn crate_root(root: &Path, file: &Path) -> PathBuf {
+    file.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .unwrap_or(root)
+        .to_path_buf()
+}
+
+// Cheap textual check so only likely pallet crates are parsed
+fn mentions_pallet(dir: &Path) -> bool {
+    let Ok(sources) = read_sources(&dir.join("src")) else {
+        return false;
+    };
+    sources
+        .iter()
+        .any(|source| PALLET_MARKERS.iter().any(|marker| source.content.contains(marker)))
+}
+
+// Package name from Cargo.toml, falling back to the directory name
+fn crate_name(dir: &Path) -> String {
+    let package_name = fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|manifest| manifest.parse::<toml::Table>().ok())
+        .and_then(|manifest| manifest.get("package")?.get("name")?.as_str().map(str::to_string));
+    package_name.unwrap_or_else(|| dir.file_name().unwrap_or_default().to_string_lossy().to_string())
+}
+
+// Mocks, tests, benchmarks and weights, which are not part of the pallet the runtime includes
+pub(crate) fn is_non_runtime_file(path: &str) -> bool {
+    is_test_file(path)
+        || Path::new(path)
+            .file_name()
+            .is_some_and(|name| NON_RUNTIME_FILES.iter().any(|file| name == *file))
+}
+
+// Mocks and tests, which only exist for the unit and integration tests of the crate
+pub(crate) fn is_test_file(path: &str) -> bool {
+    in_tests_dir(path) || Path::new(path).file_name().is_some_and(|name| name == "tests.rs" || name == "mock.rs")
+}
+
+// `tests/` of a crate or `src/tests/`, a crate that merely lives below a `tests` directory has its own `src`
+pub(crate) fn in_tests_dir(path: &str) -> bool {
+    let components: Vec<_> = Path::new(path).components().map(|component| component.as_os_str()).collect();
+    components
+        .iter()
+        .rposition(|component| *component == "tests")
+        .is_some_and(|tests| !components[tests..].iter().any(|component| *component == "src"))
+}
+
+// `#[test]` and `#[cfg(test)]`
+fn is_test_attribute(attr: &syn::Attribute) -> bool {
+    if attr.path().is_ident("test") {
+        return true;
+    }
+    attr.path().is_ident("cfg") && attr.meta.require_list().is_ok_and(|list| list.tokens.to_string() == "test")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+    use crate::{AppError, OutputFormat};
+    use serde_json::Value;
+    use std::{fs, path::Path};
+
+    const PALLET: &str = r#"
+        #[frame_support::pallet]
+        pub mod pallet {
+            #[pallet::storage]
+            pub type Items<T> = StorageValue<_, u32>;
+
+            #[pallet::call]
+            impl<T: Config> Pallet<T> {
+                pub fn store(origin: OriginFor<T>) -> DispatchResult {
+                    ensure_signed(origin)?;
+                    let value = Items::<T>::get().unwrap();
+                    Ok(())
+                }
+
+                pub fn heartbeat(origin: OriginFor<T>) -> DispatchResult {
+                    ensure_none(origin)?;
+                    Ok(())
+                }
+            }
+
+            impl<T: Config> Pallet<T> {
+                pub fn helper() {
+                    ensure_none(frame_system::RawOrigin::None.into()).expect("none");
+                }
+            }
+        }
+    "#;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn the_summary_is_read_from_the_inventory_of_each_pallet() {
+        let root = std::env::temp_dir().join(format!("asset-discovery-{}-bulk", std::process::id()));
+        write(&root, "pallets/items/Cargo.toml", "[package]\nname = \"pallet-items\"\n");
+        write(&root, "pallets/items/src/lib.rs", PALLET);
+        // Integration tests are read for coverage, their panics do not count
+        write(&root, "pallets/items/tests/store.rs", "#[test] fn store() { Items::store(origin()).unwrap(); }");
+        write(&root, "primitives/Cargo.toml", "[package]\nname = \"primitives\"\n");
+        write(&root, "primitives/src/lib.rs", "pub type Balance = u128;");
+
+        let output = root.join("out");
+        run(&root, OutputFormat::Json, Some(&output)).unwrap();
+        let summary: Value = serde_json::from_str(&fs::read_to_string(output.join("summary.JSON")).unwrap()).unwrap();
+        let pallets = summary["pallets"].as_array().unwrap();
+        assert_eq!(pallets.len(), 1);
+        let counts: Vec<u64> = ["dispatchables", "storage_items", "unsigned_entry_points", "panic_sites"]
+            .iter()
+            .map(|count| pallets[0][count].as_u64().unwrap())
+            .collect();
+        assert_eq!((pallets[0]["pallet"].as_str(), counts), (Some("pallet-items"), vec![2, 1, 1, 2]));
+        assert!(output.join("pallet-items.JSON").is_file());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn the_summary_cannot_be_written_as_sarif() {
+        let root = std::env::temp_dir().join(format!("asset-discovery-{}-bulk-sarif", std::process::id()));
+        let output = root.join("out");
+        assert!(matches!(run(&root, OutputFormat::Sarif, Some(&output)), Err(AppError::InvalidInput(_))));
+        assert!(!output.exists());
+    }
+}
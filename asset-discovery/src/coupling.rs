@@ -0,0 +1,234 @@
+use crate::{effects::RUNTIME_TYPES, has_pallet_constant, types, Asset, AssetCategory, AssetInventory, ParsedFile};
+use std::collections::{BTreeMap, BTreeSet};
+use syn::visit::Visit;
+
+// ----------------------------------------------Pallet Coupling----------------------------------------------------
+// Pallets depend on each other in two ways. Tight coupling names another pallet's Config as a supertrait,
+// `trait Config: pallet_balances::Config`, and calls into it directly. Loose coupling goes through an associated
+// type of the Config trait, `type Currency: ReservableCurrency<Self::AccountId>`, bound by the runtime. Both are
+// recorded with the operations the pallet invokes through them, the edges of the runtime's value flows.
+
+// Traits and trait modules that provide operations of another pallet
+const PROVIDER_TRAITS: [&str; 11] = [
+    "Currency",
+    "ReservableCurrency",
+    "NamedReservableCurrency",
+    "LockableCurrency",
+    "fungible",
+    "fungibles",
+    "nonfungible",
+    "nonfungibles",
+    "schedule",
+    "Randomness",
+    "EnsureOrigin",
+];
+// Supertraits every pallet has
+const SYSTEM_PALLETS: [&str; 2] = ["frame_system", "system"];
+
+/// Visitor collecting the supertraits and associated types of the pallet's Config trait
+#[derive(Default)]
+struct CouplingVisitor {
+    supertraits: Vec<String>,               // pallets whose Config is a supertrait, e.g. `pallet_balances`
+    config_types: BTreeMap<String, String>, // (associated type, bounds)
+    constants: BTreeSet<String>,            // associated types marked `#[pallet::constant]`
+}
+
+impl<'ast> Visit<'ast> for CouplingVisitor {
+    // `#[pallet::config] pub trait Config: frame_system::Config + pallet_balances::Config { .. }`
+    // FRAME v1 pallets call it `Trait`
+    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+        let is_config = node
+            .attrs
+            .iter()
+            .any(|attr| has_pallet_constant("pallet::config".to_string(), attr))
+            || node.ident == "Config"
+            || node.ident == "Trait";
+        if !is_config {
+            return syn::visit::visit_item_trait(self, node);
+        }
+
+        for bound in &node.supertraits {
+            let syn::TypeParamBound::Trait(bound) = bound else {
+                continue;
+            };
+            let segments: Vec<String> = bound.path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+            if segments.len() < 2 || SYSTEM_PALLETS.contains(&segments[0].as_str()) {
+                continue;
+            }
+            self.supertraits.push(segments[..segments.len() - 1].join("::"));
+        }
+
+        for item in &node.items {
+            let syn::TraitItem::Type(config_type) = item else {
+                continue;
+            };
+            let name = config_type.ident.to_string();
+            if config_type
+                .attrs
+                .iter()
+                .any(|attr| has_pallet_constant("pallet::constant".to_string(), attr))
+            {
+                self.constants.insert(name.clone());
+            }
+            let bounds = config_type
+                .bounds
+                .iter()
+                .filter_map(|bound| match bound {
+                    syn::TypeParamBound::Trait(bound) => Some(types::path_string(&bound.path)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" + ");
+            self.config_types.insert(name, bounds);
+        }
+        syn::visit::visit_item_trait(self, node);
+    }
+}
+
+/// Add a Dependency asset for every coupled pallet, with the operations invoked and the functions invoking them
+///
+/// Reads the `external_calls` attribute the pallet parser puts on functions, so it runs after the function assets
+/// were created.
+pub(crate) fn collect(files: &[ParsedFile], inventory: &mut AssetInventory) {
+    let mut visitor = CouplingVisitor::default();
+    for file in files {
+        visitor.visit_file(&file.syntax_tree);
+    }
+
+    // (dependency, (operations, functions))
+    let mut usage: BTreeMap<String, (BTreeSet<String>, BTreeSet<String>)> = BTreeMap::new();
+    for asset in &inventory.assets {
+        let Some(calls) = asset.attributes.get("external_calls") else {
+            continue;
+        };
+        for call in calls.split(',') {
+            let Some((dependency, operation)) = call.rsplit_once("::") else {
+                continue;
+            };
+            let entry = usage.entry(dependency.to_string()).or_default();
+            entry.0.insert(operation.to_string());
+            entry.1.insert(asset.name.clone());
+        }
+    }
+
+    let mut dependencies = Vec::new();
+    for pallet in &visitor.supertraits {
+        let mut asset = Asset::new("none", pallet, AssetCategory::Dependency(pallet.clone(), "tight".to_string()))
+            .with_attribute("supertrait", format!("{}::Config", pallet));
+        asset = with_usage(asset, usage.get(pallet));
+        dependencies.push(asset);
+    }
+
+    // Direct calls into a pallet that is not a supertrait still couple the two tightly
+    for (dependency, used) in &usage {
+        let is_pallet = dependency.starts_with("pallet_");
+        if is_pallet && !visitor.supertraits.contains(dependency) {
+            let category = AssetCategory::Dependency(dependency.clone(), "tight".to_string());
+            dependencies.push(with_usage(Asset::new("none", dependency, category), Some(used)));
+        }
+    }
+
+    for (config_type, bounds) in &visitor.config_types {
+        let is_runtime_type = RUNTIME_TYPES.contains(&config_type.as_str()) || visitor.constants.contains(config_type);
+        let provides_operations = PROVIDER_TRAITS
+            .iter()
+            .any(|provider| bounds.split(['<', ':', '+', ' ']).any(|segment| segment == *provider));
+        let used = usage.get(config_type);
+        if is_runtime_type || (!provides_operations && used.is_none()) {
+            continue;
+        }
+
+        let category = AssetCategory::Dependency(config_type.clone(), "loose".to_string());
+        let mut asset = Asset::new("none", config_type, category);
+        if !bounds.is_empty() {
+            asset = asset.with_attribute("bound", bounds.as_str());
+        }
+        dependencies.push(with_usage(asset, used));
+    }
+
+    inventory.assets.extend(dependencies);
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+fn with_usage(asset: Asset, used: Option<&(BTreeSet<String>, BTreeSet<String>)>) -> Asset {
+    let Some((operations, functions)) = used else {
+        return asset;
+    };
+    let join = |items: &BTreeSet<String>| items.iter().map(String::as_str).collect::<Vec<_>>().join(",");
+    asset
+        .with_attribute("operations", join(operations))
+        .with_attribute("used_by", join(functions))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{inventory_of, AssetCategory};
+
+    // Name, coupling and attributes of a dependency
+    type Dependency<'a> = (&'a str, &'a str, Vec<(&'a str, &'a str)>);
+
+    #[test]
+    fn tight_and_loose_coupling_is_recorded_with_its_usage() {
+        let inventory = inventory_of(
+            r#"
+            #[frame_support::pallet]
+            pub mod pallet {
+                #[pallet::config]
+                pub trait Config: frame_system::Config + pallet_timestamp::Config {
+                    type RuntimeEvent: From<Event<Self>>;
+                    type Currency: ReservableCurrency<Self::AccountId>;
+                    type Randomness: Randomness<Self::Hash, BlockNumberFor<Self>>;
+                    #[pallet::constant]
+                    type MaxItems: Get<u32>;
+                    type Limit: Get<u32>;
+                }
+
+                #[pallet::call]
+                impl<T: Config> Pallet<T> {
+                    pub fn store(origin: OriginFor<T>) -> DispatchResult {
+                        let who = ensure_signed(origin)?;
+                        <T::Currency as ReservableCurrency<_>>::reserve(&who, 10u32.into())?;
+                        pallet_timestamp::Pallet::<T>::now();
+                        pallet_assets::Pallet::<T>::mint_into(1, &who, 10)?;
+                        Ok(())
+                    }
+                }
+            }
+            "#,
+        );
+
+        let dependencies: Vec<Dependency> = inventory
+            .assets
+            .iter()
+            .filter_map(|asset| match &asset.category {
+                AssetCategory::Dependency(name, kind) => {
+                    let attributes = asset.attributes.iter().map(|(key, value)| (key.as_str(), value.as_str()));
+                    Some((name.as_str(), kind.as_str(), attributes.collect()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            dependencies,
+            [
+                (
+                    "pallet_timestamp",
+                    "tight",
+                    vec![("operations", "now"), ("supertrait", "pallet_timestamp::Config"), ("used_by", "store")]
+                ),
+                ("pallet_assets", "tight", vec![("operations", "mint_into"), ("used_by", "store")]),
+                (
+                    "Currency",
+                    "loose",
+                    vec![
+                        ("bound", "ReservableCurrency<Self::AccountId>"),
+                        ("operations", "reserve"),
+                        ("used_by", "store")
+                    ]
+                ),
+                ("Randomness", "loose", vec![("bound", "Randomness<Self::Hash, BlockNumberFor<Self>>")]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use crate::{runtime::pallet_aliases, AssetCategory, AssetInventory, Finding, ParsedFile};
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+use syn::visit::Visit;
+
+// ----------------------------------------------Test Coverage------------------------------------------------------
+// Security tests are the failing ones: every `Error` variant is a rejected path that should be pinned down by an
+// `assert_noop!` or `assert_err!`, and every event is a promise to off-chain consumers that should be checked with
+// `System::assert_last_event`. Tests in `tests.rs`, `tests/` and `#[cfg(test)]` modules are scanned token by
+// token, since almost everything they do happens inside assertion macros. The mock runtime's
+// `construct_runtime!` tells calls into the pallet apart from calls into `Balances` and friends.
+
+// Macros asserting that a call fails with a given error
+const ERROR_ASSERTIONS: [&str; 4] = ["assert_noop", "assert_err", "assert_err_ignore_postinfo", "assert_err_with_weight"];
+// Functions checking deposited events, directly or through a test helper
+const EVENT_ASSERTIONS: [&str; 5] = ["assert_last_event", "assert_has_event", "last_event", "expect_event", "events"];
+// Paths through which a crate refers to itself, the mock adds `use crate as pallet_x;`
+const SELF_PATHS: [&str; 3] = ["crate", "super", "self"];
+
+/// What the test functions exercise
+#[derive(Default)]
+struct TestCoverage {
+    calls: BTreeSet<(Option<String>, String)>, // (path head, function), e.g. (Some("Kitties"), "create")
+    errors: BTreeSet<String>,                  // `Error` variants asserted
+    events: BTreeSet<String>,                  // `Event` variants checked
+}
+
+/// Visitor collecting the coverage of every test and the pallets of the mock runtime
+#[derive(Default)]
+struct TestVisitor {
+    in_test_file: bool,                     // current file is `tests.rs` or below `tests/`
+    test_modules: usize,                    // nesting of `#[cfg(test)]` modules
+    tests: BTreeMap<String, TestCoverage>,  // (test function, coverage)
+    runtime_pallets: Vec<(String, String)>, // (runtime alias, pallet path) of the mock runtime
+    self_paths: BTreeSet<String>,           // names the crate goes by in its tests
+}
+
+impl<'ast> Visit<'ast> for TestVisitor {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        let is_test = node.attrs.iter().any(is_cfg_test);
+        self.test_modules += usize::from(is_test);
+        syn::visit::visit_item_mod(self, node);
+        self.test_modules -= usize::from(is_test);
+    }
+
+    // Test helpers count along with `#[test]` functions, they are often where the calls happen
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        let is_test = node
+            .attrs
+            .iter()
+            .any(|attr| attr.path().segments.last().is_some_and(|seg| seg.ident == "test"));
+        if is_test || self.in_test_file || self.test_modules > 0 {
+            let coverage = self.tests.entry(node.sig.ident.to_string()).or_default();
+            scan(node.block.to_token_stream(), coverage, false, false);
+        }
+        syn::visit::visit_item_fn(self, node);
+    }
+
+    fn visit_item_macro(&mut self, node: &'ast syn::ItemMacro) {
+        if node.mac.path.segments.last().is_some_and(|seg| seg.ident == "construct_runtime") {
+            self.runtime_pallets.extend(pallet_aliases(node.mac.tokens.clone()));
+        }
+        syn::visit::visit_item_macro(self, node);
+    }
+
+    // `use crate as pallet_kitties;`
+    fn visit_use_rename(&mut self, node: &'ast syn::UseRename) {
+        if node.ident == "crate" {
+            self.self_paths.insert(node.rename.to_string());
+        }
+        syn::visit::visit_use_rename(self, node);
+    }
+}
+
+/// Record which dispatchables are called, which errors asserted and which events checked by the tests, and add
+/// findings for dispatchables, errors and events no test covers
+///
+/// Sources without any test are assumed to keep them elsewhere and are left alone.
+pub(crate) fn collect(files: &[ParsedFile], inventory: &mut AssetInventory) {
+    let mut visitor = TestVisitor::default();
+    for file in files {
+        visitor.in_test_file = is_test_file(&file.path);
+        visitor.visit_file(&file.syntax_tree);
+    }
+    if visitor.tests.is_empty() {
+        return;
+    }
+
+    // Runtime aliases of every pallet in the mock but the one under test
+    let foreign: BTreeSet<&str> = visitor
+        .runtime_pallets
+        .iter()
+        .filter(|(_, path)| {
+            let head = path.split("::").next().unwrap_or_default();
+            !SELF_PATHS.contains(&head) && !visitor.self_paths.contains(head)
+        })
+        .map(|(alias, _)| alias.as_str())
+        .collect();
+
+    let mut called: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut asserted: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut checked: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for (test, coverage) in &visitor.tests {
+        for (head, function) in &coverage.calls {
+            if !head.as_deref().is_some_and(|head| foreign.contains(head)) {
+                called.entry(function).or_default().insert(test);
+            }
+        }
+        for error in &coverage.errors {
+            asserted.entry(error).or_default().insert(test);
+        }
+        for event in &coverage.events {
+            checked.entry(event).or_default().insert(test);
+        }
+    }
+
+    let join = |tests: &BTreeSet<&str>| tests.iter().copied().collect::<Vec<_>>().join(",");
+    for asset in &mut inventory.assets {
+        let (tests, attribute) = match asset.category {
+            AssetCategory::PublicFunction(..) => (called.get(asset.name.as_str()), "tested_by"),
+            AssetCategory::Error(..) => (asserted.get(asset.name.as_str()), "asserted_by"),
+            AssetCategory::Events(..) => (checked.get(asset.name.as_str()), "checked_by"),
+            _ => continue,
+        };
+        if let Some(tests) = tests {
+            asset.attributes.insert(attribute.to_string(), join(tests));
+            continue;
+        }
+
+        let finding = match asset.category {
+            // Public functions outside the call enum are not reachable by users
+            AssetCategory::PublicFunction(..) if asset.attributes.get("dispatchable").is_some_and(|v| v == "yes") => (
+                "untested-dispatchable",
+                format!("dispatchable `{}` is never called by a test", asset.name),
+            ),
+            AssetCategory::Error(..) => (
+                "untested-error",
+                format!(
+                    "`Error::{}` is never asserted with `assert_noop!` or `assert_err!`, the rejected path has no test",
+                    asset.name
+                ),
+            ),
+            AssetCategory::Events(..) => (
+                "unchecked-event",
+                format!("`Event::{}` is never checked with `System::assert_last_event` or `assert_has_event`", asset.name),
+            ),
+            _ => continue,
+        };
+        inventory.findings.push(Finding {
+            rule: finding.0.to_string(),
+            asset: asset.name.clone(),
+            message: finding.1,
+            file: None,
+            line: None,
+        });
+    }
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+// Walk the tokens of a test, `error` and `event` tell whether they sit inside an error or event assertion
+fn scan(tokens: TokenStream, coverage: &mut TestCoverage, error: bool, event: bool) {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            // `assert_noop!(..)`, `System::assert_last_event(..)` or `assert_eq!(last_event(), ..)`
+            TokenTree::Group(group) => {
+                let is_macro = i >= 2 && matches!(&tokens[i - 1], TokenTree::Punct(p) if p.as_char() == '!');
+                let callee = match (is_macro, i.checked_sub(1).map(|j| &tokens[j])) {
+                    (true, _) => ident_at(&tokens, i - 2),
+                    (false, Some(TokenTree::Ident(ident))) => Some(ident.to_string()),
+                    _ => None,
+                };
+                let callee = callee.as_deref().unwrap_or_default();
+                let inner_error = error || is_macro && ERROR_ASSERTIONS.contains(&callee);
+                let inner_event = event
+                    || EVENT_ASSERTIONS.contains(&callee)
+                    || is_macro
+                        && group.stream().into_iter().any(|tt| {
+                            matches!(&tt, TokenTree::Ident(ident) if EVENT_ASSERTIONS.iter().any(|name| ident == name))
+                        });
+                scan(group.stream(), coverage, inner_error, inner_event);
+            }
+            // `Kitties::create(..)`, `Pallet::<Test>::create(..)` or `Call::create { .. }`
+            TokenTree::Ident(ident) => {
+                let is_path_end = i >= 2 && is_path_separator(&tokens, i - 2);
+                if is_path_end && matches!(tokens.get(i + 1), Some(TokenTree::Group(_))) {
+                    let head = i.checked_sub(3).and_then(|j| ident_at(&tokens, j));
+                    coverage.calls.insert((head, ident.to_string()));
+                }
+                if (error && ident == "Error") || (event && ident == "Event") {
+                    let variants = if ident == "Error" { &mut coverage.errors } else { &mut coverage.events };
+                    variants.extend(variant_after(&tokens, i));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// `Error::<Test>::NotOwner` or `Event::Created { .. }` to the variant name
+fn variant_after(tokens: &[TokenTree], position: usize) -> Option<String> {
+    let mut i = position + 1;
+    if !is_path_separator(tokens, i) {
+        return None;
+    }
+    i += 2;
+    if matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == '<') {
+        let mut depth = 0;
+        while let Some(token) = tokens.get(i) {
+            match token {
+                TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+                TokenTree::Punct(p) if p.as_char() == '>' => depth -= 1,
+                _ => {}
+            }
+            i += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+        if !is_path_separator(tokens, i) {
+            return None;
+        }
+        i += 2;
+    }
+    ident_at(tokens, i)
+}
+
+fn is_path_separator(tokens: &[TokenTree], i: usize) -> bool {
+    matches!(
+        (tokens.get(i), tokens.get(i + 1)),
+        (Some(TokenTree::Punct(first)), Some(TokenTree::Punct(second)))
+            if first.as_char() == ':' && first.spacing() == Spacing::Joint && second.as_char() == ':'
+    )
+}
+
+fn ident_at(tokens: &[TokenTree], i: usize) -> Option<String> {
+    match tokens.get(i) {
+        Some(TokenTree::Ident(ident)) => Some(ident.to_string()),
+        _ => None,
+    }
+}
+
+fn is_test_file(path: &str) -> bool {
+    Path::new(path).file_name().is_some_and(|name| name == "tests.rs") || crate::bulk::in_tests_dir(path)
+}
+
+// `#[cfg(test)]`
+fn is_cfg_test(attr: &syn::Attribute) -> bool {
+    attr.path().is_ident("cfg") && attr.meta.require_list().is_ok_and(|list| list.tokens.to_string() == "test")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inventory_of_files;
+
+    const PALLET: &str = r#"
+        #[frame_support::pallet]
+        pub mod pallet {
+            #[pallet::event]
+            pub enum Event<T: Config> {
+                Created { id: u32 },
+                Destroyed { id: u32 },
+            }
+
+            #[pallet::error]
+            pub enum Error<T> {
+                NotOwner,
+                Overflow,
+            }
+
+            #[pallet::call]
+            impl<T: Config> Pallet<T> {
+                pub fn create(origin: OriginFor<T>) -> DispatchResult {
+                    ensure_signed(origin)?;
+                    Ok(())
+                }
+
+                pub fn destroy(origin: OriginFor<T>) -> DispatchResult {
+                    ensure_signed(origin)?;
+                    Ok(())
+                }
+            }
+
+            impl<T: Config> Pallet<T> {
+                pub fn next_id() -> u32 {
+                    0
+                }
+            }
+        }
+    "#;
+
+    const MOCK: &str = r#"
+        use crate as pallet_kitties;
+        construct_runtime!(
+            pub enum Test {
+                System: frame_system,
+                Balances: pallet_balances,
+                Kitties: pallet_kitties,
+            }
+        );
+    "#;
+
+    #[test]
+    fn uncovered_dispatchables_errors_and_events_are_reported() {
+        let tests = r#"
+            #[test]
+            fn create_works() {
+                new_test_ext().execute_with(|| {
+                    assert_ok!(Kitties::create(RuntimeOrigin::signed(1)));
+                    System::assert_last_event(Event::Created { id: 0 }.into());
+                    assert_noop!(Kitties::create(RuntimeOrigin::signed(2)), Error::<Test>::NotOwner);
+                    // A call into another pallet of the mock does not cover the pallet's own `destroy`
+                    assert_ok!(Balances::destroy(RuntimeOrigin::signed(1)));
+                });
+            }
+        "#;
+        let inventory = inventory_of_files(&[("src/lib.rs", PALLET), ("src/mock.rs", MOCK), ("src/tests.rs", tests)]);
+
+        let findings: Vec<(&str, &str)> = inventory
+            .findings
+            .iter()
+            .map(|finding| (finding.rule.as_str(), finding.asset.as_str()))
+            .collect();
+        assert_eq!(
+            findings,
+            [
+                ("untested-dispatchable", "destroy"),
+                ("unchecked-event", "Destroyed"),
+                ("untested-error", "Overflow"),
+            ]
+        );
+        let attribute = |name: &str, key: &str| {
+            let asset = inventory.assets.iter().find(|asset| asset.name == name).unwrap();
+            asset.attributes.get(key).cloned()
+        };
+        assert_eq!(attribute("create", "tested_by").as_deref(), Some("create_works"));
+        assert_eq!(attribute("NotOwner", "asserted_by").as_deref(), Some("create_works"));
+        assert_eq!(attribute("Created", "checked_by").as_deref(), Some("create_works"));
+    }
+
+    #[test]
+    fn pallets_without_tests_are_left_alone() {
+        let inventory = inventory_of_files(&[("src/lib.rs", PALLET)]);
+        let coverage_rules = ["untested-dispatchable", "untested-error", "unchecked-event"];
+        assert!(inventory.findings.iter().all(|finding| !coverage_rules.contains(&finding.rule.as_str())));
+    }
+}
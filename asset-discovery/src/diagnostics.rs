@@ -0,0 +1,197 @@
+use proc_macro2::{Delimiter, TokenTree};
+use syn::{
+    parse::{discouraged::Speculative, ParseStream, Parser},
+    Attribute, File, Item,
+};
+
+// ----------------------------------------------Parse Diagnostics--------------------------------------------------
+// A single macro-heavy item should not kill a crate-wide scan. Files are parsed item by item: an item that
+// fails to parse is skipped and reported, modules are re-entered so one broken item inside `mod pallet`
+// only loses that item. Problems are rendered with file, line, column and the offending source line.
+
+/// Region of a source file that could not be parsed and was skipped
+#[derive(Debug, Clone)]
+pub(crate) struct ParseIssue {
+    pub(crate) message: String,
+    // 1-based line and 0-based column where the skipped region starts, as reported by proc-macro2
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    // Last line of the skipped region
+    pub(crate) end_line: usize,
+}
+
+/// Parse a file, recovering from errors at item granularity
+pub(crate) fn parse_lenient(code: &str) -> (File, Vec<ParseIssue>) {
+    let mut issues = Vec::new();
+    let parser = |input: ParseStream| {
+        let attrs = input.call(Attribute::parse_inner)?;
+        let items = parse_items(input, &mut issues)?;
+        Ok(File {
+            shebang: None,
+            attrs,
+            items,
+        })
+    };
+
+    match parser.parse_str(code) {
+        Ok(file) => (file, issues),
+        // Lexer errors such as unbalanced delimiters leave nothing to recover, skip the whole file
+        Err(err) => {
+            let start = err.span().start();
+            let issue = ParseIssue {
+                message: format!("{}, the whole file was skipped", err),
+                line: start.line,
+                column: start.column,
+                end_line: code.lines().count().max(start.line),
+            };
+            let empty = File {
+                shebang: None,
+                attrs: Vec::new(),
+                items: Vec::new(),
+            };
+            (empty, vec![issue])
+        }
+    }
+}
+
+/// Render an issue in the style of rustc and codespan-reporting
+///
+/// ```text
+/// warning: expected `;`
+///   --> pallets/template/src/lib.rs:12:5
+///    |
+/// 12 |     let x = foo!(
+///    |     ^ region skipped until line 14
+/// ```
+pub(crate) fn render(file: &str, code: &str, issue: &ParseIssue) -> String {
+    let line_number = issue.line.to_string();
+    let gutter = " ".repeat(line_number.len());
+    let source_line = code.lines().nth(issue.line.saturating_sub(1)).unwrap_or("");
+    // Columns count characters, keep tabs so the caret lines up with the source line
+    let padding: String = source_line
+        .chars()
+        .take(issue.column)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let note = if issue.end_line > issue.line {
+        format!(" region skipped until line {}", issue.end_line)
+    } else {
+        " region skipped".to_string()
+    };
+
+    format!(
+        "warning: {}\n{gutter}--> {}:{}:{}\n{gutter} |\n{} | {}\n{gutter} | {}^{}\n",
+        issue.message,
+        file,
+        issue.line,
+        issue.column + 1,
+        line_number,
+        source_line,
+        padding,
+        note,
+        gutter = gutter,
+    )
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+fn parse_items(input: ParseStream, issues: &mut Vec<ParseIssue>) -> syn::Result<Vec<Item>> {
+    let mut items = Vec::new();
+    while !input.is_empty() {
+        let fork = input.fork();
+        match fork.parse::<Item>() {
+            Ok(item) => {
+                input.advance_to(&fork);
+                items.push(item);
+            }
+            Err(err) => {
+                if let Some(module) = recover_module(input, issues) {
+                    items.push(Item::Mod(module));
+                    continue;
+                }
+                issues.push(skip_item(input, err)?);
+            }
+        }
+    }
+    Ok(items)
+}
+
+// `mod pallet { .. }` with a broken item inside: keep the module and every item that does parse
+fn recover_module(input: ParseStream, issues: &mut Vec<ParseIssue>) -> Option<syn::ItemMod> {
+    let fork = input.fork();
+    let mut nested_issues = Vec::new();
+    let module = (|| -> syn::Result<syn::ItemMod> {
+        let attrs = fork.call(Attribute::parse_outer)?;
+        let vis = fork.parse()?;
+        let mod_token = fork.parse()?;
+        let ident = fork.parse()?;
+        let content;
+        let brace = syn::braced!(content in fork);
+        let inner_attrs = content.call(Attribute::parse_inner)?;
+        let items = parse_items(&content, &mut nested_issues)?;
+        Ok(syn::ItemMod {
+            attrs: attrs.into_iter().chain(inner_attrs).collect(),
+            vis,
+            unsafety: None,
+            mod_token,
+            ident,
+            content: Some((brace, items)),
+            semi: None,
+        })
+    })()
+    .ok()?;
+
+    input.advance_to(&fork);
+    issues.extend(nested_issues);
+    Some(module)
+}
+
+// Skip token trees up to the end of the broken item: a `;` or a brace-delimited body
+fn skip_item(input: ParseStream, err: syn::Error) -> syn::Result<ParseIssue> {
+    // The error can point anywhere inside the item, the region starts at its first token
+    let start = input.cursor().span().start();
+    let end_line = input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut end_line = cursor.span().start().line;
+        while let Some((token, next)) = rest.token_tree() {
+            end_line = token.span().end().line;
+            rest = next;
+            match &token {
+                TokenTree::Punct(punct) if punct.as_char() == ';' => break,
+                TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                    // `const X: S = S { .. };` ends after the brace
+                    if let Some((TokenTree::Punct(punct), next)) = rest.token_tree() {
+                        if punct.as_char() == ';' {
+                            rest = next;
+                        }
+                    }
+                    break;
+                }
+                _ => {}
+            }
+        }
+        Ok((end_line, rest))
+    })?;
+
+    Ok(ParseIssue {
+        message: err.to_string(),
+        line: start.line,
+        column: start.column,
+        end_line,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skipped_item_starts_at_its_first_token() {
+        let code = "fn before() {}\n\nstruct Broken {\n    a: u32,\n    b: ,\n}\n\nfn after() {}\n";
+        let (file, issues) = parse_lenient(code);
+
+        assert_eq!(file.items.len(), 2);
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].line, issues[0].column, issues[0].end_line), (3, 0, 6));
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeSet;
+use syn::visit::Visit;
+
+// ----------------------------------------------Function Effects---------------------------------------------------
+// Who may call a function and which storage it writes are the first questions of every dispatchable review.
+// The origin is taken from the first `ensure_*` check in the body, writes from calls of mutating storage methods
+// such as `Something::<T>::put(..)`. Calls leaving the pallet, through a Config type like `T::Currency` or
+// directly into another pallet, are the edges between pallets. Transfers passing `AllowDeath` are recorded on
+// their own, they can reap the sender.
+
+// Storage methods that change state
+const WRITE_METHODS: [&str; 17] = [
+    "put",
+    "set",
+    "insert",
+    "mutate",
+    "try_mutate",
+    "mutate_exists",
+    "try_mutate_exists",
+    "remove",
+    "take",
+    "kill",
+    "append",
+    "swap",
+    "translate",
+    "clear",
+    "clear_prefix",
+    "remove_all",
+    "remove_prefix",
+];
+
+// Arguments letting a transfer or withdrawal drop the account below the existential deposit
+const REAPING_ARGUMENTS: [&str; 2] = ["AllowDeath", "Expendable"];
+
+// Config types every pallet has, calls through them do not couple the pallet to another one
+pub(crate) const RUNTIME_TYPES: [&str; 8] = [
+    "RuntimeEvent",
+    "RuntimeCall",
+    "RuntimeOrigin",
+    "RuntimeHoldReason",
+    "RuntimeFreezeReason",
+    "RuntimeTask",
+    "PalletInfo",
+    "WeightInfo",
+];
+
+/// Origin check, storage writes and external calls of a function body
+#[derive(Default)]
+pub(crate) struct Effects {
+    // "signed", "root", "none", "signed_or_root" or "custom" for `T::SomeOrigin::ensure_origin(..)`
+    pub(crate) origin: Option<&'static str>,
+    // Names of the storage items written, to be matched against the declared storage items
+    pub(crate) writes: BTreeSet<String>,
+    // Operations of other pallets, `Currency::transfer` through `T::Currency` or `pallet_balances::transfer`
+    pub(crate) external_calls: BTreeSet<String>,
+    // Transfers and withdrawals that may reap the account they take from
+    pub(crate) allow_death: BTreeSet<String>,
+}
+
+impl<'ast> Visit<'ast> for Effects {
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(path) = &*node.func {
+            let segments: Vec<String> = path.path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+            if let Some(method) = segments.last() {
+                let origin = match method.as_str() {
+                    "ensure_signed" => Some("signed"),
+                    "ensure_root" => Some("root"),
+                    "ensure_none" => Some("none"),
+                    "ensure_signed_or_root" => Some("signed_or_root"),
+                    "ensure_origin" | "ensure_origin_or_root" => Some("custom"),
+                    _ => None,
+                };
+                // The first check decides, later ones usually guard a different branch
+                if self.origin.is_none() {
+                    self.origin = origin;
+                }
+
+                if WRITE_METHODS.contains(&method.as_str()) {
+                    // `Something::<T>::put(..)` or `<Something<T>>::put(..)`
+                    let storage = match &path.qself {
+                        Some(qself) => match &*qself.ty {
+                            syn::Type::Path(ty) => ty.path.segments.last().map(|seg| seg.ident.to_string()),
+                            _ => None,
+                        },
+                        None => segments.len().checked_sub(2).map(|i| segments[i].clone()),
+                    };
+                    self.writes.extend(storage);
+                }
+            }
+            let external = external_call(path);
+            // `ExistenceRequirement::AllowDeath`, `Preservation::Expendable` or `transfer_allow_death(..)`
+            let reaping_argument = node.args.iter().any(|arg| match arg {
+                syn::Expr::Path(arg) => arg
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|seg| REAPING_ARGUMENTS.iter().any(|name| seg.ident == name)),
+                _ => false,
+            });
+            let method = path.path.segments.last().map(|seg| seg.ident.to_string()).unwrap_or_default();
+            if reaping_argument || method == "transfer_allow_death" {
+                self.allow_death.insert(external.clone().unwrap_or(method));
+            }
+            self.external_calls.extend(external);
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+}
+
+// `T::Currency::transfer(..)`, `<T as Config>::Currency::reserve(..)`, `<T::Currency as Currency<_>>::transfer(..)`
+// or `pallet_balances::Pallet::<T>::slash(..)`
+fn external_call(path: &syn::ExprPath) -> Option<String> {
+    let method = path.path.segments.last()?.ident.to_string();
+    // `T::MaxItems::get()` reads a constant
+    if method == "get" {
+        return None;
+    }
+
+    // The qualified self type takes the place of the trait it is cast to: `<T::Currency as Currency<_>>::transfer`
+    // is called like `T::Currency::transfer`
+    let idents = |segments: &mut dyn Iterator<Item = &syn::PathSegment>| -> Vec<String> {
+        segments.map(|seg| seg.ident.to_string()).collect()
+    };
+    let segments = match &path.qself {
+        Some(qself) => {
+            let syn::Type::Path(ty) = &*qself.ty else {
+                return None;
+            };
+            let mut segments = idents(&mut ty.path.segments.iter());
+            segments.extend(idents(&mut path.path.segments.iter().skip(qself.position)));
+            segments
+        }
+        None => idents(&mut path.path.segments.iter()),
+    };
+
+    match segments.first().map(String::as_str) {
+        // Loose coupling through an associated type of the Config trait
+        Some("T") if segments.len() >= 3 => {
+            let config_type = &segments[1];
+            (!RUNTIME_TYPES.contains(&config_type.as_str())).then(|| format!("{}::{}", config_type, method))
+        }
+        // Tight coupling straight into another pallet
+        Some(krate) if krate.starts_with("pallet_") && segments.len() >= 2 => Some(format!("{}::{}", krate, method)),
+        _ => None,
+    }
+}
+
+/// Analyse the body of a function
+pub(crate) fn analyse(block: &syn::Block) -> Effects {
+    let mut effects = Effects::default();
+    effects.visit_block(block);
+    effects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effects_of(body: &str) -> Effects {
+        analyse(&syn::parse_str::<syn::Block>(body).expect("body parses"))
+    }
+
+    #[test]
+    fn only_transfers_allowing_death_are_reaping() {
+        let effects = effects_of(
+            r#"{
+                T::Currency::transfer(&who, &to, amount, ExistenceRequirement::KeepAlive)?;
+                T::Currency::withdraw(&who, fee, WithdrawReasons::FEE, AllowDeath)?;
+                T::Assets::transfer(id, &who, &to, amount, Preservation::Expendable)?;
+                pallet_balances::Pallet::<T>::transfer_allow_death(origin, dest, value)?;
+            }"#,
+        );
+        let allow_death: Vec<&str> = effects.allow_death.iter().map(String::as_str).collect();
+        assert_eq!(
+            allow_death,
+            ["Assets::transfer", "Currency::withdraw", "pallet_balances::transfer_allow_death"]
+        );
+        assert!(effects.external_calls.contains("Currency::transfer"));
+    }
+
+    #[test]
+    fn external_calls_are_found_through_every_path_form() {
+        let effects = effects_of(
+            r#"{
+                T::Currency::reserve(&who, deposit)?;
+                <T as Config>::Currency::unreserve(&who, deposit);
+                <T::Currency as Currency<T::AccountId>>::transfer(&who, &to, amount, KeepAlive)?;
+                <pallet_balances::Pallet<T> as Currency<_>>::slash(&who, amount);
+                T::WeightInfo::store();
+                T::MaxItems::get();
+                Items::<T>::insert(&who, items);
+            }"#,
+        );
+        let calls: Vec<&str> = effects.external_calls.iter().map(String::as_str).collect();
+        assert_eq!(
+            calls,
+            ["Currency::reserve", "Currency::transfer", "Currency::unreserve", "pallet_balances::slash"]
+        );
+        assert_eq!(effects.writes.iter().collect::<Vec<_>>(), ["Items"]);
+    }
+
+    #[test]
+    fn the_first_origin_check_decides() {
+        assert_eq!(effects_of("{ ensure_root(origin.clone())?; ensure_signed(origin)?; }").origin, Some("root"));
+        assert_eq!(effects_of("{ T::AdminOrigin::ensure_origin(origin)?; }").origin, Some("custom"));
+        assert_eq!(effects_of("{ Self::helper(); }").origin, None);
+    }
+}
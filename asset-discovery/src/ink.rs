@@ -0,0 +1,412 @@
+use crate::{
+    bulk::is_test_file,
+    split_tokens,
+    types::{type_string, TypeAliases},
+    AppError, Asset, AssetCategory, AssetInventory, ParsedFile,
+};
+use proc_macro2::TokenTree;
+use quote::quote;
+use syn::visit::Visit;
+
+// ----------------------------------------------ink! Contracts-----------------------------------------------------
+// ink! contracts declare their surface with `#[ink(..)]` attributes inside a `#[ink::contract]` module. The
+// contract storage, messages, constructors and events are mapped onto the pallet categories, so the same
+// threat-modeling pipeline can consume the inventory of a contract.
+
+/// Message or constructor of a contract
+struct EntryPoint {
+    name: String,
+    params: Vec<(String, String)>,
+    // "message" or "constructor"
+    kind: &'static str,
+    payable: bool,
+    // `selector = 0xCAFEBABE` or the wildcard `selector = _`
+    selector: Option<String>,
+    // Messages taking `&mut self` can change contract storage
+    mutates: bool,
+    // The body reads `self.env().caller()`, which is how contracts check who called them
+    reads_caller: bool,
+}
+
+/// Visitor collecting the items of `#[ink::contract]` modules
+#[derive(Default)]
+struct InkVisitor {
+    contracts: Vec<String>,                 // contract module names
+    in_contract: bool,                      // inside a `#[ink::contract]` module
+    storage: Vec<(String, String, String)>, // (field name, visibility, field type)
+    entry_points: Vec<EntryPoint>,          // messages and constructors
+    events: Vec<(String, Vec<String>)>,     // (event name, topic fields)
+    errors: Vec<String>,                    // variants of the contract `Error` enum
+}
+
+impl<'ast> Visit<'ast> for InkVisitor {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        let is_contract = node.attrs.iter().any(|attr| path_is(attr, &["ink", "contract"]));
+        if is_contract {
+            self.contracts.push(node.ident.to_string());
+        }
+        let outer = self.in_contract;
+        self.in_contract |= is_contract;
+        syn::visit::visit_item_mod(self, node);
+        self.in_contract = outer;
+    }
+
+    // `#[ink(storage)]` holds the contract state, `#[ink(event)]` and `#[ink::event]` declare events
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        let args: Vec<InkArg> = node.attrs.iter().flat_map(ink_args).collect();
+        if has_flag(&args, "storage") && self.in_contract {
+            for field in &node.fields {
+                let Some(ident) = &field.ident else {
+                    continue;
+                };
+                let visibility = match field.vis {
+                    syn::Visibility::Public(_) => "public",
+                    _ => "private",
+                };
+                let ty = &field.ty;
+                self.storage
+                    .push((ident.to_string(), visibility.to_string(), type_string(ty)));
+            }
+        }
+
+        // Since ink! 5 `#[ink::event]` events may be declared outside of the contract module
+        let is_event = has_flag(&args, "event") && self.in_contract
+            || node.attrs.iter().any(|attr| path_is(attr, &["ink", "event"]));
+        if is_event {
+            let topics = node
+                .fields
+                .iter()
+                .filter(|field| has_flag(&field.attrs.iter().flat_map(ink_args).collect::<Vec<_>>(), "topic"))
+                .filter_map(|field| field.ident.as_ref().map(|ident| ident.to_string()))
+                .collect();
+            self.events.push((node.ident.to_string(), topics));
+        }
+        syn::visit::visit_item_struct(self, node);
+    }
+
+    // `#[ink(message)]` and `#[ink(constructor)]`, in inherent and trait impls alike
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        // Flags may be spread over several attributes: `#[ink(message)] #[ink(payable)]`
+        let args: Vec<InkArg> = node.attrs.iter().flat_map(ink_args).collect();
+        let kind = if !self.in_contract {
+            return syn::visit::visit_impl_item_fn(self, node);
+        } else if has_flag(&args, "message") {
+            "message"
+        } else if has_flag(&args, "constructor") {
+            "constructor"
+        } else {
+            return syn::visit::visit_impl_item_fn(self, node);
+        };
+
+        let mut params = Vec::new();
+        let mut mutates = false;
+        for param in &node.sig.inputs {
+            match param {
+                syn::FnArg::Receiver(receiver) => mutates = receiver.mutability.is_some(),
+                syn::FnArg::Typed(pat_type) => {
+                    if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
+                        let ty = &pat_type.ty;
+                        params.push((pat_ident.ident.to_string(), quote!(#ty).to_string()));
+                    }
+                }
+            }
+        }
+
+        self.entry_points.push(EntryPoint {
+            name: node.sig.ident.to_string(),
+            params,
+            kind,
+            payable: has_flag(&args, "payable"),
+            selector: args.iter().find_map(|arg| match arg {
+                InkArg::Value(key, value) if key == "selector" => Some(value.clone()),
+                _ => None,
+            }),
+            mutates,
+            reads_caller: reads_caller(&node.block),
+        });
+        syn::visit::visit_impl_item_fn(self, node);
+    }
+
+    // Contracts report failures through a plain `Error` enum returned by their messages
+    fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
+        if node.ident == "Error" && self.in_contract {
+            self.errors.extend(node.variants.iter().map(|variant| variant.ident.to_string()));
+        }
+        syn::visit::visit_item_enum(self, node);
+    }
+}
+
+/// Parse ink! contract source code into an asset inventory
+pub(crate) fn parser(files: &[ParsedFile]) -> Result<AssetInventory, AppError> {
+    // End-to-end tests in `tests/` drive the contract and are not part of it
+    let mut visitor = InkVisitor::default();
+    for file in files.iter().filter(|file| !is_test_file(&file.path)) {
+        visitor.visit_file(&file.syntax_tree);
+    }
+
+    if visitor.contracts.is_empty() {
+        return Err(AppError::InvalidInput("No `#[ink::contract]` module found".to_string()));
+    }
+
+    let mut asset_inventory = AssetInventory {
+        source: None,
+        assets: Vec::new(),
+        findings: Vec::new(),
+        unparsed: files.iter().flat_map(|file| file.unparsed.iter().cloned()).collect(),
+    };
+
+    for (field, visibility, ty) in visitor.storage {
+        let category = AssetCategory::Storage(field.clone(), visibility.clone());
+        asset_inventory
+            .assets
+            .push(Asset::new(&visibility, &field, category).with_attribute("type", ty));
+    }
+
+    // Every message and constructor can be called from outside the contract. They are no FRAME calls: there is no
+    // origin to check, a contract checks `self.env().caller()` instead
+    let aliases = TypeAliases::collect(files);
+    for entry_point in visitor.entry_points {
+        let params = entry_point
+            .params
+            .iter()
+            .map(|(name, ty)| aliases.parameter(name, ty))
+            .collect();
+        let category = AssetCategory::PublicFunction(entry_point.name.clone(), params);
+        let mut asset = Asset::new("public", &entry_point.name, category)
+            .with_attribute("ink", entry_point.kind)
+            .with_attribute("payable", entry_point.payable.to_string())
+            .with_attribute("reads_caller", entry_point.reads_caller.to_string());
+        if entry_point.kind == "message" {
+            asset = asset.with_attribute("mutates", entry_point.mutates.to_string());
+        }
+        if let Some(selector) = entry_point.selector {
+            asset = asset.with_attribute("selector", selector);
+        }
+        asset_inventory.assets.push(asset);
+    }
+
+    for (event, topics) in visitor.events {
+        let mut asset = Asset::new("public", &event, AssetCategory::Events(event.clone()));
+        if !topics.is_empty() {
+            asset = asset.with_attribute("topics", topics.join(","));
+        }
+        asset_inventory.assets.push(asset);
+    }
+
+    for error in visitor.errors {
+        let category = AssetCategory::Error(error.clone());
+        asset_inventory.assets.push(Asset::new("public", &error, category));
+    }
+
+    Ok(asset_inventory)
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+/// Argument of an `#[ink(..)]` attribute
+enum InkArg {
+    // `message`, `payable`, `topic`
+    Flag(String),
+    // `selector = 0xCAFEBABE`
+    Value(String, String),
+}
+
+// Arguments of an `#[ink(..)]` attribute, empty for any other attribute
+fn ink_args(attr: &syn::Attribute) -> Vec<InkArg> {
+    if !attr.path().is_ident("ink") {
+        return Vec::new();
+    }
+    let Ok(list) = attr.meta.require_list() else {
+        return Vec::new();
+    };
+
+    split_tokens(list.tokens.clone(), ',')
+        .into_iter()
+        .filter_map(|arg| {
+            let key = match arg.first()? {
+                TokenTree::Ident(ident) => ident.to_string(),
+                _ => return None,
+            };
+            match arg.get(1) {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {
+                    let value = arg[2..].iter().map(ToString::to_string).collect::<String>();
+                    Some(InkArg::Value(key, value))
+                }
+                _ => Some(InkArg::Flag(key)),
+            }
+        })
+        .collect()
+}
+
+// `self.env().caller()` or `Self::env().caller()` anywhere in the body
+fn reads_caller(block: &syn::Block) -> bool {
+    struct CallerVisitor(bool);
+    impl<'ast> Visit<'ast> for CallerVisitor {
+        fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+            let on_env = match &*node.receiver {
+                syn::Expr::MethodCall(receiver) => receiver.method == "env",
+                syn::Expr::Call(receiver) => match &*receiver.func {
+                    syn::Expr::Path(path) => path.path.segments.last().is_some_and(|seg| seg.ident == "env"),
+                    _ => false,
+                },
+                _ => false,
+            };
+            self.0 |= node.method == "caller" && on_env;
+            syn::visit::visit_expr_method_call(self, node);
+        }
+    }
+
+    let mut visitor = CallerVisitor(false);
+    visitor.visit_block(block);
+    visitor.0
+}
+
+fn has_flag(args: &[InkArg], flag: &str) -> bool {
+    args.iter().any(|arg| matches!(arg, InkArg::Flag(name) if name == flag))
+}
+
+// `#[ink::contract]` may carry arguments such as `#[ink::contract(env = MyEnvironment)]`
+fn path_is(attr: &syn::Attribute, segments: &[&str]) -> bool {
+    let path = attr.path();
+    path.segments.len() == segments.len() && path.segments.iter().zip(segments).all(|(seg, name)| seg.ident == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_source, SourceFile};
+    use std::path::PathBuf;
+
+    const FLIPPER: &str = r#"
+        #[ink::contract]
+        mod flipper {
+            #[ink(storage)]
+            pub struct Flipper {
+                value: bool,
+                owner: AccountId,
+            }
+
+            #[ink(event)]
+            pub struct Flipped {
+                #[ink(topic)]
+                by: AccountId,
+            }
+
+            pub enum Error {
+                NotOwner,
+            }
+
+            impl Flipper {
+                #[ink(constructor)]
+                pub fn new(value: bool) -> Self {
+                    Self { value, owner: Self::env().caller() }
+                }
+
+                #[ink(message)]
+                pub fn get(&self) -> bool {
+                    self.value
+                }
+
+                #[ink(message, payable, selector = 0xCAFEBABE)]
+                pub fn flip(&mut self) -> Result<(), Error> {
+                    if self.env().caller() != self.owner {
+                        return Err(Error::NotOwner);
+                    }
+                    self.value = !self.value;
+                    Ok(())
+                }
+            }
+        }
+
+        // Helpers next to the contract are not part of it
+        pub struct Config {
+            value: u32,
+        }
+
+        pub enum Error {
+            Io,
+        }
+
+        impl Helper {
+            #[ink(message)]
+            pub fn not_a_message(&self) {}
+        }
+    "#;
+
+    fn inventory_of(files: &[(&str, &str)]) -> AssetInventory {
+        let files: Vec<ParsedFile> = files
+            .iter()
+            .map(|(path, code)| {
+                parse_source(&SourceFile {
+                    path: PathBuf::from(path),
+                    content: code.to_string(),
+                })
+            })
+            .collect();
+        parser(&files).expect("a contract is found")
+    }
+
+    #[test]
+    fn only_the_contract_module_is_collected() {
+        let inventory = inventory_of(&[
+            ("lib.rs", FLIPPER),
+            ("tests/e2e.rs", "#[ink::contract] mod e2e { impl E2e { #[ink(message)] pub fn driver(&self) {} } }"),
+        ]);
+
+        let assets: Vec<(&str, &str)> = inventory
+            .assets
+            .iter()
+            .map(|asset| (asset.name.as_str(), crate::output::category_name(&asset.category)))
+            .collect();
+        assert_eq!(
+            assets,
+            [
+                ("value", "Storage"),
+                ("owner", "Storage"),
+                ("new", "PublicFunction"),
+                ("get", "PublicFunction"),
+                ("flip", "PublicFunction"),
+                ("Flipped", "Events"),
+                ("NotOwner", "Error"),
+            ]
+        );
+    }
+
+    #[test]
+    fn entry_points_record_caller_checks_rather_than_an_origin() {
+        let inventory = inventory_of(&[("lib.rs", FLIPPER)]);
+        let attributes = |name: &str| {
+            let asset = inventory.assets.iter().find(|asset| asset.name == name).unwrap();
+            asset
+                .attributes
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            attributes("flip"),
+            [
+                ("ink", "message"),
+                ("mutates", "true"),
+                ("payable", "true"),
+                ("reads_caller", "true"),
+                ("selector", "0xCAFEBABE"),
+            ]
+        );
+        assert_eq!(
+            attributes("get"),
+            [("ink", "message"), ("mutates", "false"), ("payable", "false"), ("reads_caller", "false")]
+        );
+        assert_eq!(attributes("new"), [("ink", "constructor"), ("payable", "false"), ("reads_caller", "true")]);
+    }
+
+    #[test]
+    fn sources_without_a_contract_are_rejected() {
+        let files = [parse_source(&SourceFile {
+            path: PathBuf::from("lib.rs"),
+            content: "impl Helper { #[ink(message)] pub fn get(&self) {} }".to_string(),
+        })];
+        assert!(matches!(parser(&files), Err(AppError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,609 @@
+use crate::{
+    flatten_idents, has_pallet_constant, legacy, types::TypeAliases, AssetCategory, AssetInventory, Finding, ParsedFile,
+};
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use syn::{spanned::Spanned, visit::Visit, Type};
+
+// ----------------------------------------------Unbounded Iteration------------------------------------------------
+// Iterating a storage map or a caller supplied `Vec` costs time proportional to its length. When the weight
+// charged for a dispatchable or reserved by a hook does not grow with that length, a caller can fill a block for
+// a fixed fee or stall block production, the classic Substrate DoS. Iterations are collected from dispatchables
+// and hooks, following calls into the pallet's own helpers, and each is reported with whether the weight takes
+// the iteration count into account.
+
+// Storage methods walking every entry, or every entry below a prefix
+const ITERATION_METHODS: [&str; 10] = [
+    "iter",
+    "iter_keys",
+    "iter_values",
+    "iter_prefix",
+    "iter_prefix_values",
+    "iter_key_prefix",
+    "drain",
+    "drain_prefix",
+    "translate",
+    "translate_values",
+];
+// Storage methods removing many entries, with the position of their limit argument
+const LIMITED_METHODS: [(&str, usize); 4] = [
+    ("clear_prefix", 1),
+    ("clear", 0),
+    ("remove_all", 0),
+    ("remove_prefix", 1),
+];
+// Iterator methods on a `Vec` parameter
+const VEC_ITERATORS: [&str; 4] = ["iter", "into_iter", "iter_mut", "drain"];
+// Hooks executed by every block or on upgrade, the offchain worker does not run on-chain
+const HOOK_FUNCTIONS: [&str; 5] = ["on_initialize", "on_finalize", "on_idle", "on_poll", "on_runtime_upgrade"];
+// Weight functions whose arguments scale the weight
+const WEIGHT_FUNCTIONS: [&str; 6] = [
+    "reads",
+    "writes",
+    "reads_writes",
+    "saturating_mul",
+    "from_parts",
+    "from_ref_time",
+];
+// Identifiers in weight arguments that say nothing about what is counted
+const WEIGHT_NOISE: [&str; 10] = [
+    "as",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "usize",
+    "saturated_into",
+    "unique_saturated_into",
+    "try_into",
+];
+
+/// Iteration found in a function body
+struct Iteration {
+    target: String,      // storage item or parameter iterated
+    method: String,      // storage method, iterator method or `for`
+    parameter: bool,     // loop over a `Vec` parameter rather than storage
+    via: Option<String>, // helper the iteration happens in
+    line: usize,
+}
+
+/// Dispatchable or hook, whose weight has to pay for its iterations
+struct EntryPoint {
+    kind: &'static str,          // "dispatchable" or "hook"
+    weight: Option<TokenStream>, // weight annotation, or the body of a hook which returns its weight
+    file: String,
+}
+
+/// Iterations of a function and the pallet functions it calls
+type Analysed = (Vec<Iteration>, BTreeSet<String>);
+
+/// Visitor collecting entry points and the iterations of every function
+struct IterationVisitor<'a> {
+    aliases: &'a TypeAliases,
+    file: String,                                           // file currently visited
+    entry_points: BTreeMap<String, EntryPoint>,             // (function name, entry point)
+    functions: HashMap<String, BTreeMap<String, Analysed>>, // (function name, (file, analysed body))
+}
+
+impl<'ast> Visit<'ast> for IterationVisitor<'_> {
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let is_call = node.attrs.iter().any(|attr| has_pallet_constant("pallet::call".to_string(), attr));
+        let is_hooks = node
+            .trait_
+            .as_ref()
+            .and_then(|(_, path, _)| path.segments.last())
+            .is_some_and(|seg| seg.ident == "Hooks");
+
+        for item in &node.items {
+            let syn::ImplItem::Fn(method) = item else {
+                continue;
+            };
+            let name = method.sig.ident.to_string();
+            let params = method.sig.inputs.iter().filter_map(|input| match input {
+                syn::FnArg::Typed(pat_type) => match &*pat_type.pat {
+                    syn::Pat::Ident(ident) => Some((ident.ident.to_string(), (*pat_type.ty).clone())),
+                    _ => None,
+                },
+                syn::FnArg::Receiver(_) => None,
+            });
+            let analysed = self.analyse(params.collect(), &method.block);
+            self.insert_function(name.clone(), analysed);
+
+            let entry_point = if is_call {
+                // `#[pallet::weight(T::WeightInfo::remove(items.len() as u32))]`
+                let weight = method.attrs.iter().find_map(|attr| {
+                    let is_weight = has_pallet_constant("pallet::weight".to_string(), attr);
+                    match (is_weight, &attr.meta) {
+                        (true, syn::Meta::List(list)) => Some(list.tokens.clone()),
+                        _ => None,
+                    }
+                });
+                Some(("dispatchable", weight))
+            } else if is_hooks && HOOK_FUNCTIONS.contains(&name.as_str()) {
+                Some(("hook", Some(method.block.to_token_stream())))
+            } else {
+                None
+            };
+            if let Some((kind, weight)) = entry_point {
+                self.entry_points.insert(
+                    name,
+                    EntryPoint {
+                        kind,
+                        weight,
+                        file: self.file.clone(),
+                    },
+                );
+            }
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+
+    // FRAME v1 dispatchables and hooks live in `decl_module!`
+    fn visit_item_macro(&mut self, node: &'ast syn::ItemMacro) {
+        if node.mac.path.segments.last().is_some_and(|seg| seg.ident == "decl_module") {
+            for function in legacy::module_fns(node.mac.tokens.clone()) {
+                let analysed = self.analyse(function.params, &function.block);
+                self.insert_function(function.name.clone(), analysed);
+
+                let entry_point = if HOOK_FUNCTIONS.contains(&function.name.as_str()) {
+                    Some(("hook", Some(function.block.to_token_stream())))
+                } else if function.public {
+                    Some(("dispatchable", function.weight))
+                } else {
+                    None
+                };
+                if let Some((kind, weight)) = entry_point {
+                    self.entry_points.insert(
+                        function.name,
+                        EntryPoint {
+                            kind,
+                            weight,
+                            file: self.file.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        syn::visit::visit_item_macro(self, node);
+    }
+}
+
+impl IterationVisitor<'_> {
+    fn insert_function(&mut self, name: String, analysed: Analysed) {
+        self.functions.entry(name).or_default().insert(self.file.clone(), analysed);
+    }
+
+    // Function called from `file`: the one defined in the same file, else the only definition in another file.
+    // Functions with the same name in several other files are not followed, e.g. `purge` of a migration.
+    fn function(&self, name: &str, file: &str) -> Option<(&str, &Analysed)> {
+        let definitions = self.functions.get(name)?;
+        let (file, analysed) = match definitions.get_key_value(file) {
+            Some(definition) => definition,
+            None if definitions.len() == 1 => definitions.iter().next()?,
+            None => return None,
+        };
+        Some((file.as_str(), analysed))
+    }
+
+    fn analyse(&self, params: Vec<(String, Type)>, block: &syn::Block) -> Analysed {
+        // `BoundedVec` has an upper bound, a plain `Vec` is whatever the caller sends
+        let vec_params = params
+            .into_iter()
+            .filter(|(_, ty)| {
+                let mut finder = VecFinder::default();
+                finder.visit_type(&self.aliases.expand(ty.clone()));
+                finder.found
+            })
+            .map(|(name, _)| name)
+            .collect();
+        let mut finder = IterationFinder {
+            vec_params,
+            iterations: Vec::new(),
+            callees: BTreeSet::new(),
+        };
+        finder.visit_block(block);
+        (finder.iterations, finder.callees)
+    }
+
+    // Storage iterations of the helpers a function calls, directly or through other helpers
+    fn helper_iterations(&self, function: &str, file: &str) -> Vec<Iteration> {
+        let mut iterations = Vec::new();
+        let mut visited = BTreeSet::from([function.to_string()]);
+        let mut pending: Vec<(&str, String)> = self
+            .function(function, file)
+            .map(|(file, (_, callees))| callees.iter().map(|callee| (file, callee.clone())).collect())
+            .unwrap_or_default();
+        while let Some((caller_file, helper)) = pending.pop() {
+            if !visited.insert(helper.clone()) {
+                continue;
+            }
+            let Some((helper_file, (helper_iterations, callees))) = self.function(&helper, caller_file) else {
+                continue;
+            };
+            iterations.extend(helper_iterations.iter().filter(|iteration| !iteration.parameter).map(|iteration| {
+                Iteration {
+                    target: iteration.target.clone(),
+                    method: iteration.method.clone(),
+                    parameter: false,
+                    via: Some(iteration.via.clone().unwrap_or_else(|| helper.clone())),
+                    line: iteration.line,
+                }
+            }));
+            pending.extend(callees.iter().map(|callee| (helper_file, callee.clone())));
+        }
+        iterations
+    }
+}
+
+/// Visitor looking for a plain `Vec` in a parameter type
+#[derive(Default)]
+struct VecFinder {
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for VecFinder {
+    fn visit_path_segment(&mut self, node: &'ast syn::PathSegment) {
+        self.found |= node.ident == "Vec";
+        syn::visit::visit_path_segment(self, node);
+    }
+}
+
+/// Visitor collecting the iterations of a function body and the pallet functions it calls
+struct IterationFinder {
+    vec_params: BTreeSet<String>,
+    iterations: Vec<Iteration>,
+    callees: BTreeSet<String>, // `Self::helper(..)` and `Pallet::<T>::helper(..)`
+}
+
+impl<'ast> Visit<'ast> for IterationFinder {
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(path) = &*node.func {
+            let segments: Vec<String> = path.path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+            let method = segments.last().cloned().unwrap_or_default();
+            let owner = match &path.qself {
+                Some(qself) => match &*qself.ty {
+                    Type::Path(ty) => ty.path.segments.last().map(|seg| seg.ident.to_string()),
+                    _ => None,
+                },
+                None => segments.len().checked_sub(2).map(|i| segments[i].clone()),
+            };
+
+            if let Some(owner) = owner {
+                if matches!(owner.as_str(), "Self" | "Pallet" | "Module") {
+                    self.callees.insert(method.clone());
+                }
+
+                // `clear_prefix(prefix, u32::MAX, None)` and `remove_all(None)` remove everything in one go
+                let unlimited = LIMITED_METHODS.iter().find(|(name, _)| *name == method).map(|(_, position)| {
+                    node.args.iter().nth(*position).is_none_or(|limit| {
+                        let limit = limit.to_token_stream().to_string();
+                        limit == "None" || limit.contains("MAX") || limit.contains("max_value")
+                    })
+                });
+                if ITERATION_METHODS.contains(&method.as_str()) || unlimited == Some(true) {
+                    self.iterations.push(Iteration {
+                        target: owner,
+                        method,
+                        parameter: false,
+                        via: None,
+                        line: node.span().start().line,
+                    });
+                }
+            }
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+
+    // `for item in items { .. }` or `for item in &items { .. }`
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        let iterated = match &*node.expr {
+            syn::Expr::Reference(reference) => &*reference.expr,
+            expr => expr,
+        };
+        if let Some(param) = self.vec_param(iterated) {
+            self.push_parameter(param, "for", node.for_token.span.start().line);
+        }
+        syn::visit::visit_expr_for_loop(self, node);
+    }
+
+    // `items.iter().map(..)`, also the iterator of `for item in items.iter()`
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if VEC_ITERATORS.iter().any(|name| node.method == name) {
+            if let Some(param) = self.vec_param(&node.receiver) {
+                self.push_parameter(param, &node.method.to_string(), node.method.span().start().line);
+            }
+        }
+        syn::visit::visit_expr_method_call(self, node);
+    }
+}
+
+impl IterationFinder {
+    fn vec_param(&self, expr: &syn::Expr) -> Option<String> {
+        let syn::Expr::Path(path) = expr else {
+            return None;
+        };
+        let name = path.path.get_ident()?.to_string();
+        self.vec_params.contains(&name).then_some(name)
+    }
+
+    // One report per parameter, `for item in items.iter()` is a single loop
+    fn push_parameter(&mut self, param: String, method: &str, line: usize) {
+        if self.iterations.iter().any(|iteration| iteration.parameter && iteration.target == param) {
+            return;
+        }
+        self.iterations.push(Iteration {
+            target: param,
+            method: method.to_string(),
+            parameter: true,
+            via: None,
+            line,
+        });
+    }
+}
+
+/// Add findings for iterations in dispatchables and hooks, telling whether the weight accounts for them
+///
+/// Reads the Storage assets, so it runs after they were created.
+pub(crate) fn collect(files: &[ParsedFile], inventory: &mut AssetInventory) {
+    let aliases = TypeAliases::collect(files);
+    let mut visitor = IterationVisitor {
+        aliases: &aliases,
+        file: String::new(),
+        entry_points: BTreeMap::new(),
+        functions: HashMap::new(),
+    };
+    for file in files {
+        visitor.file = file.path.clone();
+        visitor.visit_file(&file.syntax_tree);
+    }
+
+    let storage: BTreeSet<&str> = inventory
+        .assets
+        .iter()
+        .filter(|asset| matches!(asset.category, AssetCategory::Storage(..)))
+        .map(|asset| asset.name.as_str())
+        .collect();
+
+    // (function, (iterations, weight accounts for all of them))
+    let mut summaries: BTreeMap<&str, (Vec<String>, &str)> = BTreeMap::new();
+    for (name, entry_point) in &visitor.entry_points {
+        let own = visitor
+            .function(name, &entry_point.file)
+            .map(|(_, (iterations, _))| iterations.as_slice())
+            .unwrap_or_default();
+        let helpers = visitor.helper_iterations(name, &entry_point.file);
+        let iterations: Vec<&Iteration> = own
+            .iter()
+            .chain(&helpers)
+            .filter(|iteration| iteration.parameter || storage.contains(iteration.target.as_str()))
+            .collect();
+        if iterations.is_empty() {
+            continue;
+        }
+
+        let mut overall = "yes";
+        let mut listed = Vec::new();
+        for iteration in iterations {
+            let accounted = match &entry_point.weight {
+                None => "unknown",
+                Some(weight) => {
+                    let counted = if iteration.parameter { Some(iteration.target.as_str()) } else { None };
+                    if weight_counts(weight.clone(), counted) {
+                        "yes"
+                    } else {
+                        "no"
+                    }
+                }
+            };
+            overall = match (overall, accounted) {
+                ("no", _) | (_, "no") => "no",
+                ("unknown", _) | (_, "unknown") => "unknown",
+                _ => "yes",
+            };
+            listed.push(format!("{}::{}", iteration.target, iteration.method));
+
+            let subject = if iteration.parameter {
+                format!("loops over the caller supplied `{}`", iteration.target)
+            } else {
+                format!("iterates `{}` with `{}`", iteration.target, iteration.method)
+            };
+            let via = iteration
+                .via
+                .as_ref()
+                .map(|helper| format!(" through `{}`", helper))
+                .unwrap_or_default();
+            let verdict = match accounted {
+                "yes" => "the weight accounts for the iteration count",
+                "no" => "the weight does not account for the iteration count",
+                _ => "there is no weight annotation to check it against",
+            };
+            inventory.findings.push(Finding {
+                rule: "unbounded-iteration".to_string(),
+                asset: name.clone(),
+                message: format!("{} `{}` {}{}, {}", entry_point.kind, name, subject, via, verdict),
+                file: Some(entry_point.file.clone()),
+                line: Some(iteration.line),
+            });
+        }
+        summaries.insert(name, (listed, overall));
+    }
+
+    for asset in &mut inventory.assets {
+        let is_function = matches!(asset.category, AssetCategory::PublicFunction(..) | AssetCategory::Helper(..));
+        if let (true, Some((iterations, accounted))) = (is_function, summaries.get(asset.name.as_str())) {
+            asset.attributes.insert("iterations".to_string(), iterations.join(","));
+            asset.attributes.insert("weight_accounts".to_string(), accounted.to_string());
+        }
+    }
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+// Whether a weight expression scales with something: a `WeightInfo` function or a weight helper taking a
+// non-constant argument, which must mention the iterated parameter when one is given
+fn weight_counts(weight: TokenStream, parameter: Option<&str>) -> bool {
+    let tokens: Vec<TokenTree> = weight.into_iter().collect();
+    tokens.iter().enumerate().any(|(i, token)| match token {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis && i > 0 => {
+            // Identifiers of the path leading to the call, `T::WeightInfo::remove`
+            let path: Vec<String> = tokens[..i]
+                .iter()
+                .rev()
+                .take_while(|tt| match tt {
+                    TokenTree::Ident(_) => true,
+                    TokenTree::Punct(p) => matches!(p.as_char(), ':' | '<' | '>' | '.'),
+                    _ => false,
+                })
+                .filter_map(|tt| match tt {
+                    TokenTree::Ident(ident) => Some(ident.to_string()),
+                    _ => None,
+                })
+                .collect();
+            let is_weight = path.first().is_some_and(|name| WEIGHT_FUNCTIONS.contains(&name.as_str()))
+                || path.iter().any(|name| name == "WeightInfo");
+
+            let mut idents = Vec::new();
+            flatten_idents(group.stream(), &mut idents);
+            idents.retain(|ident| !WEIGHT_NOISE.contains(&ident.as_str()));
+            let counts = match parameter {
+                Some(parameter) => idents.iter().any(|ident| ident == parameter),
+                None => !idents.is_empty(),
+            };
+            (is_weight && counts) || weight_counts(group.stream(), parameter)
+        }
+        TokenTree::Group(group) => weight_counts(group.stream(), parameter),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{inventory_of_files, AssetInventory};
+
+    const PALLET: &str = r#"
+        #[frame_support::pallet]
+        pub mod pallet {
+            #[pallet::storage]
+            pub type Items<T> = StorageMap<_, Blake2_128Concat, u32, u32>;
+
+            #[pallet::hooks]
+            impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+                fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+                    let count = Items::<T>::iter().count() as u32;
+                    T::WeightInfo::on_initialize(count)
+                }
+
+                fn on_finalize(_n: BlockNumberFor<T>) {
+                    let _ = Items::<T>::clear(u32::MAX, None);
+                }
+            }
+
+            #[pallet::call]
+            impl<T: Config> Pallet<T> {
+                #[pallet::weight(T::WeightInfo::store(items.len() as u32))]
+                pub fn store(origin: OriginFor<T>, items: Vec<u32>) -> DispatchResult {
+                    for item in items {
+                        Items::<T>::insert(item, item);
+                    }
+                    Ok(())
+                }
+
+                #[pallet::weight(T::WeightInfo::reset())]
+                pub fn reset(origin: OriginFor<T>, keep: Vec<u32>) -> DispatchResult {
+                    keep.iter().for_each(|item| Self::keep(*item));
+                    Self::purge();
+                    Ok(())
+                }
+
+                #[pallet::weight(T::DbWeight::get().writes(1))]
+                pub fn tidy(origin: OriginFor<T>) -> DispatchResult {
+                    Self::cleanup();
+                    Ok(())
+                }
+            }
+
+            impl<T: Config> Pallet<T> {
+                fn purge() {}
+            }
+        }
+    "#;
+
+    // `cleanup` only lives here, `purge` also lives in the pallet
+    const FUNCTIONS: &str = r#"
+        impl<T: Config> Pallet<T> {
+            fn cleanup() {
+                Items::<T>::drain().for_each(drop);
+            }
+        }
+
+        impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+            fn on_runtime_upgrade() -> Weight {
+                Self::purge();
+                Weight::zero()
+            }
+        }
+
+        impl<T: Config> MigrateToV2<T> {
+            fn purge() {
+                for (key, _) in Items::<T>::iter() {
+                    Items::<T>::remove(key);
+                }
+            }
+        }
+    "#;
+
+    fn attributes<'a>(inventory: &'a AssetInventory, function: &str) -> (Option<&'a str>, Option<&'a str>) {
+        let asset = inventory.assets.iter().find(|asset| asset.name == function).unwrap();
+        (
+            asset.attributes.get("iterations").map(String::as_str),
+            asset.attributes.get("weight_accounts").map(String::as_str),
+        )
+    }
+
+    #[test]
+    fn iterations_are_checked_against_the_weight() {
+        let inventory = inventory_of_files(&[("src/lib.rs", PALLET), ("src/functions.rs", FUNCTIONS)]);
+
+        assert_eq!(attributes(&inventory, "store"), (Some("items::for"), Some("yes")));
+        assert_eq!(attributes(&inventory, "reset"), (Some("keep::iter"), Some("no")));
+        assert_eq!(attributes(&inventory, "tidy"), (Some("Items::drain"), Some("no")));
+
+        let findings: Vec<(&str, &str)> = inventory
+            .findings
+            .iter()
+            .filter(|finding| finding.rule == "unbounded-iteration")
+            .map(|finding| (finding.asset.as_str(), finding.message.as_str()))
+            .collect();
+        assert_eq!(
+            findings,
+            [
+                (
+                    "on_finalize",
+                    "hook `on_finalize` iterates `Items` with `clear`, the weight does not account for the iteration \
+                     count"
+                ),
+                (
+                    "on_initialize",
+                    "hook `on_initialize` iterates `Items` with `iter`, the weight accounts for the iteration count"
+                ),
+                (
+                    "reset",
+                    "dispatchable `reset` loops over the caller supplied `keep`, the weight does not account for the \
+                     iteration count"
+                ),
+                (
+                    "store",
+                    "dispatchable `store` loops over the caller supplied `items`, the weight accounts for the \
+                     iteration count"
+                ),
+                (
+                    "tidy",
+                    "dispatchable `tidy` iterates `Items` with `drain` through `cleanup`, the weight does not account \
+                     for the iteration count"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use crate::{effects, skip_attributes, split_tokens, types::normalize, PalletVisitor};
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::quote;
+
+// ----------------------------------------------FRAME v1 Pallets---------------------------------------------------
+// Forks written with `decl_module!`, `decl_storage!`, `decl_event!` and `decl_error!` hide every item inside
+// macro invocations that syn cannot see into. The token streams are walked by hand and fed into the same
+// visitor fields as FRAME v2 pallets, so both produce the same inventory categories.
+
+/// Collect items of a FRAME v1 declaration macro, ignoring any other macro
+pub(crate) fn visit_decl_macro(visitor: &mut PalletVisitor, mac: &syn::Macro) {
+    let macro_name = mac.path.segments.last().map(|seg| seg.ident.to_string());
+    let tokens = mac.tokens.clone();
+    match macro_name.as_deref() {
+        Some("decl_module") => decl_module(visitor, tokens),
+        Some("decl_storage") => decl_storage(visitor, tokens),
+        Some("decl_event") => visitor.events.extend(enum_variants(tokens)),
+        Some("decl_error") => visitor.errors.extend(enum_variants(tokens)),
+        _ => {}
+    }
+}
+
+// pub struct Module<T: Config> for enum Call where origin: T::Origin {
+//     const MaxItems: u32 = T::MaxItems::get();
+//     #[weight = 10_000]
+//     pub fn do_something(origin, something: u32) -> DispatchResult { .. }
+//     fn on_initialize(n: T::BlockNumber) -> Weight { .. }
+// }
+fn decl_module(visitor: &mut PalletVisitor, tokens: TokenStream) {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let Some(body_position) = tokens.iter().rposition(is_brace_group) else {
+        return;
+    };
+
+    // The origin type is declared once in the header: `where origin: T::Origin`
+    let origin_type = tokens[..body_position]
+        .iter()
+        .position(|tt| matches!(tt, TokenTree::Ident(ident) if ident == "origin"))
+        .map(|origin| {
+            let ty: TokenStream = tokens[origin + 2..body_position]
+                .iter()
+                .take_while(|tt| !matches!(tt, TokenTree::Punct(p) if p.as_char() == ','))
+                .cloned()
+                .collect();
+            ty.to_string()
+        })
+        .unwrap_or_else(|| "T :: Origin".to_string());
+
+    let TokenTree::Group(body) = &tokens[body_position] else {
+        return;
+    };
+    let body: Vec<TokenTree> = body.stream().into_iter().collect();
+    for (i, token) in body.iter().enumerate() {
+        if let (TokenTree::Ident(keyword), Some(TokenTree::Ident(name))) = (token, body.get(i + 1)) {
+            if keyword == "const" {
+                visitor.constants.push(name.to_string());
+            }
+        }
+    }
+
+    // `fn deposit_event() = default;` has no body and is not an asset
+    for function in module_fns(tokens.into_iter().collect()) {
+        // The untyped `origin` parameter gets the declared origin type
+        let mut param_info = Vec::new();
+        if function.origin {
+            param_info.push(("origin".to_string(), origin_type.clone()));
+        }
+        param_info.extend(function.params.iter().map(|(name, ty)| (name.clone(), quote!(#ty).to_string())));
+
+        // Public functions are the calls of the module, the others are hooks and helpers
+        if function.public {
+            visitor.dispatchables.insert(function.name.clone());
+        }
+        let visibility = if function.public { "public" } else { "private" };
+        visitor.effects.insert(function.name.clone(), effects::analyse(&function.block));
+        visitor.functions.insert(function.name.clone(), visibility.to_string());
+        visitor.params.push((function.name, param_info));
+    }
+}
+
+/// Function of a `decl_module!` body, parsed for the passes that look into function bodies
+pub(crate) struct ModuleFn {
+    pub(crate) name: String,
+    pub(crate) public: bool,
+    pub(crate) weight: Option<TokenStream>,      // expression of `#[weight = ..]`
+    pub(crate) origin: bool,                     // takes the untyped `origin` parameter
+    pub(crate) params: Vec<(String, syn::Type)>, // typed parameters, the untyped `origin` is left out
+    pub(crate) block: syn::Block,
+    pub(crate) line: usize,
+}
+
+/// Functions with a body declared in a `decl_module!` invocation
+pub(crate) fn module_fns(tokens: TokenStream) -> Vec<ModuleFn> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let Some(TokenTree::Group(body)) = tokens.iter().rev().find(|tt| is_brace_group(tt)) else {
+        return Vec::new();
+    };
+    let body: Vec<TokenTree> = body.stream().into_iter().collect();
+
+    let mut fns = Vec::new();
+    for (i, token) in body.iter().enumerate() {
+        let (TokenTree::Ident(keyword), Some(TokenTree::Ident(name)), Some(TokenTree::Group(params))) =
+            (token, body.get(i + 1), body.get(i + 2))
+        else {
+            continue;
+        };
+        if keyword != "fn" {
+            continue;
+        }
+        let Some(block) = body[i + 3..]
+            .iter()
+            .find(|tt| is_brace_group(tt) || matches!(tt, TokenTree::Punct(p) if p.as_char() == ';'))
+            .filter(|tt| is_brace_group(tt))
+            .and_then(|block| syn::parse2::<syn::Block>(block.clone().into()).ok())
+        else {
+            continue;
+        };
+
+        // `pub fn`, `pub(crate) fn`, then the attributes before them
+        let mut start = i;
+        if matches!(start.checked_sub(1).map(|j| &body[j]), Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis)
+        {
+            start -= 1;
+        }
+        let public = matches!(start.checked_sub(1).map(|j| &body[j]), Some(TokenTree::Ident(ident)) if ident == "pub");
+        if public {
+            start -= 1;
+        }
+        let mut weight = None;
+        while start >= 2 {
+            let (TokenTree::Punct(hash), TokenTree::Group(attr)) = (&body[start - 2], &body[start - 1]) else {
+                break;
+            };
+            if hash.as_char() != '#' {
+                break;
+            }
+            let attr: Vec<TokenTree> = attr.stream().into_iter().collect();
+            if let [TokenTree::Ident(ident), TokenTree::Punct(equals), value @ ..] = attr.as_slice() {
+                if ident == "weight" && equals.as_char() == '=' {
+                    weight = Some(value.iter().cloned().collect());
+                }
+            }
+            start -= 2;
+        }
+
+        let params = split_params(params.stream());
+        let origin = params
+            .iter()
+            .any(|param| matches!(param.as_slice(), [TokenTree::Ident(ident)] if ident == "origin"));
+        let params = params
+            .into_iter()
+            .filter_map(|param| match param.as_slice() {
+                [TokenTree::Ident(param), TokenTree::Punct(colon), ty @ ..] if colon.as_char() == ':' => {
+                    let ty = syn::parse2::<syn::Type>(ty.iter().cloned().collect()).ok()?;
+                    Some((param.to_string(), ty))
+                }
+                _ => None,
+            })
+            .collect();
+        fns.push(ModuleFn {
+            name: name.to_string(),
+            public,
+            weight,
+            origin,
+            params,
+            block,
+            line: name.span().start().line,
+        });
+    }
+    fns
+}
+
+// trait Store for Module<T: Config> as TemplateModule {
+//     Something get(fn something): Option<u32>;
+//     pub Accounts: map hasher(blake2_128_concat) T::AccountId => u64;
+// }
+fn decl_storage(visitor: &mut PalletVisitor, tokens: TokenStream) {
+    // The first brace group holds the storage items, a trailing `add_extra_genesis { .. }` is ignored
+    let Some(TokenTree::Group(body)) = tokens.into_iter().find(is_brace_group) else {
+        return;
+    };
+
+    for entry in split_tokens(body.stream(), ';') {
+        let mut entry = skip_attributes(entry);
+        let is_public = matches!(entry.first(), Some(TokenTree::Ident(ident)) if ident == "pub");
+        if is_public {
+            entry.remove(0);
+            // `pub(crate)`
+            if matches!(entry.first(), Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis) {
+                entry.remove(0);
+            }
+        }
+
+        let Some(TokenTree::Ident(name)) = entry.first() else {
+            continue;
+        };
+        // `get(fn x)` and `config()` are groups, so the first `:` separates the name from the type
+        let Some(colon) = entry.iter().position(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == ':')) else {
+            continue;
+        };
+        // A lone `=` starts the default value, `=>` belongs to the map type
+        let default = entry
+            .iter()
+            .position(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == '=' && p.spacing() == Spacing::Alone))
+            .unwrap_or(entry.len());
+        let storage_type: TokenStream = entry[colon + 1..default].iter().cloned().collect();
+
+        let visibility = if is_public { "public" } else { "private" };
+        visitor
+            .storage_items
+            .insert(name.to_string(), (visibility.to_string(), normalize(&storage_type.to_string())));
+    }
+}
+
+// pub enum Event<T> where AccountId = <T as frame_system::Config>::AccountId { SomethingStored(u32, AccountId), }
+// pub enum Error for Module<T: Config> { NoneValue, StorageOverflow, }
+fn enum_variants(tokens: TokenStream) -> Vec<String> {
+    let Some(TokenTree::Group(body)) = tokens.into_iter().filter(is_brace_group).last() else {
+        return Vec::new();
+    };
+
+    split_tokens(body.stream(), ',')
+        .into_iter()
+        .filter_map(|variant| match skip_attributes(variant).first()? {
+            TokenTree::Ident(ident) => Some(ident.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+// Split parameters on commas outside of generics, `ids: BoundedVec<u32, ConstU32<64>>` stays whole
+fn split_params(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut params = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut arrow = false;
+    for token in tokens {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' && depth == 0 => {
+                params.push(Vec::new());
+                continue;
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '<' => depth += 1,
+            // The `>` of `->` in `Box<dyn Fn(u32) -> u32>` closes nothing
+            TokenTree::Punct(punct) if punct.as_char() == '>' && !arrow => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        arrow = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '-' && punct.spacing() == Spacing::Joint);
+        params.last_mut().unwrap().push(token);
+    }
+    params.retain(|param| !param.is_empty());
+    params
+}
+
+fn is_brace_group(token: &TokenTree) -> bool {
+    matches!(token, TokenTree::Group(group) if group.delimiter() == Delimiter::Brace)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{inventory_of, AssetCategory};
+
+    #[test]
+    fn decl_module_keeps_generic_parameters_whole() {
+        let inventory = inventory_of(
+            r#"
+            decl_module! {
+                pub struct Module<T: Config> for enum Call where origin: T::Origin {
+                    fn deposit_event() = default;
+
+                    #[weight = 10_000]
+                    pub fn store(origin, ids: BoundedVec<u32, ConstU32<64>>, pair: BTreeMap<u32, u64>) -> DispatchResult {
+                        Ok(())
+                    }
+                }
+            }
+            "#,
+        );
+
+        let params: Vec<(&str, &str)> = inventory
+            .assets
+            .iter()
+            .find_map(|asset| match &asset.category {
+                AssetCategory::PublicFunction(name, params) if name == "store" => Some(params),
+                _ => None,
+            })
+            .expect("`store` is a public function")
+            .iter()
+            .map(|param| (param.name.as_str(), param.param_type.as_str()))
+            .collect();
+        assert_eq!(
+            params,
+            [
+                ("origin", "T::Origin"),
+                ("ids", "BoundedVec<u32, ConstU32<64>>"),
+                ("pair", "BTreeMap<u32, u64>"),
+            ]
+        );
+        assert!(!inventory.assets.iter().any(|asset| asset.name == "deposit_event"));
+        let store = inventory.assets.iter().find(|asset| asset.name == "store").unwrap();
+        assert_eq!(store.attributes.get("dispatchable").map(String::as_str), Some("yes"));
+    }
+
+    #[test]
+    fn decl_module_calls_record_their_effects() {
+        let inventory = inventory_of(
+            r#"
+            decl_storage! {
+                trait Store for Module<T: Config> as TemplateModule {
+                    Something get(fn something): Option<u32>;
+                    pub Accounts: map hasher(blake2_128_concat) T::AccountId => Vec<u64>;
+                }
+            }
+
+            decl_module! {
+                pub struct Module<T: Config> for enum Call where origin: T::Origin {
+                    #[weight = 10_000]
+                    pub fn set(origin, value: u32) -> DispatchResult {
+                        let who = ensure_signed(origin)?;
+                        T::Currency::reserve(&who, 10u32.into())?;
+                        Something::put(value);
+                        Ok(())
+                    }
+                }
+            }
+            "#,
+        );
+
+        let attribute = |name: &str, key: &str| {
+            let asset = inventory.assets.iter().find(|asset| asset.name == name).unwrap();
+            asset.attributes.get(key).cloned()
+        };
+        assert_eq!(attribute("set", "origin").as_deref(), Some("signed"));
+        assert_eq!(attribute("set", "writes").as_deref(), Some("Something"));
+        assert_eq!(attribute("set", "external_calls").as_deref(), Some("Currency::reserve"));
+        assert_eq!(attribute("Something", "type").as_deref(), Some("Option<u32>"));
+        assert_eq!(
+            attribute("Accounts", "type").as_deref(),
+            Some("map hasher(blake2_128_concat) T::AccountId => Vec<u64>")
+        );
+    }
+}
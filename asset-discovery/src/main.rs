@@ -1,36 +1,195 @@
+mod benchmarks;
+mod bulk;
+mod coupling;
+mod coverage;
+mod diagnostics;
+mod effects;
+mod ink;
+mod iteration;
+mod legacy;
+mod migrations;
+mod output;
+mod query;
+mod runtime;
+mod storage_bounds;
+mod types;
+mod watch;
+mod xcm;
+
+use clap::{Parser, Subcommand};
+use output::OutputFormat;
 use quote::quote;
-use serde::Serialize;
-use std::{collections::HashMap, error::Error, fs, path::Path};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+use proc_macro2::{TokenStream, TokenTree};
 use syn::{visit::Visit, Attribute, File};
 
+// Traits implemented for the pallet by FRAME itself rather than for other pallets
+const FRAME_HOOKS: [&str; 9] = [
+    "Hooks",
+    "OnInitialize",
+    "OnFinalize",
+    "OnIdle",
+    "OnRuntimeUpgrade",
+    "OffchainWorker",
+    "IntegrityTest",
+    "BuildGenesisConfig",
+    "GenesisBuild",
+];
+
+/// Command line interface
+#[derive(Debug, Parser)]
+#[command(
+    name = "asset-discovery",
+    about = "Generate an asset inventory for a FRAME pallet",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Path to the pallet source file or crate directory, prompted for when omitted
+    path: Option<PathBuf>,
+    /// Previous version of the pallet source, used to detect storage changes shipped without a migration
+    #[arg(long)]
+    previous: Option<PathBuf>,
+    /// Treat the source as a runtime and list its pallets and Config bindings instead
+    #[arg(long)]
+    runtime: bool,
+    /// Treat the source as an ink! smart contract instead of a pallet
+    #[arg(long, conflicts_with = "runtime")]
+    ink: bool,
+    /// Output format of the inventory
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+    /// Where to write the inventory, defaults to `./asset-inventory.<format extension>`
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+    /// Keep watching the source and re-run discovery on every save
+    #[arg(long)]
+    watch: bool,
+    /// Treat the path as a workspace: analyse every pallet crate below it in parallel and write one inventory
+    /// per pallet plus a summary report into the `--output` directory, defaults to `./asset-inventories`
+    #[arg(long, requires = "path", conflicts_with_all = ["previous", "runtime", "ink", "watch"])]
+    bulk: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the assets matching a query, e.g. `category=Dispatchable and origin=signed and writes:Balances`
+    ///
+    /// Terms are `field=value`, `field!=value` or `field:value` (substring), combined with `and`, `or`, `not`
+    /// and parentheses. Fields are `category`, `name`, `visibility`, `param`, `details` and any attribute key
+    /// such as `origin`, `writes` or `type`.
+    Query {
+        /// Inventory JSON file, or pallet source file or crate directory to analyse first
+        path: PathBuf,
+        query: String,
+        /// Treat the source as a runtime
+        #[arg(long)]
+        runtime: bool,
+        /// Treat the source as an ink! smart contract
+        #[arg(long, conflicts_with = "runtime")]
+        ink: bool,
+        /// Output format of the matching assets
+        #[arg(long, value_enum, default_value_t = OutputFormat::Markdown)]
+        format: OutputFormat,
+    },
+}
+
 fn main() -> Result<(), AppError> {
+    let cli = Cli::parse();
+
+    if let Some(Command::Query {
+        path,
+        query,
+        runtime,
+        ink,
+        format,
+    }) = &cli.command
+    {
+        let parser = select_parser(*runtime, *ink);
+        return query::run(path, query, parser, *format).map_err(|e| {
+            eprintln!("Error running query: {}", e);
+            if let Some(source) = e.source() {
+                eprintln!("Caused by: {}", source);
+            }
+            e
+        });
+    }
+
+    if let (true, Some(path)) = (cli.bulk, &cli.path) {
+        return bulk::run(path, cli.format, cli.output.as_deref()).map_err(|e| {
+            eprintln!("Error scanning workspace: {}", e);
+            if let Some(source) = e.source() {
+                eprintln!("Caused by: {}", source);
+            }
+            e
+        });
+    }
+
     // Read source code
-    let file_content = loop {
-        match source_code_reader() {
-            // If we successfully read the file content, exit the loop and return the content.
-            // This breaks out of the loop with the value 'content' which becomes the value of file_content
-            Ok(content) => break content,
-            Err(e) => {
+    let (source_path, sources) = match &cli.path {
+        Some(path) => {
+            let sources = read_sources(path).map_err(|e| {
                 eprintln!("Error reading source code: {}", e);
-                if let Some(source) = e.source() {
-                    eprintln!("Caused by: {}", source);
-                }
-                continue;
-            }
+                e
+            })?;
+            (path.clone(), sources)
+        }
+        None => {
+            let source = prompt_source_code();
+            (source.path.clone(), vec![source])
         }
     };
 
+    let parser = select_parser(cli.runtime, cli.ink);
+
+    // Parse the previous version, used to catch storage changes without a migration
+    let previous = match &cli.previous {
+        Some(previous_path) => Some(
+            read_sources(previous_path)
+                .and_then(|sources| parser(&sources.iter().map(parse_source).collect::<Vec<_>>()))
+                .map_err(|e| {
+                    eprintln!("Error reading previous version: {}", e);
+                    e
+                })?,
+        ),
+        None => None,
+    };
+
+    if cli.watch {
+        return watch::run(&source_path, parser, previous.as_ref(), cli.format, cli.output.as_deref()).map_err(|e| {
+            eprintln!("Error watching source code: {}", e);
+            if let Some(source) = e.source() {
+                eprintln!("Caused by: {}", source);
+            }
+            e
+        });
+    }
+
     // Parse source code into self-defined format [AssetInventory]
-    let output = parser(file_content).map_err(|e| {
+    let files: Vec<ParsedFile> = sources.iter().map(parse_source).collect();
+    let mut output = parser(&files).map_err(|e| {
         eprintln!("Error parsing source code: {}", e);
         if let Some(source) = e.source() {
             eprintln!("Caused by: {}", source);
         }
         e
     })?;
+    output.source = Some(source_path.display().to_string());
+
+    if let Some(previous) = &previous {
+        let findings = migrations::compare_versions(previous, &output);
+        output.findings.extend(findings);
+    }
 
     // Write result to file
-    result_writer(output).map_err(|e| {
+    result_writer(&output, cli.format, cli.output.as_deref()).map_err(|e| {
         eprintln!("Error writing results: {}", e);
         if let Some(source) = e.source() {
             eprintln!("Caused by: {}", source);
@@ -41,15 +200,126 @@ fn main() -> Result<(), AppError> {
     Ok(())
 }
 
+/// Helper function to pick the parser matching the kind of source code
+fn select_parser(runtime: bool, ink: bool) -> fn(&[ParsedFile]) -> Result<AssetInventory, AppError> {
+    if runtime {
+        runtime::parser
+    } else if ink {
+        ink::parser
+    } else {
+        parser
+    }
+}
+
+/// Helper function to keep prompting until a readable source file is given
+fn prompt_source_code() -> SourceFile {
+    loop {
+        match source_code_reader() {
+            // If we successfully read the file content, exit the loop and return the content.
+            // This breaks out of the loop with the value 'source' which becomes the returned path and source code
+            Ok(source) => break source,
+            Err(e) => {
+                eprintln!("Error reading source code: {}", e);
+                if let Some(source) = e.source() {
+                    eprintln!("Caused by: {}", source);
+                }
+                continue;
+            }
+        }
+    }
+}
+
 /// Helper function to read the Rust source code file
-fn source_code_reader() -> Result<String, AppError> {
+fn source_code_reader() -> Result<SourceFile, AppError> {
     println!("Please enter the path to the source code:");
     let mut input = String::new();
     std::io::stdin()
         .read_line(&mut input)
-        .map_err(|e| AppError::IoError(e))?;
-    
-    let file_path = Path::new(input.trim());
+        .map_err(AppError::IoError)?;
+
+    let file_path = PathBuf::from(input.trim());
+    let content = read_source(&file_path)?;
+    Ok(SourceFile {
+        path: file_path,
+        content,
+    })
+}
+
+/// Helper function to read a single source file, or every Rust file of a crate directory
+fn read_sources(path: &Path) -> Result<Vec<SourceFile>, AppError> {
+    if !path.is_dir() {
+        let content = read_source(path)?;
+        return Ok(vec![SourceFile {
+            path: path.to_path_buf(),
+            content,
+        }]);
+    }
+
+    let mut paths = Vec::new();
+    collect_rust_files(path, &mut paths)?;
+    if paths.is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "No .rs files found in {}",
+            path.display()
+        )));
+    }
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let content = read_source(&path)?;
+            Ok(SourceFile { path, content })
+        })
+        .collect()
+}
+
+// Recursively collect `.rs` files, skipping build output and hidden directories
+fn collect_rust_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden_or_target = path
+            .file_name()
+            .is_some_and(|name| name == "target" || name.to_string_lossy().starts_with('.'));
+        if hidden_or_target {
+            continue;
+        }
+        if path.is_dir() {
+            collect_rust_files(&path, paths)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Helper function to parse a source file with error recovery, reporting skipped regions on stderr
+fn parse_source(source: &SourceFile) -> ParsedFile {
+    let path = source.path.display().to_string();
+    let (syntax_tree, issues) = diagnostics::parse_lenient(&source.content);
+
+    let unparsed = issues
+        .iter()
+        .map(|issue| {
+            eprint!("{}", diagnostics::render(&path, &source.content, issue));
+            UnparsedRegion {
+                file: path.clone(),
+                start_line: issue.line,
+                end_line: issue.end_line,
+                message: issue.message.clone(),
+            }
+        })
+        .collect();
+
+    ParsedFile {
+        path,
+        syntax_tree,
+        unparsed,
+    }
+}
+
+/// Helper function to validate and read a Rust source file
+fn read_source(file_path: &Path) -> Result<String, AppError> {
     if !file_path.exists() {
         return Err(AppError::InvalidInput(format!(
             "File does not exist: {}", 
@@ -57,42 +327,92 @@ fn source_code_reader() -> Result<String, AppError> {
         )));
     }
     
-    if !file_path.extension().map_or(false, |ext| ext == "rs") {
+    if file_path.extension().is_none_or(|ext| ext != "rs") {
         return Err(AppError::InvalidInput(format!(
             "Invalid file extension for {}, expected .rs file", 
             file_path.display()
         )));
     }
 
-    fs::read_to_string(file_path).map_err(|e| AppError::IoError(e))
+    fs::read_to_string(file_path).map_err(AppError::IoError)
 }
 
 /// Helper function to write the result to user specified location
-fn result_writer(result: AssetInventory) -> Result<(), AppError> {
-    let result_string = result.to_json()
-        .map_err(|e| AppError::SerializationError(e))?;
-    let output_path = Path::new("./asset-inventory.JSON");
-    
+fn result_writer(result: &AssetInventory, format: OutputFormat, output_path: Option<&Path>) -> Result<(), AppError> {
+    let writer = format.writer();
+    let result_string = writer.write(result)?;
+    let output_path = output_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(format!("./asset-inventory.{}", writer.extension())));
+
     fs::write(output_path, result_string)
-        .map_err(|e| AppError::IoError(e))
+        .map_err(AppError::IoError)
+}
+
+/// Source file handed to the parsers
+struct SourceFile {
+    path: PathBuf,
+    content: String,
+}
+
+/// Source file parsed with error recovery
+#[derive(Clone)]
+struct ParsedFile {
+    path: String,
+    syntax_tree: File,
+    // Regions skipped because they could not be parsed
+    unparsed: Vec<UnparsedRegion>,
 }
 
 /// Unified visitor to collect all relevant pallet items
 struct PalletVisitor {
     functions: HashMap<String, String>,                   // (function name, visibility)
     params: Vec<(String, Vec<(String, String)>)>,         // (function name, [(param name, param type)])
-    storage_items: HashMap<String, String>,               // (storage name, visibility)
+    storage_items: HashMap<String, (String, String)>,     // (storage name, (visibility, storage type))
     constants: Vec<String>,                               // constant names
     events: Vec<String>,                                  // event names
     errors: Vec<String>,                                  // error names
+    effects: HashMap<String, effects::Effects>,           // (function name, origin check and storage writes)
+    exposed_traits: Vec<ExposedTrait>,                    // traits implemented for `Pallet<T>`
+    dispatchables: BTreeSet<String>,                      // `#[pallet::call]` and public `decl_module!` functions
+}
+
+/// Trait implemented by the pallet for other pallets to call
+struct ExposedTrait {
+    name: String,
+    methods: Vec<(String, String)>, // (method name, signature)
+    writes: BTreeSet<String>,       // storage written by any of the methods
 }
 
 impl<'ast> Visit<'ast> for PalletVisitor {
     // Extract function information from impl blocks
     fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        // Trait impls for the pallet are cross-pallet entry points, recorded as a whole instead of as helpers
+        if let Some(name) = exposed_trait_name(node) {
+            let mut exposed = ExposedTrait {
+                name,
+                methods: Vec::new(),
+                writes: BTreeSet::new(),
+            };
+            for item in &node.items {
+                if let syn::ImplItem::Fn(method) = item {
+                    exposed
+                        .methods
+                        .push((method.sig.ident.to_string(), types::signature_string(&method.sig)));
+                    exposed.writes.extend(effects::analyse(&method.block).writes);
+                }
+            }
+            self.exposed_traits.push(exposed);
+            return syn::visit::visit_item_impl(self, node);
+        }
+
+        let is_call = node.attrs.iter().any(|attr| has_pallet_constant("pallet::call".to_string(), attr));
         for item in &node.items {
             if let syn::ImplItem::Fn(method) = item {
                 let fn_name = method.sig.ident.to_string();
+                if is_call {
+                    self.dispatchables.insert(fn_name.clone());
+                }
                 let visibility = match &method.vis {
                     syn::Visibility::Public(_) => "public",
                     _ => "private",
@@ -103,12 +423,13 @@ impl<'ast> Visit<'ast> for PalletVisitor {
                     if let syn::FnArg::Typed(pat_type) = param {
                         if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
                             let param_name = pat_ident.ident.to_string();
-                            let param_type = quote!(#pat_type).to_string();
-                            param_info.push((param_name, param_type));
+                            let ty = &pat_type.ty;
+                            param_info.push((param_name, quote!(#ty).to_string()));
                         }
                     }
                 }
 
+                self.effects.insert(fn_name.clone(), effects::analyse(&method.block));
                 self.functions.insert(fn_name.clone(), visibility.to_string());
                 self.params.push((fn_name, param_info));
             }
@@ -128,7 +449,11 @@ impl<'ast> Visit<'ast> for PalletVisitor {
                                 syn::Visibility::Public(_) => "public",
                                 _ => "private",
                             };
-                            self.storage_items.insert(storage_name, visibility.to_string());
+                            let ty = &storage_type.ty;
+                            self.storage_items.insert(
+                                storage_name,
+                                (visibility.to_string(), types::type_string(ty)),
+                            );
                         }
                     }
                 }
@@ -148,6 +473,12 @@ impl<'ast> Visit<'ast> for PalletVisitor {
         }
     }
 
+    // Extract items hidden inside FRAME v1 `decl_*!` macros
+    fn visit_item_macro(&mut self, node: &'ast syn::ItemMacro) {
+        legacy::visit_decl_macro(self, &node.mac);
+        syn::visit::visit_item_macro(self, node);
+    }
+
     // Extract events and errors from enums
     fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
         let is_event = node.attrs.iter().any(|attr| has_pallet_constant("pallet::event".to_string(), attr));
@@ -165,9 +496,7 @@ impl<'ast> Visit<'ast> for PalletVisitor {
 }
 
 /// Parse source code with unified visitor
-fn parser(code: String) -> Result<AssetInventory, AppError> {
-    let syntax_tree: File = syn::parse_file(&code)?;
-
+fn parser(files: &[ParsedFile]) -> Result<AssetInventory, AppError> {
     // Initialize unified visitor
     let mut visitor = PalletVisitor {
         functions: HashMap::new(),
@@ -176,73 +505,139 @@ fn parser(code: String) -> Result<AssetInventory, AppError> {
         constants: Vec::new(),
         events: Vec::new(),
         errors: Vec::new(),
+        effects: HashMap::new(),
+        exposed_traits: Vec::new(),
+        dispatchables: BTreeSet::new(),
     };
 
-    // Visit all items in the file
-    visitor.visit_file(&syntax_tree);
+    // Mocks, tests, benchmarks and weights are not part of the runtime: benchmarks are only read by the benchmark
+    // pass, mocks and tests only by the coverage pass
+    let runtime_files: Vec<ParsedFile> = files
+        .iter()
+        .filter(|file| !bulk::is_non_runtime_file(&file.path))
+        .cloned()
+        .collect();
+    let benchmark_files: Vec<ParsedFile> = files
+        .iter()
+        .filter(|file| !bulk::is_test_file(&file.path))
+        .cloned()
+        .collect();
+
+    // Visit all items in every runtime file
+    for file in &runtime_files {
+        visitor.visit_file(&file.syntax_tree);
+    }
 
-    let mut asset_inventory = AssetInventory { assets: Vec::new() };
+    let mut asset_inventory = AssetInventory {
+        source: None,
+        assets: Vec::new(),
+        findings: Vec::new(),
+        unparsed: Vec::new(),
+    };
 
     // Convert visitor data into assets
+    let aliases = types::TypeAliases::collect(&runtime_files);
     for (function, params) in visitor.params {
+        let params = params
+            .iter()
+            .map(|(name, ty)| aliases.parameter(name, ty))
+            .collect();
         let visibility = visitor.functions.get(&function).unwrap();
         let category = if visibility == "public" {
             AssetCategory::PublicFunction(function.clone(), params)
         } else {
             AssetCategory::Helper(function.clone(), params)
         };
-        asset_inventory.assets.push(Asset {
-            visibility: visibility.to_string(),
-            name: function.clone(),
-            category,
-        });
+        let mut asset = Asset::new(visibility, &function, category);
+        if visitor.dispatchables.contains(&function) {
+            asset = asset.with_attribute("dispatchable", "yes");
+        }
+
+        // Writes to anything but the pallet's own storage items are not tracked
+        if let Some(effects) = visitor.effects.get(&function) {
+            if let Some(origin) = effects.origin {
+                asset = asset.with_attribute("origin", origin);
+            }
+            let writes: Vec<&str> = effects
+                .writes
+                .iter()
+                .filter(|storage| visitor.storage_items.contains_key(*storage))
+                .map(String::as_str)
+                .collect();
+            if !writes.is_empty() {
+                asset = asset.with_attribute("writes", writes.join(","));
+            }
+            if !effects.external_calls.is_empty() {
+                let calls: Vec<&str> = effects.external_calls.iter().map(String::as_str).collect();
+                asset = asset.with_attribute("external_calls", calls.join(","));
+            }
+            if !effects.allow_death.is_empty() {
+                let calls: Vec<&str> = effects.allow_death.iter().map(String::as_str).collect();
+                asset = asset.with_attribute("allow_death", calls.join(","));
+            }
+        }
+        asset_inventory.assets.push(asset);
+    }
+
+    // Other pallets call these without any origin check
+    for exposed in visitor.exposed_traits {
+        let writes: Vec<&str> = exposed
+            .writes
+            .iter()
+            .filter(|storage| visitor.storage_items.contains_key(*storage))
+            .map(String::as_str)
+            .collect();
+        let category = AssetCategory::ExposedTrait(exposed.name.clone(), exposed.methods);
+        let mut asset = Asset::new("public", &exposed.name, category);
+        if !writes.is_empty() {
+            asset = asset.with_attribute("writes", writes.join(","));
+        }
+        asset_inventory.assets.push(asset);
     }
 
     // Parse visitor type into Asset type
-    for (storage_item, visibility) in visitor.storage_items {
+    for (storage_item, (visibility, storage_type)) in visitor.storage_items {
         let category = AssetCategory::Storage(storage_item.clone(), visibility.clone());
-        asset_inventory.assets.push(Asset {
-            visibility: visibility.to_string(),
-            name: storage_item.clone(),
-            category,
-        });
+        asset_inventory.assets.push(
+            Asset::new(&visibility, &storage_item, category).with_attribute("type", storage_type),
+        );
     }
 
     // Parse visitor type into Asset type
     for constant in visitor.constants {
         let category = AssetCategory::Constant(constant.clone());
-        asset_inventory.assets.push(Asset {
-            visibility: "none".to_string(),
-            name: constant.clone(),
-            category,
-        });
+        asset_inventory.assets.push(Asset::new("none", &constant, category));
     }
 
     // Parse visitor type into Asset type
     for event in visitor.events {
         let category = AssetCategory::Events(event.clone());
-        asset_inventory.assets.push(Asset {
-            visibility: "public".to_string(),
-            name: event.clone(),
-            category,
-        });
+        asset_inventory.assets.push(Asset::new("public", &event, category));
     }
 
     // Parse visitor type into Asset type
     for error in visitor.errors {
         let category = AssetCategory::Error(error.clone());
-        asset_inventory.assets.push(Asset {
-            visibility: "public".to_string(),
-            name: error.clone(),
-            category,
-        });
+        asset_inventory.assets.push(Asset::new("public", &error, category));
     }
 
+    // Storage versions and migrations live outside the visitor above
+    migrations::collect(&runtime_files, &mut asset_inventory);
+    for file in files {
+        asset_inventory.unparsed.extend(file.unparsed.iter().cloned());
+    }
+    storage_bounds::collect(&runtime_files, &mut asset_inventory);
+    iteration::collect(&runtime_files, &mut asset_inventory);
+    coupling::collect(&runtime_files, &mut asset_inventory);
+    xcm::collect(&runtime_files, &mut asset_inventory);
+    benchmarks::collect(&benchmark_files, &mut asset_inventory);
+    coverage::collect(files, &mut asset_inventory);
+
     Ok(asset_inventory)
 }
 
 /// Asset Category
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 enum AssetCategory {
     /// Point of interest:
     /// 1. Sensitive data handling (e.g. balances, access control lists, etc.)
@@ -259,14 +654,14 @@ enum AssetCategory {
     /// # Arguments
     /// * `String` - The name of the event
     Events(String),
-    /// Point of interest:
-    /// 1. Custom types that handle sensitive data
-    /// 2. Enums that determine state transitions
-    /// 3. Composite types containing priviledged information
-    ///
-    /// # Arguments
-    /// * `String` - The name of the custom type
-    /// * `String` - The purpose of the custom type
+    // Point of interest:
+    // 1. Custom types that handle sensitive data
+    // 2. Enums that determine state transitions
+    // 3. Composite types containing priviledged information
+    //
+    // # Arguments
+    // * `String` - The name of the custom type
+    // * `String` - The purpose of the custom type
     // CustomType(String, String),
     /// Point of interest:
     /// 1. Constants that define security thresholds
@@ -275,11 +670,11 @@ enum AssetCategory {
     /// # Arguments
     /// * `String` - The name of the constant
     Constant(String),
-    /// Point of interest:
-    /// 1. Weight calculations and resource limits
-    ///
-    /// # Arguments
-    /// * `String` - The name of the function for which the weight is defined
+    // Point of interest:
+    // 1. Weight calculations and resource limits
+    //
+    // # Arguments
+    // * `String` - The name of the function for which the weight is defined
     //Weight(String),
     /// Point of interest:
     /// 1. Internal state leak through error handling
@@ -289,32 +684,165 @@ enum AssetCategory {
     Error(String),
     /// # Arguments
     /// * `String` - The name of the public function
-    /// * `Vec<(String, String)>` - The parameters of the public function,
-    ///   where the first string is the parameter name, and the second string is the parameter type
-    PublicFunction(String, Vec<(String, String)>),
+    /// * `Vec<Parameter>` - The parameters of the public function
+    PublicFunction(String, Vec<Parameter>),
     /// Point of interest:
     /// 1. Internal helper functions handling priviledged operations
     /// 2. Validation logic
     ///
     /// # Arguments
     /// * `String` - The name of the helper function
-    /// * `Vec<(String, String)>` - The parameters of the helper function,
-    ///   where the first string is the parameter name, and the second string is the parameter type
-    Helper(String, Vec<(String, String)>),
+    /// * `Vec<Parameter>` - The parameters of the helper function
+    Helper(String, Vec<Parameter>),
+    /// Point of interest:
+    /// 1. Privileged cross-pallet entry points, e.g. `fungibles::Mutate`, that bypass origin checks
+    /// 2. Storage written on behalf of other pallets
+    ///
+    /// # Arguments
+    /// * `String` - The path of the implemented trait, e.g. `fungibles::Mutate<T::AccountId>`
+    /// * `Vec<(String, String)>` - The methods of the impl, where the first string is the method name,
+    ///   and the second string is its signature
+    ExposedTrait(String, Vec<(String, String)>),
+    /// Point of interest:
+    /// 1. Value flows into other pallets, e.g. transfers, reserves and slashes through `T::Currency`
+    /// 2. Assumptions about another pallet's behaviour that the runtime may not uphold
+    ///
+    /// # Arguments
+    /// * `String` - The coupled pallet, e.g. `pallet_balances`, or the Config type it is reached through,
+    ///   e.g. `Currency`
+    /// * `String` - The kind of coupling: `tight` through a Config supertrait or direct calls, `loose` through
+    ///   a Config type
+    Dependency(String, String),
+    /// Point of interest:
+    /// 1. Barriers admitting unpaid or unauthenticated messages
+    /// 2. Reserve and teleport trust that lets another chain mint assets here
+    /// 3. Pallets sending or executing XCM on behalf of their callers
+    ///
+    /// # Arguments
+    /// * `String` - The config item, e.g. `Barrier`, or the function sending or executing XCM
+    /// * `String` - The kind: `executor_config`, `pallet_xcm_config`, `send` or `execute`
+    Xcm(String, String),
+    /// Point of interest:
+    /// 1. Weights measured below the worst case: short component ranges or a setup leaving storage empty
+    /// 2. Dispatchables without any benchmark, running on a guessed weight
+    ///
+    /// # Arguments
+    /// * `String` - The name of the benchmark
+    /// * `String` - The dispatchables it measures, comma separated, empty when none could be identified
+    Benchmark(String, String),
+    /// Point of interest:
+    /// 1. Storage layout changes that are not reflected by a version bump
+    ///
+    /// # Arguments
+    /// * `String` - The in-code storage version declared through `#[pallet::storage_version]`
+    StorageVersion(String),
+    /// Point of interest:
+    /// 1. Migrations that can run twice or corrupt storage on upgrade
+    /// 2. Migrations that cannot be verified with try-runtime
+    ///
+    /// # Arguments
+    /// * `String` - The name of the migration type
+    /// * `String` - How the migration is hooked in: `OnRuntimeUpgrade`, `UncheckedOnRuntimeUpgrade`,
+    ///   `VersionedMigration` or `Hooks`
+    Migration(String, String),
+    /// Point of interest:
+    /// 1. Pallets whose Call part exposes dispatchables to users
+    /// 2. Pallet indices that must stay stable across upgrades
+    ///
+    /// # Arguments
+    /// * `String` - The name of the pallet in the runtime
+    /// * `String` - The path of the pallet crate, e.g. `pallet_balances`
+    /// * `Vec<String>` - The explicitly included parts, empty when the parts are implicit
+    RuntimePallet(String, String, Vec<String>),
+    /// Point of interest:
+    /// 1. Security thresholds chosen by the runtime, e.g. deposits and limits
+    /// 2. Cross-pallet wiring through associated types, e.g. `type Currency = Balances`
+    ///
+    /// # Arguments
+    /// * `String` - The path of the pallet the Config belongs to
+    /// * `String` - The name of the Config item
+    /// * `String` - The concrete type or value bound to the item
+    ConfigItem(String, String, String),
 }
 
 /// Asset Data Structure
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Asset {
     visibility: String,
     name: String,
     category: AssetCategory,
+    /// Facts derived by the analysis passes, e.g. the declared type of a storage item
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    attributes: BTreeMap<String, String>,
+}
+
+impl Asset {
+    fn new(visibility: &str, name: &str, category: AssetCategory) -> Self {
+        Asset {
+            visibility: visibility.to_string(),
+            name: name.to_string(),
+            category,
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    fn with_attribute(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.attributes.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+/// Parameter Data Structure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Parameter {
+    name: String,
+    // Type as written, without token spacing, e.g. `BalanceOf<T>`
+    param_type: String,
+    // Type after following local `type` aliases, when it differs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resolved: Option<String>,
+    // Underlying primitive when the type resolves to primitives only, e.g. `u128` or `Vec<u8>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    primitive: Option<String>,
+}
+
+/// Finding Data Structure
+/// A pattern spotted during discovery that needs an auditor's attention
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Finding {
+    // Stable identifier of the rule that raised the finding, e.g. "storage-changed-without-migration"
+    rule: String,
+    // Name of the asset the finding refers to
+    asset: String,
+    message: String,
+    // Source file and line the finding points at, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+}
+
+/// Unparsed Region Data Structure
+/// Part of a source file skipped because it could not be parsed, its assets are missing from the inventory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UnparsedRegion {
+    file: String,
+    start_line: usize,
+    end_line: usize,
+    message: String,
 }
 
 /// Asset Inventory Data Structure
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct AssetInventory {
+    // Path of the analysed source file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
     assets: Vec<Asset>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    findings: Vec<Finding>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    unparsed: Vec<UnparsedRegion>,
 }
 
 impl AssetInventory {
@@ -337,6 +865,57 @@ fn has_pallet_constant(name: String, attrs: &Attribute) -> bool {
     path_str == name
 }
 
+// Trait path of an `impl Trait for Pallet<T>` block, FRAME hooks and the genesis build are left to other passes
+fn exposed_trait_name(node: &syn::ItemImpl) -> Option<String> {
+    let (_, trait_path, _) = node.trait_.as_ref()?;
+    let syn::Type::Path(self_ty) = &*node.self_ty else {
+        return None;
+    };
+    let self_name = self_ty.path.segments.last()?.ident.to_string();
+    let trait_name = trait_path.segments.last()?.ident.to_string();
+    let is_pallet = self_name == "Pallet" || self_name == "Module";
+    if !is_pallet || FRAME_HOOKS.contains(&trait_name.as_str()) {
+        return None;
+    }
+    Some(types::path_string(trait_path))
+}
+
+// Split a token stream on a top level punctuation character
+fn split_tokens(tokens: TokenStream, separator: char) -> Vec<Vec<TokenTree>> {
+    let mut entries = vec![Vec::new()];
+    for token in tokens {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == separator => entries.push(Vec::new()),
+            _ => entries.last_mut().unwrap().push(token),
+        }
+    }
+    entries.retain(|entry| !entry.is_empty());
+    entries
+}
+
+// Every identifier of a token stream, nested groups included
+fn flatten_idents(tokens: TokenStream, idents: &mut Vec<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => idents.push(ident.to_string()),
+            TokenTree::Group(group) => flatten_idents(group.stream(), idents),
+            _ => {}
+        }
+    }
+}
+
+// Drop leading `#[..]` attributes such as `#[cfg(feature = "runtime-benchmarks")]`
+fn skip_attributes(tokens: Vec<TokenTree>) -> Vec<TokenTree> {
+    let mut start = 0;
+    while let (Some(TokenTree::Punct(punct)), Some(TokenTree::Group(_))) = (tokens.get(start), tokens.get(start + 1)) {
+        if punct.as_char() != '#' {
+            break;
+        }
+        start += 2;
+    }
+    tokens[start..].to_vec()
+}
+
 // ----------------------------------------------Robust Error Handling----------------------------------------------
 
 // Custom error type for the application
@@ -345,6 +924,8 @@ enum AppError {
     IoError(std::io::Error),
     ParseError(syn::Error),
     SerializationError(serde_json::Error),
+    FormatError(String),
+    WatchError(notify::Error),
     InvalidInput(String),
 }
 
@@ -354,6 +935,8 @@ impl std::fmt::Display for AppError {
             AppError::IoError(err) => write!(f, "IO Error: {}", err),
             AppError::ParseError(err) => write!(f, "Parse Error: {}", err),
             AppError::SerializationError(err) => write!(f, "Serialization Error: {}", err),
+            AppError::FormatError(msg) => write!(f, "Format Error: {}", msg),
+            AppError::WatchError(err) => write!(f, "Watch Error: {}", err),
             AppError::InvalidInput(msg) => write!(f, "Invalid Input: {}", msg),
         }
     }
@@ -366,7 +949,8 @@ impl std::error::Error for AppError {
             AppError::IoError(err) => Some(err),
             AppError::ParseError(err) => Some(err),
             AppError::SerializationError(err) => Some(err),
-            AppError::InvalidInput(_) => None,
+            AppError::WatchError(err) => Some(err),
+            AppError::FormatError(_) | AppError::InvalidInput(_) => None,
         }
     }
 }
@@ -388,3 +972,89 @@ impl From<serde_json::Error> for AppError {
         AppError::SerializationError(err)
     }
 }
+
+impl From<notify::Error> for AppError {
+    fn from(err: notify::Error) -> Self {
+        AppError::WatchError(err)
+    }
+}
+
+// Inventory of a single source file, for the tests of the analysis passes
+#[cfg(test)]
+fn inventory_of(code: &str) -> AssetInventory {
+    inventory_of_files(&[("lib.rs", code)])
+}
+
+#[cfg(test)]
+fn inventory_of_files(files: &[(&str, &str)]) -> AssetInventory {
+    let files: Vec<ParsedFile> = files
+        .iter()
+        .map(|(path, code)| {
+            parse_source(&SourceFile {
+                path: PathBuf::from(path),
+                content: code.to_string(),
+            })
+        })
+        .collect();
+    parser(&files).expect("source parses")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mocks_and_tests_are_not_assets() {
+        let inventory = inventory_of_files(&[
+            ("src/lib.rs", "impl<T: Config> Pallet<T> { pub fn transfer(origin: OriginFor<T>) {} }"),
+            ("src/mock.rs", "impl Test { pub fn mock_public_fn() {} }"),
+            ("src/tests.rs", "impl Test { pub fn test_helper() {} }"),
+            ("tests/integration.rs", "impl Test { pub fn integration_helper() {} }"),
+        ]);
+
+        let names: Vec<&str> = inventory.assets.iter().map(|asset| asset.name.as_str()).collect();
+        assert_eq!(names, ["transfer"]);
+    }
+
+    #[test]
+    fn mocks_are_left_out_of_every_runtime_pass() {
+        let inventory = inventory_of_files(&[
+            (
+                "src/lib.rs",
+                r#"
+                type Balance = u128;
+                impl<T: Config> Pallet<T> { pub fn transfer(amount: Balance) {} }
+                "#,
+            ),
+            (
+                "src/mock.rs",
+                r#"
+                type Balance = u64;
+                impl xcm_executor::Config for XcmConfig {
+                    type Barrier = AllowUnpaidExecutionFrom<Everything>;
+                    type IsTeleporter = Everything;
+                }
+                "#,
+            ),
+        ]);
+
+        assert!(inventory.findings.is_empty());
+        assert!(!inventory.assets.iter().any(|asset| matches!(asset.category, AssetCategory::Xcm(..))));
+        let transfer = inventory.assets.iter().find(|asset| asset.name == "transfer").unwrap();
+        let AssetCategory::PublicFunction(_, params) = &transfer.category else {
+            panic!("`transfer` is a public function");
+        };
+        assert_eq!(params[0].primitive.as_deref(), Some("u128"));
+    }
+
+    #[test]
+    fn crates_below_a_tests_directory_are_analysed() {
+        let inventory = inventory_of_files(&[
+            ("tests/fixtures/pallet/src/lib.rs", "impl<T: Config> Pallet<T> { pub fn transfer() {} }"),
+            ("tests/fixtures/pallet/src/tests/mod.rs", "impl Test { pub fn test_helper() {} }"),
+        ]);
+
+        let names: Vec<&str> = inventory.assets.iter().map(|asset| asset.name.as_str()).collect();
+        assert_eq!(names, ["transfer"]);
+    }
+}
@@ -0,0 +1,431 @@
+use crate::{has_pallet_constant, Asset, AssetCategory, AssetInventory, Finding, ParsedFile};
+use quote::quote;
+use std::collections::{HashMap, HashSet};
+use syn::visit::Visit;
+
+// ----------------------------------------------Storage Migrations-------------------------------------------------
+// Broken migrations are the riskiest part of a runtime upgrade. This pass records the in-code storage version,
+// every migration hooked into the upgrade path and whether it can be verified with try-runtime.
+
+const TRY_RUNTIME_HOOKS: [&str; 2] = ["pre_upgrade", "post_upgrade"];
+
+/// Migration discovered in the source
+struct Migration {
+    name: String,
+    kind: &'static str,
+    try_runtime_hooks: Vec<String>,
+    // Whether the migration reads the on-chain storage version before touching storage
+    checks_on_chain_version: bool,
+    // Only set for `VersionedMigration` wrappers: (from version, to version, inner migration)
+    versioned: Option<(String, String, String)>,
+    file: String,
+    line: usize,
+}
+
+/// Visitor collecting storage version declarations and migrations
+#[derive(Default)]
+struct MigrationVisitor {
+    file: String,                                         // file being visited
+    version_consts: HashMap<(String, String), String>,    // ((file, const name), version number)
+    storage_versions: Vec<(String, String)>,              // (file, argument of `#[pallet::storage_version(..)]`)
+    migrations: Vec<Migration>,
+}
+
+impl<'ast> Visit<'ast> for MigrationVisitor {
+    // `const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);`
+    fn visit_item_const(&mut self, node: &'ast syn::ItemConst) {
+        if type_name(&node.ty).as_deref() == Some("StorageVersion") {
+            if let Some(version) = storage_version_number(&node.expr) {
+                self.version_consts.insert((self.file.clone(), node.ident.to_string()), version);
+            }
+        }
+        syn::visit::visit_item_const(self, node);
+    }
+
+    // `#[pallet::storage_version(STORAGE_VERSION)] pub struct Pallet<T>(_);`
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        for attr in &node.attrs {
+            if has_pallet_constant("pallet::storage_version".to_string(), attr) {
+                if let Ok(expr) = attr.parse_args::<syn::Expr>() {
+                    self.storage_versions.push((self.file.clone(), quote!(#expr).to_string()));
+                }
+            }
+        }
+        syn::visit::visit_item_struct(self, node);
+    }
+
+    // `impl OnRuntimeUpgrade for X`, `impl UncheckedOnRuntimeUpgrade for X` and `on_runtime_upgrade` in hooks
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        if let Some((_, trait_path, _)) = &node.trait_ {
+            let trait_name = trait_path.segments.last().map(|seg| seg.ident.to_string());
+            let fn_names: Vec<String> = node
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    syn::ImplItem::Fn(method) => Some(method.sig.ident.to_string()),
+                    _ => None,
+                })
+                .collect();
+
+            let kind = match trait_name.as_deref() {
+                Some("OnRuntimeUpgrade") => Some("OnRuntimeUpgrade"),
+                Some("UncheckedOnRuntimeUpgrade") => Some("UncheckedOnRuntimeUpgrade"),
+                Some("Hooks") if fn_names.iter().any(|name| name == "on_runtime_upgrade") => Some("Hooks"),
+                _ => None,
+            };
+
+            if let Some(kind) = kind {
+                let name = match kind {
+                    "Hooks" => "on_runtime_upgrade".to_string(),
+                    _ => type_name(&node.self_ty).unwrap_or_default(),
+                };
+                let body = quote!(#node).to_string();
+                self.migrations.push(Migration {
+                    name,
+                    kind,
+                    try_runtime_hooks: fn_names
+                        .into_iter()
+                        .filter(|name| TRY_RUNTIME_HOOKS.contains(&name.as_str()))
+                        .collect(),
+                    checks_on_chain_version: body.contains("on_chain_storage_version"),
+                    versioned: None,
+                    file: self.file.clone(),
+                    line: node.impl_token.span.start().line,
+                });
+            }
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+
+    // `pub type MigrateV0ToV1<T> = VersionedMigration<0, 1, InnerMigrateV0ToV1<T>, Pallet<T>, DbWeight>;`
+    fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
+        if let syn::Type::Path(type_path) = &*node.ty {
+            if let Some(segment) = type_path.path.segments.last() {
+                if segment.ident == "VersionedMigration" {
+                    if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                        let args: Vec<String> = args
+                            .args
+                            .iter()
+                            .map(|arg| match arg {
+                                syn::GenericArgument::Type(ty) => {
+                                    type_name(ty).unwrap_or_else(|| quote!(#ty).to_string())
+                                }
+                                other => quote!(#other).to_string(),
+                            })
+                            .collect();
+                        if args.len() >= 3 {
+                            self.migrations.push(Migration {
+                                name: node.ident.to_string(),
+                                kind: "VersionedMigration",
+                                try_runtime_hooks: Vec::new(),
+                                checks_on_chain_version: true,
+                                versioned: Some((args[0].clone(), args[1].clone(), args[2].clone())),
+                                file: self.file.clone(),
+                                line: node.type_token.span.start().line,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        syn::visit::visit_item_type(self, node);
+    }
+}
+
+/// Collect storage version and migration assets, and flag migrations that are risky on their own
+///
+/// Runs over all files at once: a `VersionedMigration` often wraps a migration written in another file.
+pub(crate) fn collect(files: &[ParsedFile], inventory: &mut AssetInventory) {
+    let mut visitor = MigrationVisitor::default();
+    for file in files {
+        visitor.file = file.path.clone();
+        visitor.visit_file(&file.syntax_tree);
+    }
+
+    for (file, declared) in &visitor.storage_versions {
+        // Resolve `STORAGE_VERSION` to the number it was constructed with, preferably from the same file
+        let version = visitor
+            .version_consts
+            .get(&(file.clone(), declared.clone()))
+            .or_else(|| {
+                visitor
+                    .version_consts
+                    .iter()
+                    .find_map(|((_, name), version)| (name == declared).then_some(version))
+            })
+            .cloned()
+            .or_else(|| syn::parse_str::<syn::Expr>(declared).ok().and_then(|e| storage_version_number(&e)))
+            .unwrap_or_else(|| declared.clone());
+        inventory
+            .assets
+            .push(Asset::new("none", declared, AssetCategory::StorageVersion(version)));
+    }
+
+    // Inner migrations wrapped by a `VersionedMigration` are guarded by the wrapper
+    let wrapped: Vec<String> = visitor
+        .migrations
+        .iter()
+        .filter_map(|migration| migration.versioned.as_ref().map(|(_, _, inner)| inner.clone()))
+        .collect();
+
+    for migration in &visitor.migrations {
+        let mut asset = Asset::new(
+            "none",
+            &migration.name,
+            AssetCategory::Migration(migration.name.clone(), migration.kind.to_string()),
+        );
+        if let Some((from, to, inner)) = &migration.versioned {
+            asset = asset
+                .with_attribute("from_version", from.as_str())
+                .with_attribute("to_version", to.as_str())
+                .with_attribute("wraps", inner.as_str());
+        } else {
+            let hooks = if migration.try_runtime_hooks.is_empty() {
+                "none".to_string()
+            } else {
+                migration.try_runtime_hooks.join(",")
+            };
+            asset = asset.with_attribute("try_runtime", hooks);
+        }
+        inventory.assets.push(asset);
+
+        if migration.versioned.is_some() {
+            continue;
+        }
+
+        let missing: Vec<&str> = TRY_RUNTIME_HOOKS
+            .iter()
+            .copied()
+            .filter(|hook| !migration.try_runtime_hooks.iter().any(|h| h == hook))
+            .collect();
+        if !missing.is_empty() {
+            inventory.findings.push(Finding {
+                rule: "migration-without-try-runtime-checks".to_string(),
+                asset: migration.name.clone(),
+                message: format!(
+                    "{} migration `{}` does not implement {}, so it cannot be verified with try-runtime",
+                    migration.kind,
+                    migration.name,
+                    missing.join(" and ")
+                ),
+                file: Some(migration.file.clone()),
+                line: Some(migration.line),
+            });
+        }
+
+        let guarded = migration.checks_on_chain_version || wrapped.contains(&migration.name);
+        if migration.kind != "UncheckedOnRuntimeUpgrade" && !guarded {
+            inventory.findings.push(Finding {
+                rule: "unversioned-migration".to_string(),
+                asset: migration.name.clone(),
+                message: format!(
+                    "{} migration `{}` never reads the on-chain storage version and may run more than once",
+                    migration.kind, migration.name
+                ),
+                file: Some(migration.file.clone()),
+                line: Some(migration.line),
+            });
+        }
+    }
+}
+
+/// Compare two versions of a pallet and report storage items whose type changed
+/// without a storage version bump or a migration
+pub(crate) fn compare_versions(previous: &AssetInventory, current: &AssetInventory) -> Vec<Finding> {
+    let previous_types = storage_types(previous);
+    let current_types = storage_types(current);
+
+    let version_bumped = storage_version(previous) != storage_version(current);
+    // Migrations shipped with an earlier release already ran and do not cover this change
+    let previous_migrations = migrations(previous);
+    let has_migration = migrations(current)
+        .iter()
+        .any(|migration| !previous_migrations.contains(migration));
+
+    let mut findings = Vec::new();
+    for (name, old_type) in &previous_types {
+        let Some(new_type) = current_types.get(name) else {
+            continue;
+        };
+        if old_type == new_type || (version_bumped && has_migration) {
+            continue;
+        }
+
+        let missing = match (version_bumped, has_migration) {
+            (false, false) => "a storage version bump or a migration",
+            (false, true) => "a storage version bump",
+            _ => "a migration",
+        };
+        findings.push(Finding {
+            rule: "storage-changed-without-migration".to_string(),
+            asset: name.clone(),
+            message: format!(
+                "Storage item `{}` changed from `{}` to `{}` without {}",
+                name, old_type, new_type, missing
+            ),
+            file: None,
+            line: None,
+        });
+    }
+    findings
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+fn storage_types(inventory: &AssetInventory) -> HashMap<String, String> {
+    inventory
+        .assets
+        .iter()
+        .filter(|asset| matches!(asset.category, AssetCategory::Storage(..)))
+        .filter_map(|asset| {
+            asset
+                .attributes
+                .get("type")
+                .map(|ty| (asset.name.clone(), ty.clone()))
+        })
+        .collect()
+}
+
+fn migrations(inventory: &AssetInventory) -> HashSet<&str> {
+    inventory
+        .assets
+        .iter()
+        .filter_map(|asset| match &asset.category {
+            AssetCategory::Migration(name, _) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn storage_version(inventory: &AssetInventory) -> Option<&str> {
+    inventory.assets.iter().find_map(|asset| match &asset.category {
+        AssetCategory::StorageVersion(version) => Some(version.as_str()),
+        _ => None,
+    })
+}
+
+// Last path segment of a type, e.g. `InnerMigrateV0ToV1` for `v1::InnerMigrateV0ToV1<T>`
+fn type_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(type_path) => type_path.path.segments.last().map(|seg| seg.ident.to_string()),
+        _ => None,
+    }
+}
+
+// Version number out of `StorageVersion::new(N)`
+fn storage_version_number(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Call(call) => match (&*call.func, call.args.first()) {
+            (syn::Expr::Path(func), Some(arg)) if func.path.segments.last()?.ident == "new" => {
+                Some(quote!(#arg).to_string())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare_versions;
+    use crate::{inventory_of, inventory_of_files, AssetCategory, AssetInventory};
+
+    const LIB: &str = r#"
+        #[frame_support::pallet]
+        pub mod pallet {
+            const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+            #[pallet::pallet]
+            #[pallet::storage_version(STORAGE_VERSION)]
+            pub struct Pallet<T>(_);
+
+            #[pallet::storage]
+            pub type Items<T> = StorageValue<_, BoundedVec<u64, ConstU32<8>>>;
+        }
+    "#;
+
+    const MIGRATION: &str = r#"
+        pub struct InnerMigrateV0ToV1<T>(PhantomData<T>);
+        impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV0ToV1<T> {
+            fn on_runtime_upgrade() -> Weight { Weight::zero() }
+        }
+
+        pub struct Cleanup<T>(PhantomData<T>);
+        impl<T: Config> OnRuntimeUpgrade for Cleanup<T> {
+            fn on_runtime_upgrade() -> Weight { Weight::zero() }
+        }
+    "#;
+
+    fn rules(inventory: &AssetInventory) -> Vec<(&str, &str)> {
+        inventory
+            .findings
+            .iter()
+            .map(|finding| (finding.rule.as_str(), finding.asset.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn versioned_migrations_guard_migrations_from_other_files() {
+        let versioned = "pub type MigrateV0ToV1<T> =
+            VersionedMigration<0, 1, v1::InnerMigrateV0ToV1<T>, Pallet<T>, ()>;";
+        let inventory =
+            inventory_of_files(&[("lib.rs", LIB), ("migrations/v1.rs", MIGRATION), ("migrations.rs", versioned)]);
+
+        let migrations: Vec<(&str, &str)> = inventory
+            .assets
+            .iter()
+            .filter_map(|asset| match &asset.category {
+                AssetCategory::Migration(name, kind) => Some((name.as_str(), kind.as_str())),
+                AssetCategory::StorageVersion(version) => Some((asset.name.as_str(), version.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            migrations,
+            [
+                ("STORAGE_VERSION", "1"),
+                ("InnerMigrateV0ToV1", "UncheckedOnRuntimeUpgrade"),
+                ("Cleanup", "OnRuntimeUpgrade"),
+                ("MigrateV0ToV1", "VersionedMigration"),
+            ]
+        );
+        assert_eq!(
+            rules(&inventory),
+            [
+                ("migration-without-try-runtime-checks", "InnerMigrateV0ToV1"),
+                ("migration-without-try-runtime-checks", "Cleanup"),
+                ("unversioned-migration", "Cleanup"),
+            ]
+        );
+        assert!(inventory.findings.iter().all(|finding| finding.file.as_deref() == Some("migrations/v1.rs")));
+    }
+
+    #[test]
+    fn only_new_migrations_cover_a_storage_change() {
+        let changed = |version: u32, with_migration: bool| {
+            let lib = LIB
+                .replace("BoundedVec<u64, ConstU32<8>>", "BoundedVec<u128, ConstU32<8>>")
+                .replace("StorageVersion::new(1)", &format!("StorageVersion::new({version})"));
+            let migration = match with_migration {
+                true => "pub struct MigrateV1ToV2; impl OnRuntimeUpgrade for MigrateV1ToV2 {}",
+                false => "",
+            };
+            inventory_of(&format!("{lib}\n{MIGRATION}\n{migration}"))
+        };
+        let previous = inventory_of(&format!("{LIB}\n{MIGRATION}"));
+        let missing = |current: &AssetInventory| -> Vec<String> {
+            compare_versions(&previous, current)
+                .into_iter()
+                .map(|finding| finding.message)
+                .collect()
+        };
+
+        // The migrations of the previous release do not count
+        let message = "Storage item `Items` changed from `StorageValue<_, BoundedVec<u64, ConstU32<8>>>` to \
+                       `StorageValue<_, BoundedVec<u128, ConstU32<8>>>` without";
+        assert_eq!(missing(&changed(2, false)), [format!("{message} a migration")]);
+        assert_eq!(missing(&changed(1, true)), [format!("{message} a storage version bump")]);
+        assert!(missing(&changed(2, true)).is_empty());
+        assert!(missing(&previous).is_empty());
+    }
+}
@@ -0,0 +1,451 @@
+use crate::{AppError, Asset, AssetCategory, AssetInventory};
+use clap::ValueEnum;
+use serde_json::json;
+use std::path::Path;
+
+// ----------------------------------------------Output Formats-----------------------------------------------------
+// Every format is a writer turning the inventory into a string, main only decides where the string goes.
+
+/// Output format selectable on the command line
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Compact JSON, the format read by threat-modeling
+    #[default]
+    Json,
+    /// Indented JSON for humans
+    PrettyJson,
+    Yaml,
+    /// One row per asset
+    Csv,
+    /// Asset and finding tables
+    Markdown,
+    /// SARIF 2.1.0 log of the findings, for code-scanning viewers
+    Sarif,
+}
+
+impl OutputFormat {
+    pub(crate) fn writer(self) -> Box<dyn InventoryWriter> {
+        match self {
+            OutputFormat::Json => Box::new(JsonWriter { pretty: false }),
+            OutputFormat::PrettyJson => Box::new(JsonWriter { pretty: true }),
+            OutputFormat::Yaml => Box::new(YamlWriter),
+            OutputFormat::Csv => Box::new(CsvWriter),
+            OutputFormat::Markdown => Box::new(MarkdownWriter),
+            OutputFormat::Sarif => Box::new(SarifWriter),
+        }
+    }
+}
+
+/// Writer rendering an asset inventory in one output format
+pub(crate) trait InventoryWriter {
+    /// File extension used for the default output path
+    fn extension(&self) -> &'static str;
+    fn write(&self, inventory: &AssetInventory) -> Result<String, AppError>;
+}
+
+struct JsonWriter {
+    pretty: bool,
+}
+
+impl InventoryWriter for JsonWriter {
+    fn extension(&self) -> &'static str {
+        "JSON"
+    }
+
+    fn write(&self, inventory: &AssetInventory) -> Result<String, AppError> {
+        if self.pretty {
+            Ok(serde_json::to_string_pretty(inventory)?)
+        } else {
+            Ok(inventory.to_json()?)
+        }
+    }
+}
+
+struct YamlWriter;
+
+impl InventoryWriter for YamlWriter {
+    fn extension(&self) -> &'static str {
+        "yaml"
+    }
+
+    fn write(&self, inventory: &AssetInventory) -> Result<String, AppError> {
+        serde_yaml::to_string(inventory).map_err(|e| AppError::FormatError(e.to_string()))
+    }
+}
+
+struct CsvWriter;
+
+impl InventoryWriter for CsvWriter {
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn write(&self, inventory: &AssetInventory) -> Result<String, AppError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let format_error = |e: csv::Error| AppError::FormatError(e.to_string());
+
+        writer
+            .write_record(["name", "category", "visibility", "details", "attributes"])
+            .map_err(format_error)?;
+        for asset in &inventory.assets {
+            writer
+                .write_record([
+                    asset.name.as_str(),
+                    category_name(&asset.category),
+                    asset.visibility.as_str(),
+                    &category_details(&asset.category),
+                    &attributes(asset),
+                ])
+                .map_err(format_error)?;
+        }
+
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| AppError::FormatError(e.to_string()))?;
+        String::from_utf8(bytes).map_err(|e| AppError::FormatError(e.to_string()))
+    }
+}
+
+struct MarkdownWriter;
+
+impl InventoryWriter for MarkdownWriter {
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn write(&self, inventory: &AssetInventory) -> Result<String, AppError> {
+        let mut markdown = String::from("# Asset Inventory\n\n");
+        if let Some(source) = &inventory.source {
+            markdown.push_str(&format!("Source: `{}`\n\n", source));
+        }
+
+        markdown.push_str("| Category | Name | Visibility | Details | Attributes |\n");
+        markdown.push_str("|---|---|---|---|---|\n");
+        for asset in &inventory.assets {
+            markdown.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                category_name(&asset.category),
+                escape_markdown(&asset.name),
+                asset.visibility,
+                escape_markdown(&category_details(&asset.category)),
+                escape_markdown(&attributes(asset)),
+            ));
+        }
+
+        if !inventory.findings.is_empty() {
+            markdown.push_str("\n## Findings\n\n");
+            markdown.push_str("| Rule | Asset | Line | Message |\n");
+            markdown.push_str("|---|---|---|---|\n");
+            for finding in &inventory.findings {
+                markdown.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    finding.rule,
+                    escape_markdown(&finding.asset),
+                    finding.line.map(|line| line.to_string()).unwrap_or_default(),
+                    escape_markdown(&finding.message),
+                ));
+            }
+        }
+
+        if !inventory.unparsed.is_empty() {
+            markdown.push_str("\n## Unparsed Regions\n\n");
+            markdown.push_str("| File | Lines | Message |\n");
+            markdown.push_str("|---|---|---|\n");
+            for region in &inventory.unparsed {
+                markdown.push_str(&format!(
+                    "| {} | {}-{} | {} |\n",
+                    region.file,
+                    region.start_line,
+                    region.end_line,
+                    escape_markdown(&region.message),
+                ));
+            }
+        }
+
+        Ok(markdown)
+    }
+}
+
+// SARIF level of the findings of a rule, rules not listed are warnings
+const RULE_LEVELS: [(&str, &str); 10] = [
+    ("permissive-xcm-barrier", "error"),
+    ("broad-teleport-trust", "error"),
+    ("unrestricted-xcm-execute", "error"),
+    ("unrestricted-xcm-transact", "error"),
+    ("storage-changed-without-migration", "error"),
+    ("unbounded-iteration", "error"),
+    ("untested-error", "note"),
+    ("unchecked-event", "note"),
+    ("untested-dispatchable", "note"),
+    ("unparsed-region", "note"),
+];
+
+struct SarifWriter;
+
+impl InventoryWriter for SarifWriter {
+    fn extension(&self) -> &'static str {
+        "sarif"
+    }
+
+    fn write(&self, inventory: &AssetInventory) -> Result<String, AppError> {
+        let mut rule_ids: Vec<&str> = inventory.findings.iter().map(|f| f.rule.as_str()).collect();
+        rule_ids.sort();
+        rule_ids.dedup();
+        let rule = |id: &str| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": id.replace('-', " ") },
+                "defaultConfiguration": { "level": level(id) }
+            })
+        };
+        let mut rules: Vec<_> = rule_ids.iter().map(|id| rule(id)).collect();
+
+        // Locations are relative to the scanned source, which viewers resolve against their checkout
+        let source = inventory.source.as_deref().unwrap_or("");
+        let location = |file: &str| {
+            let mut location = json!({ "uri": relative_uri(file, source) });
+            if !source.is_empty() && Path::new(file).starts_with(source) {
+                location["uriBaseId"] = json!("SRCROOT");
+            }
+            location
+        };
+        let mut results: Vec<_> = inventory
+            .findings
+            .iter()
+            .map(|finding| {
+                let mut result_location = json!({ "logicalLocations": [{ "name": finding.asset }] });
+                // Findings comparing two versions have no file
+                if let Some(file) = &finding.file {
+                    let mut physical_location = json!({ "artifactLocation": location(file) });
+                    if let Some(line) = finding.line {
+                        physical_location["region"] = json!({ "startLine": line });
+                    }
+                    result_location["physicalLocation"] = physical_location;
+                }
+                json!({
+                    "ruleId": finding.rule,
+                    "level": level(&finding.rule),
+                    "message": { "text": finding.message },
+                    "locations": [result_location]
+                })
+            })
+            .collect();
+
+        // Skipped regions are reported as notes so reviewers know which code was not analysed
+        results.extend(inventory.unparsed.iter().map(|region| {
+            json!({
+                "ruleId": "unparsed-region",
+                "level": level("unparsed-region"),
+                "message": { "text": region.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": location(&region.file),
+                        "region": { "startLine": region.start_line, "endLine": region.end_line }
+                    }
+                }]
+            })
+        }));
+        if !inventory.unparsed.is_empty() {
+            rules.push(rule("unparsed-region"));
+        }
+
+        let mut run = json!({
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules
+                }
+            },
+            "results": results
+        });
+        if let Some(root) = root_uri(source) {
+            run["originalUriBaseIds"] = json!({ "SRCROOT": { "uri": root } });
+        }
+
+        let log = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [run]
+        });
+        Ok(serde_json::to_string_pretty(&log)?)
+    }
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+pub(crate) fn category_name(category: &AssetCategory) -> &'static str {
+    match category {
+        AssetCategory::Storage(..) => "Storage",
+        AssetCategory::Events(..) => "Events",
+        AssetCategory::Constant(..) => "Constant",
+        AssetCategory::Error(..) => "Error",
+        AssetCategory::PublicFunction(..) => "PublicFunction",
+        AssetCategory::Helper(..) => "Helper",
+        AssetCategory::StorageVersion(..) => "StorageVersion",
+        AssetCategory::Migration(..) => "Migration",
+        AssetCategory::RuntimePallet(..) => "RuntimePallet",
+        AssetCategory::ConfigItem(..) => "ConfigItem",
+        AssetCategory::ExposedTrait(..) => "ExposedTrait",
+        AssetCategory::Dependency(..) => "Dependency",
+        AssetCategory::Xcm(..) => "Xcm",
+        AssetCategory::Benchmark(..) => "Benchmark",
+    }
+}
+
+// Category payload beyond the asset name, flattened into a single cell
+pub(crate) fn category_details(category: &AssetCategory) -> String {
+    match category {
+        AssetCategory::PublicFunction(_, params) | AssetCategory::Helper(_, params) => params
+            .iter()
+            .map(|param| param.param_type.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        AssetCategory::StorageVersion(version) => version.clone(),
+        AssetCategory::Migration(_, kind) => kind.clone(),
+        AssetCategory::RuntimePallet(_, path, parts) if parts.is_empty() => path.clone(),
+        AssetCategory::RuntimePallet(_, path, parts) => format!("{} {{{}}}", path, parts.join(", ")),
+        AssetCategory::ConfigItem(_, _, bound) => bound.clone(),
+        AssetCategory::ExposedTrait(_, methods) => methods
+            .iter()
+            .map(|(_, signature)| signature.as_str())
+            .collect::<Vec<_>>()
+            .join("; "),
+        AssetCategory::Dependency(_, kind) | AssetCategory::Xcm(_, kind) => kind.clone(),
+        AssetCategory::Benchmark(_, dispatchables) => dispatchables.clone(),
+        AssetCategory::Storage(..) | AssetCategory::Events(..) | AssetCategory::Constant(..) | AssetCategory::Error(..) => {
+            String::new()
+        }
+    }
+}
+
+fn level(rule: &str) -> &'static str {
+    RULE_LEVELS
+        .iter()
+        .find_map(|(id, level)| (*id == rule).then_some(*level))
+        .unwrap_or("warning")
+}
+
+// Path of a file relative to the scanned source, with `/` separators as URIs require. A single scanned file is
+// relative to its directory.
+fn relative_uri(file: &str, source: &str) -> String {
+    let (file, source) = (Path::new(file), Path::new(source));
+    let relative = match file.strip_prefix(source) {
+        Ok(relative) if relative.as_os_str().is_empty() => file.file_name().map(Path::new).unwrap_or(file),
+        Ok(relative) => relative,
+        Err(_) => file,
+    };
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// `file://` URI of the directory the locations are relative to, if the source exists on disk
+fn root_uri(source: &str) -> Option<String> {
+    let root = Path::new(source).canonicalize().ok()?;
+    let root = if root.is_dir() { root } else { root.parent()?.to_path_buf() };
+    let root = root.to_string_lossy().replace('\\', "/");
+    Some(format!("file://{}{}/", if root.starts_with('/') { "" } else { "/" }, root.trim_end_matches('/')))
+}
+
+fn attributes(asset: &Asset) -> String {
+    asset
+        .attributes
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{relative_uri, InventoryWriter, SarifWriter};
+    use crate::{AssetInventory, Finding, UnparsedRegion};
+    use serde_json::Value;
+
+    fn finding(rule: &str, file: Option<&str>) -> Finding {
+        Finding {
+            rule: rule.to_string(),
+            asset: "Barrier".to_string(),
+            message: "message".to_string(),
+            file: file.map(str::to_string),
+            line: Some(3),
+        }
+    }
+
+    #[test]
+    fn sarif_locations_are_relative_to_the_source() {
+        let inventory = AssetInventory {
+            source: Some("pallets/items".to_string()),
+            assets: Vec::new(),
+            findings: vec![
+                finding("permissive-xcm-barrier", Some("pallets/items/src/lib.rs")),
+                finding("missing-benchmark", Some("pallets/items/src/benchmarking.rs")),
+                finding("storage-changed-without-migration", None),
+            ],
+            unparsed: vec![UnparsedRegion {
+                file: "pallets/items/src/weights.rs".to_string(),
+                start_line: 1,
+                end_line: 2,
+                message: "expected item".to_string(),
+            }],
+        };
+        let log: Value = serde_json::from_str(&SarifWriter.write(&inventory).unwrap()).unwrap();
+
+        let results: Vec<(&str, &str, &str)> = log["runs"][0]["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| {
+                let location = &result["locations"][0]["physicalLocation"]["artifactLocation"];
+                (
+                    result["ruleId"].as_str().unwrap(),
+                    result["level"].as_str().unwrap(),
+                    location["uri"].as_str().unwrap_or_default(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("permissive-xcm-barrier", "error", "src/lib.rs"),
+                ("missing-benchmark", "warning", "src/benchmarking.rs"),
+                ("storage-changed-without-migration", "error", ""),
+                ("unparsed-region", "note", "src/weights.rs"),
+            ]
+        );
+
+        let location = &log["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"];
+        assert_eq!(location["uriBaseId"], "SRCROOT");
+
+        let rules = log["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap();
+        let levels: Vec<(&str, &str)> = rules
+            .iter()
+            .map(|rule| (rule["id"].as_str().unwrap(), rule["defaultConfiguration"]["level"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                ("missing-benchmark", "warning"),
+                ("permissive-xcm-barrier", "error"),
+                ("storage-changed-without-migration", "error"),
+                ("unparsed-region", "note"),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_single_source_file_is_relative_to_its_directory() {
+        assert_eq!(relative_uri("pallet/src/lib.rs", "pallet/src/lib.rs"), "lib.rs");
+        assert_eq!(relative_uri("other/lib.rs", "pallet"), "other/lib.rs");
+    }
+}
@@ -0,0 +1,455 @@
+use crate::{
+    output::{category_details, category_name},
+    parse_source, read_sources, AppError, Asset, AssetCategory, AssetInventory, OutputFormat, ParsedFile,
+};
+use std::{collections::HashSet, fs, path::Path};
+
+// ----------------------------------------------Inventory Queries--------------------------------------------------
+// Audit questions such as "which dispatchables take a `Vec`" are filters over the inventory. A query combines
+// terms with `and`, `or`, `not` and parentheses, adjacent terms are joined with `and`:
+//
+//   category=Dispatchable and origin=signed and writes:Balances
+//   category=Dispatchable param:Vec
+//   category=Storage and type:Twox64Concat
+//
+// `field=value` compares whole values, `field!=value` negates it and `field:value` looks for a substring.
+// Comparisons ignore case, substring matches also ignore whitespace so `param:Vec<u8>` finds `Vec < u8 >`.
+
+// Attributes holding comma separated lists, `=` matches any of their elements
+const LIST_ATTRIBUTES: [&str; 13] = [
+    "writes",
+    "topics",
+    "disabled_parts",
+    "external_calls",
+    "operations",
+    "used_by",
+    "benchmarks",
+    "components",
+    "tested_by",
+    "asserted_by",
+    "checked_by",
+    "unbounded_types",
+    "iterations",
+];
+
+/// Query expression
+#[derive(Debug)]
+enum Query {
+    Term(String, Operator, String),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Operator {
+    Equals,
+    NotEquals,
+    Contains,
+}
+
+/// Run a query against an inventory JSON file or the inventory of a source file or crate directory, and print
+/// the matching assets to stdout
+pub(crate) fn run(
+    path: &Path,
+    query: &str,
+    parser: fn(&[ParsedFile]) -> Result<AssetInventory, AppError>,
+    format: OutputFormat,
+) -> Result<(), AppError> {
+    // Parse the query first so a typo fails before a whole crate is analysed
+    let query = parse(query)?;
+
+    let inventory = if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        serde_json::from_str(&fs::read_to_string(path)?)?
+    } else {
+        let files: Vec<ParsedFile> = read_sources(path)?.iter().map(parse_source).collect();
+        let mut inventory = parser(&files)?;
+        inventory.source = Some(path.display().to_string());
+        inventory
+    };
+
+    let total = inventory.assets.len();
+    let assets: Vec<Asset> = inventory.assets.into_iter().filter(|asset| query.matches(asset)).collect();
+    let names: HashSet<&str> = assets.iter().map(|asset| asset.name.as_str()).collect();
+    let findings = inventory
+        .findings
+        .into_iter()
+        .filter(|finding| names.contains(finding.asset.as_str()))
+        .collect();
+
+    let matched = AssetInventory {
+        source: inventory.source,
+        assets,
+        findings,
+        unparsed: Vec::new(),
+    };
+    print!("{}", format.writer().write(&matched)?);
+    eprintln!("{} of {} assets match", matched.assets.len(), total);
+    Ok(())
+}
+
+impl Query {
+    fn matches(&self, asset: &Asset) -> bool {
+        match self {
+            Query::Term(field, operator, expected) => {
+                let values = field_values(asset, field);
+                match operator {
+                    Operator::Equals => values.iter().any(|value| value.eq_ignore_ascii_case(expected)),
+                    Operator::NotEquals => !values.iter().any(|value| value.eq_ignore_ascii_case(expected)),
+                    Operator::Contains => {
+                        let expected = normalize(expected);
+                        values.iter().any(|value| normalize(value).contains(&expected))
+                    }
+                }
+            }
+            Query::And(left, right) => left.matches(asset) && right.matches(asset),
+            Query::Or(left, right) => left.matches(asset) || right.matches(asset),
+            Query::Not(query) => !query.matches(asset),
+        }
+    }
+}
+
+// Values of an asset field, any attribute can be queried by its key
+fn field_values(asset: &Asset, field: &str) -> Vec<String> {
+    match field.to_ascii_lowercase().as_str() {
+        "category" => {
+            let category = category_name(&asset.category);
+            let mut values = vec![category.to_string()];
+            // Dispatchables are listed as public functions marked by the parser, other `pub fn` are not calls.
+            // Messages and constructors are the calls of an ink! contract
+            let is_call = asset.attributes.get("dispatchable").is_some_and(|value| value == "yes")
+                || asset.attributes.contains_key("ink");
+            if is_call {
+                values.push("Dispatchable".to_string());
+            }
+            values
+        }
+        "name" => vec![asset.name.clone()],
+        "visibility" => vec![asset.visibility.clone()],
+        "details" => vec![category_details(&asset.category)],
+        "param" | "params" => match &asset.category {
+            AssetCategory::PublicFunction(_, params) | AssetCategory::Helper(_, params) => {
+                // Aliases match by their written, resolved and primitive type
+                params
+                    .iter()
+                    .flat_map(|param| {
+                        std::iter::once(&param.param_type)
+                            .chain(param.resolved.as_ref())
+                            .chain(param.primitive.as_ref())
+                            .cloned()
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        },
+        key => match asset.attributes.get(key) {
+            Some(value) if LIST_ATTRIBUTES.contains(&key) => std::iter::once(value.clone())
+                .chain(value.split(',').map(|item| item.trim().to_string()))
+                .collect(),
+            Some(value) => vec![value.clone()],
+            None => Vec::new(),
+        },
+    }
+}
+
+fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// ----------------------------------------------Query Parser-------------------------------------------------------
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    // Keywords are only recognised when unquoted
+    Keyword(String),
+    Word(String),
+}
+
+/// Parse a query string
+fn parse(query: &str) -> Result<Query, AppError> {
+    let mut parser = QueryParser {
+        tokens: tokenize(query)?,
+        position: 0,
+    };
+    if parser.tokens.is_empty() {
+        return Err(invalid("the query is empty"));
+    }
+
+    let query = parser.or()?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(query),
+        Some(token) => Err(invalid(&format!("unexpected {}", describe(token)))),
+    }
+}
+
+// Recursive descent over `or` > `and` > `not` > term, from the loosest to the tightest binding
+struct QueryParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl QueryParser {
+    fn or(&mut self) -> Result<Query, AppError> {
+        let mut query = self.and()?;
+        while self.eat_keyword("or") {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, AppError> {
+        let mut query = self.not()?;
+        loop {
+            let explicit = self.eat_keyword("and");
+            let implicit = match self.tokens.get(self.position) {
+                Some(Token::Open | Token::Word(_)) => true,
+                Some(Token::Keyword(word)) => word == "not",
+                _ => false,
+            };
+            if !explicit && !implicit {
+                return Ok(query);
+            }
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Query, AppError> {
+        if self.eat_keyword("not") {
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        self.term()
+    }
+
+    fn term(&mut self) -> Result<Query, AppError> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        match token {
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.tokens.get(self.position) {
+                    Some(Token::Close) => {
+                        self.position += 1;
+                        Ok(query)
+                    }
+                    _ => Err(invalid("missing `)`")),
+                }
+            }
+            Some(Token::Word(word)) => parse_term(word),
+            Some(token) => Err(invalid(&format!("expected a term, found {}", describe(token)))),
+            None => Err(invalid("expected a term at the end of the query")),
+        }
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.position), Some(Token::Keyword(word)) if word == keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.at_keyword(keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+}
+
+// `field=value`, `field!=value` or `field:value`
+fn parse_term(word: &str) -> Result<Query, AppError> {
+    let Some(position) = word.find(['=', ':', '!']) else {
+        return Err(invalid(&format!(
+            "`{}` is not a term, expected `field=value`, `field!=value` or `field:value`",
+            word
+        )));
+    };
+    let (field, rest) = word.split_at(position);
+    let (operator, value) = if let Some(value) = rest.strip_prefix("!=") {
+        (Operator::NotEquals, value)
+    } else if let Some(value) = rest.strip_prefix('=') {
+        (Operator::Equals, value)
+    } else if let Some(value) = rest.strip_prefix(':') {
+        (Operator::Contains, value)
+    } else {
+        return Err(invalid(&format!("unknown operator in `{}`", word)));
+    };
+    if field.is_empty() {
+        return Err(invalid(&format!("missing field name in `{}`", word)));
+    }
+    Ok(Query::Term(field.to_string(), operator, value.to_string()))
+}
+
+// Split on whitespace and parentheses, double quotes keep spaces inside a value: `type:"Blake2_128Concat, T"`
+fn tokenize(query: &str) -> Result<Vec<Token>, AppError> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    if c != '"' {
+                        word.push(c);
+                        continue;
+                    }
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => word.push(c),
+                            None => return Err(invalid("unterminated `\"`")),
+                        }
+                    }
+                }
+                let keyword = word.to_ascii_lowercase();
+                if !quoted && matches!(keyword.as_str(), "and" | "or" | "not") {
+                    tokens.push(Token::Keyword(keyword));
+                } else {
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Open => "`(`".to_string(),
+        Token::Close => "`)`".to_string(),
+        Token::Keyword(word) | Token::Word(word) => format!("`{}`", word),
+    }
+}
+
+fn invalid(message: &str) -> AppError {
+    AppError::InvalidInput(format!("Invalid query: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{inventory_of, Parameter};
+
+    fn function(name: &str, params: &[&str], attributes: &[(&str, &str)]) -> Asset {
+        let params = params
+            .iter()
+            .map(|ty| Parameter {
+                name: "param".to_string(),
+                param_type: ty.to_string(),
+                resolved: None,
+                primitive: None,
+            })
+            .collect();
+        attributes.iter().fold(
+            Asset::new("public", name, AssetCategory::PublicFunction(name.to_string(), params)),
+            |asset, (key, value)| asset.with_attribute(key, *value),
+        )
+    }
+
+    fn matching<'a>(query: &str, assets: &'a [Asset]) -> Vec<&'a str> {
+        let query = parse(query).expect("query parses");
+        assets
+            .iter()
+            .filter(|asset| query.matches(asset))
+            .map(|asset| asset.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let assets = [
+            function("a", &[], &[("origin", "signed")]),
+            function("b", &[], &[("origin", "root"), ("writes", "Balances")]),
+            function("c", &[], &[("origin", "none"), ("writes", "Balances")]),
+        ];
+
+        assert_eq!(matching("origin=signed or origin=root and writes:Balances", &assets), ["a", "b"]);
+        assert_eq!(matching("(origin=signed or origin=root) and writes:Balances", &assets), ["b"]);
+        assert_eq!(matching("not origin=signed and writes:Balances", &assets), ["b", "c"]);
+    }
+
+    #[test]
+    fn adjacent_terms_are_joined_with_and() {
+        let assets = [
+            function("a", &["Vec<u8>"], &[("origin", "signed")]),
+            function("b", &["u32"], &[("origin", "signed")]),
+            function("c", &["Vec<u8>"], &[("origin", "root")]),
+        ];
+
+        assert_eq!(matching("origin=signed param:Vec", &assets), ["a"]);
+        assert_eq!(matching("origin=signed not param:Vec", &assets), ["b"]);
+        assert_eq!(matching("param:Vec (origin=root or name=b)", &assets), ["c"]);
+    }
+
+    #[test]
+    fn quotes_keep_spaces_and_keywords() {
+        let assets = [
+            function("a", &[], &[("type", "StorageMap<_, Blake2_128Concat, T>")]),
+            function("and", &[], &[]),
+        ];
+
+        assert_eq!(matching(r#"type:"Blake2_128Concat, T""#, &assets), ["a"]);
+        assert_eq!(matching(r#"name="and""#, &assets), ["and"]);
+        assert!(parse(r#"type:"Blake2_128Concat"#).is_err());
+    }
+
+    #[test]
+    fn not_equals_matches_assets_without_the_value() {
+        let assets = [
+            function("a", &[], &[("origin", "signed")]),
+            function("b", &[], &[("origin", "root")]),
+            function("c", &[], &[]),
+        ];
+
+        assert_eq!(matching("origin!=signed", &assets), ["b", "c"]);
+        assert_eq!(matching("origin!=SIGNED", &assets), ["b", "c"]);
+    }
+
+    #[test]
+    fn malformed_queries_are_rejected() {
+        for query in ["", "origin", "=signed", "(origin=signed", "origin=signed)", "origin=signed and"] {
+            assert!(parse(query).is_err(), "`{}` parses", query);
+        }
+    }
+
+    #[test]
+    fn dispatchables_are_call_functions_only() {
+        let inventory = inventory_of(
+            r#"
+            #[frame_support::pallet]
+            pub mod pallet {
+                #[pallet::call]
+                impl<T: Config> Pallet<T> {
+                    pub fn transfer(origin: OriginFor<T>, amount: u32) -> DispatchResult { Ok(()) }
+                }
+
+                impl<T: Config> Pallet<T> {
+                    pub fn helper(x: u32) -> u32 { x }
+                }
+            }
+            "#,
+        );
+
+        assert_eq!(matching("category=Dispatchable", &inventory.assets), ["transfer"]);
+    }
+}
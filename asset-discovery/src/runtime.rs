@@ -0,0 +1,459 @@
+use crate::{
+    has_pallet_constant, skip_attributes, split_tokens, types::normalize, xcm, AppError, Asset, AssetCategory,
+    AssetInventory, ParsedFile,
+};
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::quote;
+use std::collections::HashMap;
+use syn::visit::Visit;
+
+// ----------------------------------------------Runtime Discovery--------------------------------------------------
+// Second stage of the framework: instead of a single pallet, look at how a runtime assembles pallets.
+// Pallets come from `construct_runtime!` or a `#[frame_support::runtime]` module, and every
+// `impl pallet_x::Config for Runtime` block tells us which concrete types are bound to each Config item.
+
+/// Pallet declared in the runtime
+struct RuntimePallet {
+    name: String,
+    path: String,
+    index: Option<u32>,
+    parts: Vec<String>,
+    // "explicit", "implicit", "exclude_parts" or "use_parts"
+    parts_mode: &'static str,
+    // Parts removed through `exclude_parts { .. }` or `#[runtime::disable_call]`
+    disabled: Vec<String>,
+}
+
+/// Entry of a `parameter_types!` block or a plain constant
+struct ParameterType {
+    ty: String,
+    value: String,
+}
+
+/// Visitor collecting runtime composition, Config impls and parameter types
+#[derive(Default)]
+struct RuntimeVisitor {
+    pallets: Vec<RuntimePallet>,
+    config_items: Vec<(String, String, String, Option<String>)>, // (pallet, item, bound type, derive_impl)
+    parameter_types: HashMap<String, ParameterType>,            // (parameter name, parameter type)
+}
+
+impl<'ast> Visit<'ast> for RuntimeVisitor {
+    // `construct_runtime!` and `parameter_types!`
+    fn visit_item_macro(&mut self, node: &'ast syn::ItemMacro) {
+        let macro_name = node.mac.path.segments.last().map(|seg| seg.ident.to_string());
+        match macro_name.as_deref() {
+            Some("construct_runtime") => self.pallets.extend(parse_construct_runtime(node.mac.tokens.clone())),
+            Some("parameter_types") => self.parameter_types.extend(parse_parameter_types(node.mac.tokens.clone())),
+            _ => {}
+        }
+        syn::visit::visit_item_macro(self, node);
+    }
+
+    // `#[frame_support::runtime] mod runtime { .. }`
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        let is_runtime = node.attrs.iter().any(|attr| {
+            has_pallet_constant("frame_support::runtime".to_string(), attr)
+                || has_pallet_constant("runtime".to_string(), attr)
+        });
+        if is_runtime {
+            if let Some((_, items)) = &node.content {
+                self.pallets.extend(items.iter().filter_map(runtime_module_pallet));
+            }
+        }
+        syn::visit::visit_item_mod(self, node);
+    }
+
+    // `impl pallet_x::Config for Runtime { type Item = Bound; }`, other `Config` impls such as
+    // `impl xcm_executor::Config for XcmConfig` do not configure a pallet of the runtime
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let for_runtime = match &*node.self_ty {
+            syn::Type::Path(ty) => ty.path.segments.last().is_some_and(|seg| seg.ident == "Runtime"),
+            _ => false,
+        };
+        if let (Some((_, trait_path, _)), true) = (&node.trait_, for_runtime) {
+            let is_config = trait_path.segments.last().is_some_and(|seg| seg.ident == "Config");
+            if is_config && trait_path.segments.len() > 1 {
+                let pallet = trait_path
+                    .segments
+                    .iter()
+                    .take(trait_path.segments.len() - 1)
+                    .map(|seg| seg.ident.to_string())
+                    .collect::<Vec<_>>()
+                    .join("::");
+                let derive_impl = node.attrs.iter().find_map(|attr| {
+                    let is_derive_impl = attr.path().segments.last().is_some_and(|seg| seg.ident == "derive_impl");
+                    match (is_derive_impl, &attr.meta) {
+                        (true, syn::Meta::List(list)) => Some(list.tokens.to_string().replace(' ', "")),
+                        _ => None,
+                    }
+                });
+
+                for item in &node.items {
+                    let (item_name, bound) = match item {
+                        syn::ImplItem::Type(ty) => {
+                            let bound = &ty.ty;
+                            (ty.ident.to_string(), normalize(&quote!(#bound).to_string()))
+                        }
+                        syn::ImplItem::Const(constant) => {
+                            let bound = &constant.expr;
+                            (constant.ident.to_string(), normalize(&quote!(#bound).to_string()))
+                        }
+                        _ => continue,
+                    };
+                    self.config_items.push((pallet.clone(), item_name, bound, derive_impl.clone()));
+                }
+            }
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+
+    // Plain constants referenced from parameter types, e.g. `pub const EXISTENTIAL_DEPOSIT: Balance = 500;`
+    fn visit_item_const(&mut self, node: &'ast syn::ItemConst) {
+        let (ty, expr) = (&node.ty, &node.expr);
+        self.parameter_types.insert(
+            node.ident.to_string(),
+            ParameterType {
+                ty: normalize(&quote!(#ty).to_string()),
+                value: normalize(&quote!(#expr).to_string()),
+            },
+        );
+        syn::visit::visit_item_const(self, node);
+    }
+}
+
+/// Parse runtime source code into an asset inventory of pallets and Config bindings
+/// Runtimes often split `construct_runtime!`, Config impls and parameter types across files, so all files share
+/// one visitor.
+pub(crate) fn parser(files: &[ParsedFile]) -> Result<AssetInventory, AppError> {
+    let mut visitor = RuntimeVisitor::default();
+    for file in files {
+        visitor.visit_file(&file.syntax_tree);
+    }
+
+    if visitor.pallets.is_empty() {
+        return Err(AppError::InvalidInput(
+            "No `construct_runtime!` or `#[frame_support::runtime]` declaration found".to_string(),
+        ));
+    }
+
+    let mut asset_inventory = AssetInventory {
+        source: None,
+        assets: Vec::new(),
+        findings: Vec::new(),
+        unparsed: files.iter().flat_map(|file| file.unparsed.iter().cloned()).collect(),
+    };
+
+    // Pallets without an explicit index follow the previous one
+    let mut next_index = 0;
+    for pallet in visitor.pallets {
+        let index = pallet.index.unwrap_or(next_index);
+        next_index = index + 1;
+
+        // A pallet is reachable by users when its Call part is included
+        let exposes_call = match pallet.parts_mode {
+            "explicit" | "use_parts" => pallet.parts.iter().any(|part| part == "Call"),
+            _ => !pallet.disabled.iter().any(|part| part == "Call"),
+        };
+        let visibility = if exposes_call { "public" } else { "private" };
+
+        let mut asset = Asset::new(
+            visibility,
+            &pallet.name,
+            AssetCategory::RuntimePallet(pallet.name.clone(), pallet.path, pallet.parts),
+        )
+        .with_attribute("index", index.to_string())
+        .with_attribute("parts_mode", pallet.parts_mode);
+        if !pallet.disabled.is_empty() {
+            asset = asset.with_attribute("disabled_parts", pallet.disabled.join(","));
+        }
+        asset_inventory.assets.push(asset);
+    }
+
+    for (pallet, item, bound, derive_impl) in visitor.config_items {
+        let name = format!("{}::{}", pallet, item);
+        let mut asset = Asset::new("none", &name, AssetCategory::ConfigItem(pallet, item, bound.clone()));
+        if let Some(value) = resolve_value(&bound, &visitor.parameter_types) {
+            asset = asset.with_attribute("value", value);
+        }
+        if let Some(parameter) = visitor.parameter_types.get(&bound) {
+            asset = asset.with_attribute("value_type", parameter.ty.as_str());
+        }
+        if let Some(derive_impl) = derive_impl {
+            asset = asset.with_attribute("derive_impl", derive_impl);
+        }
+        asset_inventory.assets.push(asset);
+    }
+    xcm::collect(files, &mut asset_inventory);
+
+    Ok(asset_inventory)
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+/// Name and path of every pallet in a `construct_runtime!`, e.g. `("Balances", "pallet_balances")`
+pub(crate) fn pallet_aliases(tokens: TokenStream) -> Vec<(String, String)> {
+    parse_construct_runtime(tokens)
+        .into_iter()
+        .map(|pallet| (pallet.name, pallet.path))
+        .collect()
+}
+
+// Entries of `construct_runtime!`, both the legacy `pub enum Runtime where .. { .. }` and the
+// `pub struct Runtime { .. }` forms, e.g. `Balances: pallet_balances::{Pallet, Call, Storage} = 10`
+fn parse_construct_runtime(tokens: TokenStream) -> Vec<RuntimePallet> {
+    let Some(body) = tokens.into_iter().filter_map(|tt| match tt {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => Some(group.stream()),
+        _ => None,
+    }).last() else {
+        return Vec::new();
+    };
+
+    split_tokens(body, ',')
+        .into_iter()
+        .filter_map(|entry| {
+            let mut tokens = skip_attributes(entry).into_iter().peekable();
+            let name = match tokens.next()? {
+                TokenTree::Ident(ident) => ident.to_string(),
+                _ => return None,
+            };
+
+            let mut path = String::new();
+            let mut pallet = RuntimePallet {
+                name,
+                path: String::new(),
+                index: None,
+                parts: Vec::new(),
+                parts_mode: "implicit",
+                disabled: Vec::new(),
+            };
+            // Skip the `:` separating the name from the pallet path
+            tokens.next();
+            while let Some(token) = tokens.next() {
+                match token {
+                    TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                        pallet.parts = part_names(group.stream());
+                        pallet.parts_mode = "explicit";
+                    }
+                    TokenTree::Ident(ident) if ident == "exclude_parts" || ident == "use_parts" => {
+                        if let Some(TokenTree::Group(group)) = tokens.next() {
+                            if ident == "exclude_parts" {
+                                pallet.disabled = part_names(group.stream());
+                                pallet.parts_mode = "exclude_parts";
+                            } else {
+                                pallet.parts = part_names(group.stream());
+                                pallet.parts_mode = "use_parts";
+                            }
+                        }
+                    }
+                    TokenTree::Punct(punct) if punct.as_char() == '=' => {
+                        if let Some(TokenTree::Literal(index)) = tokens.next() {
+                            pallet.index = index.to_string().parse().ok();
+                        }
+                    }
+                    other => path.push_str(&other.to_string()),
+                }
+            }
+            pallet.path = path.trim_end_matches("::").to_string();
+            Some(pallet)
+        })
+        .collect()
+}
+
+// `#[runtime::pallet_index(10)] pub type Balances = pallet_balances;` inside a `#[frame_support::runtime]` module
+fn runtime_module_pallet(item: &syn::Item) -> Option<RuntimePallet> {
+    let syn::Item::Type(item_type) = item else {
+        return None;
+    };
+
+    let mut index = None;
+    let mut disabled = Vec::new();
+    for attr in &item_type.attrs {
+        let attr_name = attr.path().segments.last().map(|seg| seg.ident.to_string());
+        match attr_name.as_deref() {
+            Some("pallet_index") => index = attr.parse_args::<syn::LitInt>().ok()?.base10_parse().ok(),
+            Some("disable_call") => disabled.push("Call".to_string()),
+            Some("disable_unsigned") => disabled.push("ValidateUnsigned".to_string()),
+            _ => {}
+        }
+    }
+    // Type aliases without `pallet_index` are not pallets, e.g. `pub type Runtime = ..`
+    index?;
+
+    // Drop the `::Pallet<Runtime>` suffix so the path matches the construct_runtime! form
+    let ty = &item_type.ty;
+    let path = quote!(#ty).to_string().replace(' ', "");
+    let path = path.split("::Pallet<").next().unwrap_or(&path).to_string();
+
+    Some(RuntimePallet {
+        name: item_type.ident.to_string(),
+        path,
+        index,
+        parts: Vec::new(),
+        parts_mode: "implicit",
+        disabled,
+    })
+}
+
+// Entries of `parameter_types!`, e.g. `pub const ExistentialDeposit: Balance = 500;`
+fn parse_parameter_types(tokens: TokenStream) -> Vec<(String, ParameterType)> {
+    split_tokens(tokens, ';')
+        .into_iter()
+        .filter_map(|entry| {
+            let tokens = skip_attributes(entry);
+            // The name is the identifier right before the first `:`
+            let colon = tokens.iter().position(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == ':'))?;
+            let name = match tokens.get(colon.checked_sub(1)?)? {
+                TokenTree::Ident(ident) => ident.to_string(),
+                _ => return None,
+            };
+            let equals = tokens.iter().position(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == '='))?;
+            let ty: TokenStream = tokens[colon + 1..equals].iter().cloned().collect();
+            let value: TokenStream = tokens[equals + 1..].iter().cloned().collect();
+            Some((
+                name,
+                ParameterType {
+                    ty: normalize(&ty.to_string()),
+                    value: normalize(&value.to_string()),
+                },
+            ))
+        })
+        .collect()
+}
+
+// Concrete value bound to a Config item, following parameter types and `ConstU32<N>`-style wrappers
+fn resolve_value(bound: &str, parameter_types: &HashMap<String, ParameterType>) -> Option<String> {
+    let mut value = bound.to_string();
+    let mut resolved = false;
+    // Parameter types may point at other constants, follow a few levels
+    for _ in 0..4 {
+        match parameter_types.get(&value) {
+            Some(parameter) => {
+                value = parameter.value.clone();
+                resolved = true;
+            }
+            None => break,
+        }
+    }
+
+    // `ConstU32<50>`, `ConstBool<true>`, ...
+    if value.starts_with("Const") {
+        if let Some(inner) = value.split_once('<').and_then(|(_, rest)| rest.strip_suffix('>')) {
+            return Some(inner.to_string());
+        }
+    }
+    resolved.then_some(value)
+}
+
+// Part names out of `{Pallet, Call, Storage, Event<T>, Config<T>}`, ignoring generic arguments
+fn part_names(tokens: TokenStream) -> Vec<String> {
+    let mut depth = 0;
+    let mut parts = Vec::new();
+    for token in tokens {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '<' => depth += 1,
+            TokenTree::Punct(punct) if punct.as_char() == '>' => depth -= 1,
+            TokenTree::Ident(ident) if depth == 0 => parts.push(ident.to_string()),
+            _ => {}
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parser;
+    use crate::{parse_source, AssetCategory, SourceFile};
+    use std::path::PathBuf;
+
+    const RUNTIME: &str = r#"
+        construct_runtime!(
+            pub enum Runtime {
+                System: frame_system = 0,
+                Balances: pallet_balances::{Pallet, Call, Storage, Event<T>} = 10,
+                Sudo: pallet_sudo exclude_parts { Call },
+                XcmPallet: pallet_xcm = 31,
+            }
+        );
+
+        pub const UNIT: Balance = 1_000_000_000_000;
+        parameter_types! {
+            pub const ExistentialDeposit: Balance = 500 * UNIT;
+            pub const MaxReserves: u32 = 50;
+        }
+
+        impl pallet_balances::Config for Runtime {
+            type Balance = Balance;
+            type ExistentialDeposit = ExistentialDeposit;
+            type MaxLocks = ConstU32<50>;
+            type AccountStore = frame_system::Pallet<Runtime>;
+        }
+
+        pub struct XcmConfig;
+        impl xcm_executor::Config for XcmConfig {
+            type Barrier = AllowUnpaidExecutionFrom<Everything>;
+        }
+
+        impl pallet_timestamp::Config for Test {
+            type MinimumPeriod = ConstU64<1>;
+        }
+    "#;
+
+    #[test]
+    fn pallets_are_indexed_in_declaration_order() {
+        let file = parse_source(&SourceFile {
+            path: PathBuf::from("runtime/src/lib.rs"),
+            content: RUNTIME.to_string(),
+        });
+        let inventory = parser(&[file]).expect("the runtime is declared");
+
+        let pallets: Vec<(&str, &str, &str, &str)> = inventory
+            .assets
+            .iter()
+            .filter_map(|asset| match &asset.category {
+                AssetCategory::RuntimePallet(name, path, _) => {
+                    Some((name.as_str(), path.as_str(), asset.visibility.as_str(), asset.attributes["index"].as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            pallets,
+            [
+                ("System", "frame_system", "public", "0"),
+                ("Balances", "pallet_balances", "public", "10"),
+                ("Sudo", "pallet_sudo", "private", "11"),
+                ("XcmPallet", "pallet_xcm", "public", "31"),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_config_impls_for_the_runtime_are_bindings() {
+        let file = parse_source(&SourceFile {
+            path: PathBuf::from("runtime/src/lib.rs"),
+            content: RUNTIME.to_string(),
+        });
+        let inventory = parser(&[file]).expect("the runtime is declared");
+
+        let bindings: Vec<(&str, &str, Option<&str>)> = inventory
+            .assets
+            .iter()
+            .filter_map(|asset| match &asset.category {
+                AssetCategory::ConfigItem(_, _, bound) => {
+                    Some((asset.name.as_str(), bound.as_str(), asset.attributes.get("value").map(String::as_str)))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            bindings,
+            [
+                ("pallet_balances::Balance", "Balance", None),
+                ("pallet_balances::ExistentialDeposit", "ExistentialDeposit", Some("500 * UNIT")),
+                ("pallet_balances::MaxLocks", "ConstU32<50>", Some("50")),
+                ("pallet_balances::AccountStore", "frame_system::Pallet<Runtime>", None),
+            ]
+        );
+    }
+}
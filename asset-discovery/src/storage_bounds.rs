@@ -0,0 +1,388 @@
+use crate::{
+    flatten_idents, has_pallet_constant,
+    types::{type_string, TypeAliases},
+    AssetCategory, AssetInventory, Finding, ParsedFile,
+};
+use proc_macro2::{TokenStream, TokenTree};
+use std::collections::{BTreeSet, HashMap};
+use syn::{visit::Visit, Type};
+
+// ----------------------------------------------Storage Bounds-----------------------------------------------------
+// Every byte in storage ends up in the proof of validity a parachain sends to the relay chain, so storage that can
+// grow without a bound is state bloat waiting to happen. FRAME enforces `MaxEncodedLen` on stored types unless
+// the pallet opts out with `#[pallet::without_storage_info]` or an item with `#[pallet::unbounded]`, and FRAME v1
+// pallets never enforce it. Each storage item is classified as `bounded`, `unbounded` when it holds a container
+// without a bound, or `unchecked` when nothing unbounded was found but `MaxEncodedLen` is not enforced either.
+
+// Containers without a length bound and their bounded replacement
+const UNBOUNDED_TYPES: [(&str, &str); 9] = [
+    ("Vec", "BoundedVec"),
+    ("VecDeque", "BoundedVec"),
+    ("String", "BoundedVec<u8, _>"),
+    ("BTreeMap", "BoundedBTreeMap"),
+    ("BTreeSet", "BoundedBTreeSet"),
+    ("HashMap", "BoundedBTreeMap"),
+    ("HashSet", "BoundedBTreeSet"),
+    ("BinaryHeap", "BoundedVec"),
+    ("LinkedList", "BoundedVec"),
+];
+
+/// Struct or enum declared in the analysed source
+struct LocalType {
+    max_encoded_len: bool, // derives `MaxEncodedLen`
+    fields: Vec<Type>,
+}
+
+/// Visitor collecting storage attributes and the stored types declared locally
+#[derive(Default)]
+struct BoundsVisitor {
+    file: String,                                  // file currently visited
+    without_storage_info: Option<(String, usize)>, // location of `#[pallet::without_storage_info]`
+    storage: HashMap<String, (String, usize)>,     // (`#[pallet::storage]` item, location)
+    unbounded: BTreeSet<String>,                   // items marked `#[pallet::unbounded]`
+    local_types: HashMap<String, LocalType>,       // (type name, type)
+    max_encoded_len_impls: BTreeSet<String>,       // types with a manual `impl MaxEncodedLen`
+}
+
+impl<'ast> Visit<'ast> for BoundsVisitor {
+    // `#[pallet::pallet] #[pallet::without_storage_info] pub struct Pallet<T>(_);`
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        let opts_out = node
+            .attrs
+            .iter()
+            .any(|attr| has_pallet_constant("pallet::without_storage_info".to_string(), attr));
+        if opts_out {
+            self.without_storage_info = Some((self.file.clone(), node.ident.span().start().line));
+        }
+
+        let local_type = LocalType {
+            max_encoded_len: derives_max_encoded_len(&node.attrs),
+            fields: node.fields.iter().map(|field| field.ty.clone()).collect(),
+        };
+        self.local_types.insert(node.ident.to_string(), local_type);
+        syn::visit::visit_item_struct(self, node);
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
+        let local_type = LocalType {
+            max_encoded_len: derives_max_encoded_len(&node.attrs),
+            fields: node
+                .variants
+                .iter()
+                .flat_map(|variant| variant.fields.iter().map(|field| field.ty.clone()))
+                .collect(),
+        };
+        self.local_types.insert(node.ident.to_string(), local_type);
+        syn::visit::visit_item_enum(self, node);
+    }
+
+    // `#[pallet::storage] #[pallet::unbounded] pub type Proposals<T> = StorageValue<_, Vec<Proposal>>;`
+    fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
+        let is_storage = node
+            .attrs
+            .iter()
+            .any(|attr| has_pallet_constant("pallet::storage".to_string(), attr));
+        if is_storage {
+            let name = node.ident.to_string();
+            self.storage
+                .insert(name.clone(), (self.file.clone(), node.ident.span().start().line));
+            if node
+                .attrs
+                .iter()
+                .any(|attr| has_pallet_constant("pallet::unbounded".to_string(), attr))
+            {
+                self.unbounded.insert(name);
+            }
+        }
+        syn::visit::visit_item_type(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let is_max_encoded_len = node
+            .trait_
+            .as_ref()
+            .and_then(|(_, path, _)| path.segments.last())
+            .is_some_and(|seg| seg.ident == "MaxEncodedLen");
+        if let (true, Type::Path(self_ty)) = (is_max_encoded_len, &*node.self_ty) {
+            self.max_encoded_len_impls
+                .extend(self_ty.path.segments.last().map(|seg| seg.ident.to_string()));
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+}
+
+/// Unbounded containers and local types without `MaxEncodedLen` reachable from a stored type
+#[derive(Default)]
+struct Bounds {
+    unbounded: BTreeSet<String>,               // e.g. `Vec` or `String in Proposal`
+    missing_max_encoded_len: BTreeSet<String>, // local types without `MaxEncodedLen`
+}
+
+/// Add a `boundedness` attribute to every storage item and findings for storage that can grow without a bound
+///
+/// Reads the `type` attribute of the Storage assets, so it runs after they were created.
+pub(crate) fn collect(files: &[ParsedFile], inventory: &mut AssetInventory) {
+    let mut visitor = BoundsVisitor::default();
+    for file in files {
+        visitor.file = file.path.clone();
+        visitor.visit_file(&file.syntax_tree);
+    }
+    let aliases = TypeAliases::collect(files);
+
+    if let Some((file, line)) = &visitor.without_storage_info {
+        inventory.findings.push(Finding {
+            rule: "without-storage-info".to_string(),
+            asset: "Pallet".to_string(),
+            message: "`#[pallet::without_storage_info]` turns off `MaxEncodedLen` checks for all storage, its size \
+                      is not accounted for in the proof of validity"
+                .to_string(),
+            file: Some(file.clone()),
+            line: Some(*line),
+        });
+    }
+
+    let mut findings = Vec::new();
+    for asset in &mut inventory.assets {
+        if !matches!(asset.category, AssetCategory::Storage(..)) {
+            continue;
+        }
+        let Some(storage_type) = asset.attributes.get("type") else {
+            continue;
+        };
+
+        let mut bounds = Bounds::default();
+        inspect(&visitor, &aliases, storage_type, None, &mut bounds, &mut BTreeSet::new());
+
+        // FRAME v1 storage from `decl_storage!` has no `#[pallet::storage]` and no storage info
+        let location = visitor.storage.get(&asset.name);
+        let declared_unbounded = visitor.unbounded.contains(&asset.name);
+        let enforced = location.is_some() && visitor.without_storage_info.is_none() && !declared_unbounded;
+        let boundedness = if !bounds.unbounded.is_empty() {
+            "unbounded"
+        } else if !enforced || !bounds.missing_max_encoded_len.is_empty() {
+            "unchecked"
+        } else {
+            "bounded"
+        };
+        asset.attributes.insert("boundedness".to_string(), boundedness.to_string());
+        if !bounds.unbounded.is_empty() {
+            let unbounded: Vec<&str> = bounds.unbounded.iter().map(String::as_str).collect();
+            asset.attributes.insert("unbounded_types".to_string(), unbounded.join(","));
+        }
+
+        let finding = |rule: &str, message: String| Finding {
+            rule: rule.to_string(),
+            asset: asset.name.clone(),
+            message,
+            file: location.map(|(file, _)| file.clone()),
+            line: location.map(|(_, line)| *line),
+        };
+        if declared_unbounded {
+            findings.push(finding(
+                "unbounded-storage-attribute",
+                format!("`{}` is marked `#[pallet::unbounded]`, its size is not accounted for", asset.name),
+            ));
+        }
+        if !bounds.unbounded.is_empty() {
+            let replacements: Vec<String> = bounds
+                .unbounded
+                .iter()
+                .map(|container| {
+                    let name = container.split(' ').next().unwrap_or_default();
+                    let bounded = UNBOUNDED_TYPES
+                        .iter()
+                        .find(|(unbounded, _)| *unbounded == name)
+                        .map_or("a bounded type", |(_, bounded)| bounded);
+                    format!("`{}` with `{}`", container, bounded)
+                })
+                .collect();
+            findings.push(finding(
+                "unbounded-storage-type",
+                format!(
+                    "`{}` stores containers without a length bound, replace {}",
+                    asset.name,
+                    replacements.join(", ")
+                ),
+            ));
+        }
+        if !bounds.missing_max_encoded_len.is_empty() {
+            let types: Vec<&str> = bounds.missing_max_encoded_len.iter().map(String::as_str).collect();
+            findings.push(finding(
+                "missing-max-encoded-len",
+                format!("`{}` stores `{}` which does not derive `MaxEncodedLen`", asset.name, types.join("`, `")),
+            ));
+        }
+    }
+    inventory.findings.extend(findings);
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+// Walk the identifiers of a type, following aliases and the fields of local types. Types are handled as token
+// strings since `decl_storage!` types such as `map hasher(blake2_128_concat) u32 => Vec<u8>` are not Rust types.
+fn inspect(
+    visitor: &BoundsVisitor,
+    aliases: &TypeAliases,
+    ty: &str,
+    owner: Option<&str>,
+    bounds: &mut Bounds,
+    visited: &mut BTreeSet<String>,
+) {
+    let expanded = match syn::parse_str::<Type>(ty) {
+        Ok(ty) => type_string(&aliases.expand(ty)),
+        Err(_) => ty.to_string(),
+    };
+    let Ok(tokens) = expanded.parse::<TokenStream>() else {
+        return;
+    };
+
+    let mut idents = Vec::new();
+    flatten_idents(tokens, &mut idents);
+    for ident in idents {
+        if UNBOUNDED_TYPES.iter().any(|(unbounded, _)| *unbounded == ident) {
+            bounds.unbounded.insert(match owner {
+                Some(owner) => format!("{} in {}", ident, owner),
+                None => ident,
+            });
+            continue;
+        }
+        let Some(local_type) = visitor.local_types.get(&ident) else {
+            continue;
+        };
+        if !visited.insert(ident.clone()) {
+            continue;
+        }
+        if !local_type.max_encoded_len && !visitor.max_encoded_len_impls.contains(&ident) {
+            bounds.missing_max_encoded_len.insert(ident.clone());
+        }
+        for field in &local_type.fields {
+            inspect(visitor, aliases, &type_string(field), Some(&ident), bounds, visited);
+        }
+    }
+}
+
+// `#[derive(Encode, Decode, MaxEncodedLen)]`
+fn derives_max_encoded_len(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("derive")
+            && attr.meta.require_list().is_ok_and(|list| {
+                list.tokens
+                    .clone()
+                    .into_iter()
+                    .any(|tt| matches!(tt, TokenTree::Ident(ident) if ident == "MaxEncodedLen"))
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{inventory_of, AssetInventory};
+
+    fn boundedness<'a>(inventory: &'a AssetInventory, storage: &str) -> (&'a str, Option<&'a str>) {
+        let asset = inventory.assets.iter().find(|asset| asset.name == storage).expect("storage item exists");
+        (
+            asset.attributes["boundedness"].as_str(),
+            asset.attributes.get("unbounded_types").map(String::as_str),
+        )
+    }
+
+    fn findings(inventory: &AssetInventory) -> Vec<(&str, &str)> {
+        let mut findings: Vec<(&str, &str)> = inventory
+            .findings
+            .iter()
+            .map(|finding| (finding.rule.as_str(), finding.asset.as_str()))
+            .collect();
+        findings.sort();
+        findings
+    }
+
+    #[test]
+    fn every_storage_item_is_classified() {
+        let inventory = inventory_of(
+            r#"
+            #[frame_support::pallet]
+            pub mod pallet {
+                #[derive(Encode, Decode, MaxEncodedLen)]
+                pub struct Kitty { dna: [u8; 16], price: u128 }
+                #[derive(Encode, Decode)]
+                pub struct Proposal { title: String }
+                #[derive(Encode, Decode, MaxEncodedLen)]
+                pub struct Note { text: BoundedVec<u8, ConstU32<64>> }
+                #[derive(Encode, Decode)]
+                pub struct Ledger { total: u128 }
+                impl MaxEncodedLen for Ledger {
+                    fn max_encoded_len() -> usize { 16 }
+                }
+                #[derive(Encode, Decode)]
+                pub struct Pending { since: u32 }
+
+                #[pallet::pallet]
+                pub struct Pallet<T>(_);
+
+                #[pallet::storage]
+                pub type Kitties<T> = StorageMap<_, Blake2_128Concat, u32, Kitty>;
+                #[pallet::storage]
+                pub type Owners<T: Config> = StorageValue<_, BoundedVec<T::AccountId, T::MaxOwners>>;
+                #[pallet::storage]
+                pub type Ledgers<T> = StorageValue<_, Ledger>;
+                #[pallet::storage]
+                pub type Proposals<T> = StorageValue<_, Vec<Proposal>>;
+                #[pallet::storage]
+                #[pallet::unbounded]
+                pub type Notes<T> = StorageValue<_, Note>;
+                #[pallet::storage]
+                pub type Queue<T> = StorageValue<_, Pending>;
+            }
+            "#,
+        );
+
+        assert_eq!(boundedness(&inventory, "Kitties"), ("bounded", None));
+        assert_eq!(boundedness(&inventory, "Owners"), ("bounded", None));
+        assert_eq!(boundedness(&inventory, "Ledgers"), ("bounded", None));
+        assert_eq!(boundedness(&inventory, "Proposals"), ("unbounded", Some("String in Proposal,Vec")));
+        assert_eq!(boundedness(&inventory, "Notes"), ("unchecked", None));
+        assert_eq!(boundedness(&inventory, "Queue"), ("unchecked", None));
+        assert_eq!(
+            findings(&inventory),
+            [
+                ("missing-max-encoded-len", "Proposals"),
+                ("missing-max-encoded-len", "Queue"),
+                ("unbounded-storage-attribute", "Notes"),
+                ("unbounded-storage-type", "Proposals"),
+            ]
+        );
+    }
+
+    #[test]
+    fn storage_without_enforced_bounds_is_unchecked() {
+        let inventory = inventory_of(
+            r#"
+            #[frame_support::pallet]
+            pub mod pallet {
+                #[pallet::pallet]
+                #[pallet::without_storage_info]
+                pub struct Pallet<T>(_);
+
+                #[pallet::storage]
+                pub type Count<T> = StorageValue<_, u32>;
+            }
+            "#,
+        );
+        assert_eq!(boundedness(&inventory, "Count"), ("unchecked", None));
+        assert_eq!(findings(&inventory), [("without-storage-info", "Pallet")]);
+
+        // FRAME v1 never enforces `MaxEncodedLen`
+        let inventory = inventory_of(
+            r#"
+            decl_storage! {
+                trait Store for Module<T: Config> as Example {
+                    Count get(fn count): u32;
+                    Names: map hasher(blake2_128_concat) T::AccountId => Vec<u8>;
+                }
+            }
+            "#,
+        );
+        assert_eq!(boundedness(&inventory, "Count"), ("unchecked", None));
+        assert_eq!(boundedness(&inventory, "Names"), ("unbounded", Some("Vec")));
+    }
+}
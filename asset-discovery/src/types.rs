@@ -0,0 +1,361 @@
+use crate::{bulk::is_non_runtime_file, has_pallet_constant, Parameter, ParsedFile};
+use quote::quote;
+use std::collections::{HashMap, HashSet};
+use syn::{
+    visit::Visit,
+    visit_mut::{self, VisitMut},
+    GenericArgument, PathArguments, Type,
+};
+
+// ----------------------------------------------Parameter Types----------------------------------------------------
+// Parameter types are rendered without the parameter name and token spacing, `BalanceOf<T>` rather than
+// `amount : BalanceOf < T >`. Local `type` aliases are followed so fuzzing and extreme-value generation can
+// target the underlying primitive, e.g. `type Balance = u128`.
+
+// Types with a known value range
+const PRIMITIVES: [&str; 16] = [
+    "bool", "char", "str", "String", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128",
+    "isize",
+];
+// Wrappers that keep a type primitive when everything inside is primitive
+const CONTAINERS: [&str; 3] = ["Vec", "Option", "Box"];
+// Guard against aliases referring to each other
+const MAX_ALIAS_DEPTH: usize = 8;
+
+/// Alias declared with `type Name<Params> = Target;`
+struct Alias {
+    params: Vec<String>,
+    target: Type,
+}
+
+/// Local type aliases of the analysed source
+#[derive(Default)]
+pub(crate) struct TypeAliases {
+    aliases: HashMap<String, Alias>,
+    // Names declared with different targets, e.g. in two modules, which are left unresolved
+    ambiguous: HashSet<String>,
+}
+
+impl<'ast> Visit<'ast> for TypeAliases {
+    // Storage items are declared as type aliases too and are not followed
+    fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
+        let is_storage = node
+            .attrs
+            .iter()
+            .any(|attr| has_pallet_constant("pallet::storage".to_string(), attr));
+        if !is_storage {
+            let name = node.ident.to_string();
+            let params: Vec<String> = node.generics.type_params().map(|param| param.ident.to_string()).collect();
+            match self.aliases.get(&name) {
+                // Which declaration a use refers to depends on the module it is in, so none is followed
+                Some(known) if known.params != params || type_string(&known.target) != type_string(&node.ty) => {
+                    self.ambiguous.insert(name);
+                }
+                Some(_) => {}
+                None => {
+                    self.aliases.insert(
+                        name,
+                        Alias {
+                            params,
+                            target: (*node.ty).clone(),
+                        },
+                    );
+                }
+            }
+        }
+        syn::visit::visit_item_type(self, node);
+    }
+}
+
+impl TypeAliases {
+    /// Aliases of the runtime files, the ones of mocks and tests would shadow them
+    pub(crate) fn collect(files: &[ParsedFile]) -> Self {
+        let mut aliases = TypeAliases::default();
+        for file in files.iter().filter(|file| !is_non_runtime_file(&file.path)) {
+            aliases.visit_file(&file.syntax_tree);
+        }
+        for name in &aliases.ambiguous {
+            aliases.aliases.remove(name);
+        }
+        aliases
+    }
+
+    /// Build a parameter from its name and the type as written in the source
+    pub(crate) fn parameter(&self, name: &str, ty: &str) -> Parameter {
+        let Ok(ty) = syn::parse_str::<Type>(ty) else {
+            return Parameter {
+                name: name.to_string(),
+                param_type: normalize(ty),
+                resolved: None,
+                primitive: None,
+            };
+        };
+
+        let param_type = type_string(&ty);
+        let resolved = self.expand(ty);
+        let resolved_string = type_string(&resolved);
+
+        Parameter {
+            name: name.to_string(),
+            primitive: is_primitive(&resolved).then(|| resolved_string.clone()),
+            resolved: (resolved_string != param_type).then_some(resolved_string),
+            param_type,
+        }
+    }
+
+    /// Follow the aliases in a type, e.g. `Barrier` to the tuple of barriers it names
+    pub(crate) fn resolve(&self, ty: &Type) -> String {
+        type_string(&self.expand(ty.clone()))
+    }
+
+    /// Replace every alias in a type by its target
+    pub(crate) fn expand(&self, mut ty: Type) -> Type {
+        AliasExpander {
+            aliases: &self.aliases,
+            depth: 0,
+        }
+        .visit_type_mut(&mut ty);
+        ty
+    }
+}
+
+// Replace alias names by their target, substituting the alias' generic parameters
+struct AliasExpander<'a> {
+    aliases: &'a HashMap<String, Alias>,
+    depth: usize,
+}
+
+impl VisitMut for AliasExpander<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        let expansion = match ty {
+            Type::Path(path) if path.qself.is_none() && path.path.segments.len() == 1 => {
+                let segment = &path.path.segments[0];
+                self.aliases.get(&segment.ident.to_string()).map(|alias| {
+                    let args = match &segment.arguments {
+                        PathArguments::AngleBracketed(args) => args
+                            .args
+                            .iter()
+                            .filter_map(|arg| match arg {
+                                GenericArgument::Type(ty) => Some(ty.clone()),
+                                _ => None,
+                            })
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    let mut target = alias.target.clone();
+                    ParamSubstitution {
+                        substitutions: alias.params.iter().cloned().zip(args).collect(),
+                    }
+                    .visit_type_mut(&mut target);
+                    target
+                })
+            }
+            _ => None,
+        };
+
+        match expansion {
+            Some(target) if self.depth < MAX_ALIAS_DEPTH => {
+                *ty = target;
+                self.depth += 1;
+                self.visit_type_mut(ty);
+                self.depth -= 1;
+            }
+            _ => visit_mut::visit_type_mut(self, ty),
+        }
+    }
+}
+
+// `type BalanceOf<T> = ..` used as `BalanceOf<Runtime>` puts `Runtime` wherever the alias says `T`
+struct ParamSubstitution {
+    substitutions: HashMap<String, Type>,
+}
+
+impl VisitMut for ParamSubstitution {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(path) = ty {
+            if let Some(ident) = path.path.get_ident() {
+                if let Some(substitution) = self.substitutions.get(&ident.to_string()) {
+                    *ty = substitution.clone();
+                    return;
+                }
+            }
+        }
+        visit_mut::visit_type_mut(self, ty);
+    }
+
+    // `<T as Config>::Balance` and `T::Balance` keep their shape with the substituted type
+    fn visit_path_mut(&mut self, path: &mut syn::Path) {
+        if path.segments.len() > 1 {
+            let first = path.segments[0].ident.to_string();
+            if let Some(Type::Path(substitution)) = self.substitutions.get(&first) {
+                if substitution.qself.is_none() {
+                    let rest: Vec<_> = path.segments.iter().skip(1).cloned().collect();
+                    path.segments = substitution.path.segments.iter().cloned().chain(rest).collect();
+                }
+            }
+        }
+        visit_mut::visit_path_mut(self, path);
+    }
+}
+
+/// Render a type without token spacing, e.g. `Vec<(T::AccountId, u32)>`
+pub(crate) fn type_string(ty: &Type) -> String {
+    normalize(&quote!(#ty).to_string())
+}
+
+/// Render a path without token spacing, e.g. `fungibles::Mutate<T::AccountId>`
+pub(crate) fn path_string(path: &syn::Path) -> String {
+    normalize(&quote!(#path).to_string())
+}
+
+/// Render a function signature without generics, e.g. `fn burn(&mut self, amount: Balance) -> DispatchResult`
+pub(crate) fn signature_string(sig: &syn::Signature) -> String {
+    let params: Vec<String> = sig
+        .inputs
+        .iter()
+        .map(|param| match param {
+            syn::FnArg::Receiver(receiver) => normalize(&quote!(#receiver).to_string()),
+            syn::FnArg::Typed(pat_type) => {
+                let pat = &pat_type.pat;
+                format!("{}: {}", normalize(&quote!(#pat).to_string()), type_string(&pat_type.ty))
+            }
+        })
+        .collect();
+    let output = match &sig.output {
+        syn::ReturnType::Default => String::new(),
+        syn::ReturnType::Type(_, ty) => format!(" -> {}", type_string(ty)),
+    };
+    format!("fn {}({}){}", sig.ident, params.join(", "), output)
+}
+
+/// Collapse the spacing `quote` puts between tokens
+pub(crate) fn normalize(ty: &str) -> String {
+    let mut normalized = ty.split_whitespace().collect::<Vec<_>>().join(" ");
+    for (from, to) in [
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ,", ","),
+        (" ::", "::"),
+        (":: ", "::"),
+        ("& ", "&"),
+        ("( ", "("),
+        (" )", ")"),
+        ("[ ", "["),
+        (" ]", "]"),
+        (" ;", ";"),
+    ] {
+        normalized = normalized.replace(from, to);
+    }
+    // `Fn (u32)` and `hasher (blake2_128_concat)`, a tuple never follows an identifier
+    let mut collapsed = String::with_capacity(normalized.len());
+    let mut chars = normalized.chars().peekable();
+    while let Some(c) = chars.next() {
+        let follows_ident = collapsed.chars().next_back().is_some_and(|last| last.is_alphanumeric() || last == '_');
+        if c == ' ' && follows_ident && chars.peek() == Some(&'(') {
+            continue;
+        }
+        collapsed.push(c);
+    }
+    collapsed
+}
+
+// Only primitives and standard containers of primitives, e.g. `u128`, `Vec<u8>` or `[u8; 32]`
+fn is_primitive(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let Some(segment) = path.path.segments.last() else {
+                return false;
+            };
+            let name = segment.ident.to_string();
+            if path.path.segments.len() == 1 && PRIMITIVES.contains(&name.as_str()) {
+                return matches!(segment.arguments, PathArguments::None);
+            }
+            if !CONTAINERS.contains(&name.as_str()) {
+                return false;
+            }
+            match &segment.arguments {
+                PathArguments::AngleBracketed(args) => args.args.iter().all(|arg| match arg {
+                    GenericArgument::Type(ty) => is_primitive(ty),
+                    _ => false,
+                }),
+                _ => false,
+            }
+        }
+        Type::Array(array) => is_primitive(&array.elem),
+        Type::Slice(slice) => is_primitive(&slice.elem),
+        Type::Reference(reference) => is_primitive(&reference.elem),
+        Type::Tuple(tuple) => !tuple.elems.is_empty() && tuple.elems.iter().all(is_primitive),
+        Type::Paren(paren) => is_primitive(&paren.elem),
+        Type::Group(group) => is_primitive(&group.elem),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_source, SourceFile};
+    use std::path::PathBuf;
+
+    fn aliases_of(files: &[(&str, &str)]) -> TypeAliases {
+        let files: Vec<ParsedFile> = files
+            .iter()
+            .map(|(path, code)| {
+                parse_source(&SourceFile {
+                    path: PathBuf::from(path),
+                    content: code.to_string(),
+                })
+            })
+            .collect();
+        TypeAliases::collect(&files)
+    }
+
+    #[test]
+    fn normalize_drops_token_spacing() {
+        assert_eq!(normalize("BalanceOf < T >"), "BalanceOf<T>");
+        assert_eq!(normalize("Vec < (T :: AccountId , u32) >"), "Vec<(T::AccountId, u32)>");
+        assert_eq!(normalize("& [u8 ; 32]"), "&[u8; 32]");
+        assert_eq!(normalize("Box < dyn Fn (u32) -> (u32 , u64) >"), "Box<dyn Fn(u32) -> (u32, u64)>");
+        assert_eq!(normalize("< T as frame_system :: Config > :: AccountId"), "<T as frame_system::Config>::AccountId");
+    }
+
+    #[test]
+    fn aliases_resolve_to_primitives() {
+        let aliases = aliases_of(&[(
+            "src/lib.rs",
+            r#"
+            pub type Balance = u128;
+            pub type BalanceOf<T> = <T as Config>::Balance;
+            pub type Amounts<T> = Vec<(T, Balance)>;
+            "#,
+        )]);
+
+        let balance = aliases.parameter("amount", "Balance");
+        assert_eq!((balance.resolved.as_deref(), balance.primitive.as_deref()), (Some("u128"), Some("u128")));
+        let amounts = aliases.parameter("amounts", "Amounts<u32>");
+        assert_eq!(amounts.resolved.as_deref(), Some("Vec<(u32, u128)>"));
+        assert_eq!(amounts.primitive.as_deref(), Some("Vec<(u32, u128)>"));
+        let associated = aliases.parameter("amount", "BalanceOf<Runtime>");
+        assert_eq!(associated.resolved.as_deref(), Some("<Runtime as Config>::Balance"));
+        assert_eq!(associated.primitive, None);
+        let unknown = aliases.parameter("who", "T::AccountId");
+        assert_eq!((unknown.param_type.as_str(), unknown.resolved), ("T::AccountId", None));
+    }
+
+    #[test]
+    fn mock_aliases_do_not_shadow_the_pallet() {
+        let aliases = aliases_of(&[("src/lib.rs", "type Balance = u128;"), ("src/mock.rs", "type Balance = u64;")]);
+        assert_eq!(aliases.parameter("amount", "Balance").primitive.as_deref(), Some("u128"));
+    }
+
+    #[test]
+    fn conflicting_aliases_are_not_followed() {
+        let aliases = aliases_of(&[
+            ("src/lib.rs", "type Balance = u128; type Index = u32;"),
+            ("src/types.rs", "type Balance = u64; type Index = u32;"),
+        ]);
+        assert_eq!(aliases.parameter("amount", "Balance").resolved, None);
+        assert_eq!(aliases.parameter("index", "Index").primitive.as_deref(), Some("u32"));
+    }
+}
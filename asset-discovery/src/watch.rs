@@ -0,0 +1,361 @@
+use crate::{
+    bulk::crate_root, migrations, output::category_name, parse_source, read_sources, result_writer, AppError,
+    AssetInventory, OutputFormat, ParsedFile, SourceFile,
+};
+use notify::RecursiveMode;
+use notify_debouncer_mini::new_debouncer;
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+// ----------------------------------------------Watch Mode---------------------------------------------------------
+// Parsed files are cached by content hash so a save only re-parses the files that changed. Below a workspace the
+// inventory of every crate is cached too, and only the crates containing a changed file run through the visitors
+// again. The passes look across the files of a crate, so a crate is the smallest unit that can be re-analysed.
+
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+type Parser = fn(&[ParsedFile]) -> Result<AssetInventory, AppError>;
+
+type CacheKey = (PathBuf, u64);
+
+/// Result of refreshing the cache against the sources on disk
+struct Refresh {
+    keys: Vec<CacheKey>,
+    files: Vec<ParsedFile>,
+    // Files that had to be parsed again
+    reparsed: Vec<String>,
+    // Whether any file was added, changed or deleted since the last refresh
+    changed: bool,
+}
+
+/// Parsed files keyed by path and content hash, and the inventory of every crate built from them
+#[derive(Default)]
+struct AstCache {
+    entries: HashMap<CacheKey, ParsedFile>,
+    // (crate root, (keys of its files, inventory or `None` when the parser rejected the crate))
+    crates: HashMap<PathBuf, (Vec<CacheKey>, Option<AssetInventory>)>,
+}
+
+impl AstCache {
+    /// Parse the sources, reusing every file whose content did not change
+    /// Parsed files are returned in source order
+    fn refresh(&mut self, sources: &[SourceFile]) -> Refresh {
+        let mut keys = Vec::with_capacity(sources.len());
+        let mut files = Vec::with_capacity(sources.len());
+        let mut reparsed = Vec::new();
+
+        for source in sources {
+            let key = (source.path.clone(), content_hash(&source.content));
+            let file = match self.entries.remove(&key) {
+                Some(file) => file,
+                None => {
+                    let file = parse_source(source);
+                    reparsed.push(file.path.clone());
+                    file
+                }
+            };
+            keys.push(key);
+            files.push(file);
+        }
+        // Whatever is left belongs to deleted files or outdated content
+        let changed = !reparsed.is_empty() || !self.entries.is_empty();
+        self.entries.clear();
+        Refresh {
+            keys,
+            files,
+            reparsed,
+            changed,
+        }
+    }
+
+    fn store(&mut self, keys: Vec<CacheKey>, files: Vec<ParsedFile>) {
+        self.entries.extend(keys.into_iter().zip(files));
+    }
+
+    /// Run the parser over every crate below `root` whose files changed and merge the inventories of all crates
+    /// Returns the inventory and the number of crates analysed again. Crates the parser rejects, e.g. crates
+    /// without a runtime in runtime mode, are left out unless no crate is accepted.
+    fn analyse(
+        &mut self,
+        root: &Path,
+        parser: Parser,
+        keys: &[CacheKey],
+        files: &[ParsedFile],
+    ) -> Result<(AssetInventory, usize), AppError> {
+        let mut crates: BTreeMap<PathBuf, Vec<usize>> = BTreeMap::new();
+        for (index, (path, _)) in keys.iter().enumerate() {
+            crates.entry(crate_root(root, path)).or_default().push(index);
+        }
+
+        let mut inventory = AssetInventory {
+            source: None,
+            assets: Vec::new(),
+            findings: Vec::new(),
+            unparsed: Vec::new(),
+        };
+        let mut analysed = 0;
+        let mut rejected = None;
+        let mut cached = HashMap::new();
+        for (crate_dir, indices) in crates {
+            let crate_keys: Vec<CacheKey> = indices.iter().map(|&index| keys[index].clone()).collect();
+            let crate_inventory = match self.crates.remove(&crate_dir) {
+                Some((previous_keys, crate_inventory)) if previous_keys == crate_keys => crate_inventory,
+                _ => {
+                    analysed += 1;
+                    let crate_files: Vec<ParsedFile> = indices.iter().map(|&index| files[index].clone()).collect();
+                    match parser(&crate_files) {
+                        Ok(crate_inventory) => Some(crate_inventory),
+                        Err(e) => {
+                            rejected.get_or_insert(e);
+                            None
+                        }
+                    }
+                }
+            };
+
+            if let Some(crate_inventory) = &crate_inventory {
+                inventory.assets.extend(crate_inventory.assets.iter().cloned());
+                inventory.findings.extend(crate_inventory.findings.iter().cloned());
+                inventory.unparsed.extend(crate_inventory.unparsed.iter().cloned());
+            }
+            cached.insert(crate_dir, (crate_keys, crate_inventory));
+        }
+        // Crates that disappeared are dropped with the old map
+        let accepted = cached.values().any(|(_, crate_inventory)| crate_inventory.is_some());
+        self.crates = cached;
+
+        match (accepted, rejected) {
+            (false, Some(e)) => Err(e),
+            _ => Ok((inventory, analysed)),
+        }
+    }
+}
+
+/// Run discovery once, then again on every change below `root` until interrupted
+pub(crate) fn run(
+    root: &Path,
+    parser: Parser,
+    previous: Option<&AssetInventory>,
+    format: OutputFormat,
+    output: Option<&Path>,
+) -> Result<(), AppError> {
+    let mut cache = AstCache::default();
+    // Without a previous run there is always something to report
+    let Some(mut inventory) = discover(root, parser, previous, &mut cache, None)? else {
+        return Ok(());
+    };
+    result_writer(&inventory, format, output)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(DEBOUNCE, tx)?;
+    debouncer.watcher().watch(root, RecursiveMode::Recursive)?;
+    println!("Watching {} for changes, press Ctrl-C to stop", root.display());
+
+    for result in rx {
+        let events = match result {
+            Ok(events) => events,
+            Err(e) => {
+                eprintln!("Error watching source code: {}", e);
+                continue;
+            }
+        };
+        if !events.iter().any(|event| is_watched_source(&event.path)) {
+            continue;
+        }
+
+        // Reading the sources raises events too, the cache tells whether any content really changed
+        match discover(root, parser, previous, &mut cache, Some(&inventory)) {
+            Ok(Some(updated)) => {
+                result_writer(&updated, format, output)?;
+                inventory = updated;
+            }
+            Ok(None) => {}
+            // A file may be half-written or deleted mid-save, keep the last good inventory
+            Err(e) => eprintln!("Error re-running discovery: {}", e),
+        }
+    }
+    Ok(())
+}
+
+// Read, parse what changed, run the visitors and print how the surface moved since the last run
+// Returns `None` when no source changed since the last run
+fn discover(
+    root: &Path,
+    parser: Parser,
+    previous: Option<&AssetInventory>,
+    cache: &mut AstCache,
+    last: Option<&AssetInventory>,
+) -> Result<Option<AssetInventory>, AppError> {
+    let started = Instant::now();
+    let sources = read_sources(root)?;
+    let Refresh {
+        keys,
+        files,
+        reparsed,
+        changed,
+    } = cache.refresh(&sources);
+    if !changed && last.is_some() {
+        cache.store(keys, files);
+        return Ok(None);
+    }
+    let result = cache.analyse(root, parser, &keys, &files);
+    cache.store(keys, files);
+
+    let (mut inventory, analysed) = result?;
+    inventory.source = Some(root.display().to_string());
+    if let Some(previous) = previous {
+        let findings = migrations::compare_versions(previous, &inventory);
+        inventory.findings.extend(findings);
+    }
+
+    if last.is_some() {
+        println!("\nChanged: {}", reparsed.join(", "));
+    }
+    println!(
+        "Re-parsed {} of {} files and re-analysed {} of {} crates in {} ms: {} assets, {} findings",
+        reparsed.len(),
+        sources.len(),
+        analysed,
+        cache.crates.len(),
+        started.elapsed().as_millis(),
+        inventory.assets.len(),
+        inventory.findings.len()
+    );
+    if let Some(last) = last {
+        for line in surface_changes(last, &inventory) {
+            println!("  {}", line);
+        }
+    }
+    Ok(Some(inventory))
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+// Assets added and removed, findings raised and resolved between two runs
+fn surface_changes(before: &AssetInventory, after: &AssetInventory) -> Vec<String> {
+    let assets = |inventory: &AssetInventory| -> BTreeSet<String> {
+        inventory
+            .assets
+            .iter()
+            .map(|asset| format!("{} {}", category_name(&asset.category), asset.name))
+            .collect()
+    };
+    let findings = |inventory: &AssetInventory| -> BTreeSet<String> {
+        inventory
+            .findings
+            .iter()
+            .map(|finding| format!("[{}] {}", finding.rule, finding.message))
+            .collect()
+    };
+
+    let (assets_before, assets_after) = (assets(before), assets(after));
+    let (findings_before, findings_after) = (findings(before), findings(after));
+
+    let mut changes = Vec::new();
+    changes.extend(assets_after.difference(&assets_before).map(|asset| format!("+ {}", asset)));
+    changes.extend(assets_before.difference(&assets_after).map(|asset| format!("- {}", asset)));
+    changes.extend(findings_after.difference(&findings_before).map(|finding| format!("new finding {}", finding)));
+    changes.extend(findings_before.difference(&findings_after).map(|finding| format!("resolved {}", finding)));
+    if changes.is_empty() {
+        changes.push("no surface changes".to_string());
+    }
+    changes
+}
+
+fn is_watched_source(path: &Path) -> bool {
+    let in_target = path.components().any(|component| component.as_os_str() == "target");
+    !in_target && path.extension().is_some_and(|ext| ext == "rs")
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AstCache;
+    use crate::{runtime, SourceFile};
+    use std::{fs, path::PathBuf};
+
+    const PALLET: &str = r#"
+        #[frame_support::pallet]
+        pub mod pallet {
+            #[pallet::call]
+            impl<T: Config> Pallet<T> {
+                pub fn store(origin: OriginFor<T>) -> DispatchResult { ensure_signed(origin)?; Ok(()) }
+            }
+        }
+    "#;
+
+    // Workspace with one crate per name, only the manifests are written
+    fn workspace(name: &str, crates: &[&str]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("asset-discovery-{}-{}", std::process::id(), name));
+        for krate in crates {
+            fs::create_dir_all(root.join(krate).join("src")).unwrap();
+            fs::write(root.join(krate).join("Cargo.toml"), "").unwrap();
+        }
+        root
+    }
+
+    fn source(root: &std::path::Path, path: &str, content: &str) -> SourceFile {
+        SourceFile {
+            path: root.join(path),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn only_crates_with_changed_files_are_analysed_again() {
+        let root = workspace("changed", &["items", "votes"]);
+        let mut cache = AstCache::default();
+        let mut run = |sources: &[SourceFile]| {
+            let refresh = cache.refresh(sources);
+            let result = cache.analyse(&root, crate::parser, &refresh.keys, &refresh.files);
+            cache.store(refresh.keys, refresh.files);
+            let (inventory, analysed) = result.unwrap();
+            (refresh.reparsed.len(), analysed, inventory.assets.len())
+        };
+
+        let mut sources = vec![
+            source(&root, "items/src/lib.rs", PALLET),
+            source(&root, "votes/src/lib.rs", PALLET),
+            source(&root, "votes/src/tests.rs", "fn store_works() {}"),
+        ];
+        let (_, _, assets) = run(&sources);
+        assert_eq!(run(&sources), (0, 0, assets));
+
+        // A test file only changes its own crate
+        sources[2].content = "fn store_fails() {}".to_string();
+        assert_eq!(run(&sources), (1, 1, assets));
+
+        sources.remove(0);
+        assert_eq!(run(&sources), (0, 0, assets / 2));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn crates_the_parser_rejects_are_left_out() {
+        let root = workspace("rejected", &["runtime", "items"]);
+        let runtime = "construct_runtime!(pub enum Runtime { System: frame_system = 0, Items: pallet_items = 1 });";
+        let sources = [source(&root, "items/src/lib.rs", PALLET), source(&root, "runtime/src/lib.rs", runtime)];
+
+        let mut cache = AstCache::default();
+        let refresh = cache.refresh(&sources);
+        let (inventory, analysed) = cache.analyse(&root, runtime::parser, &refresh.keys, &refresh.files).unwrap();
+        let names: Vec<&str> = inventory.assets.iter().map(|asset| asset.name.as_str()).collect();
+        assert_eq!((names, analysed), (vec!["System", "Items"], 2));
+
+        // Without any runtime the error of the parser is reported
+        let mut cache = AstCache::default();
+        let refresh = cache.refresh(&sources[..1]);
+        assert!(cache.analyse(&root, runtime::parser, &refresh.keys, &refresh.files).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,374 @@
+use crate::{
+    types::{path_string, type_string, TypeAliases},
+    Asset, AssetCategory, AssetInventory, Finding, ParsedFile,
+};
+use std::collections::HashMap;
+use syn::{spanned::Spanned, visit::Visit};
+
+// ----------------------------------------------XCM Discovery------------------------------------------------------
+// Cross-chain messages enter a parachain through the XCM executor, whose `Config` decides which messages pass the
+// barrier, which chains are trusted as reserves or teleporters and how foreign origins become local ones.
+// Over-permissive barriers and teleport trust are the most damaging misconfigurations, so the relevant config
+// items are recorded with their aliases resolved, and pallets sending or executing XCM are recorded as well.
+
+// Security relevant items of `xcm_executor::Config`
+const EXECUTOR_ITEMS: [&str; 9] = [
+    "Barrier",
+    "IsReserve",
+    "IsTeleporter",
+    "OriginConverter",
+    "Trader",
+    "AssetTransactor",
+    "SafeCallFilter",
+    "UniversalAliases",
+    "Aliasers",
+];
+// Security relevant items of `pallet_xcm::Config`
+const PALLET_XCM_ITEMS: [&str; 6] = [
+    "XcmRouter",
+    "SendXcmOrigin",
+    "ExecuteXcmOrigin",
+    "XcmExecuteFilter",
+    "XcmTeleportFilter",
+    "XcmReserveTransferFilter",
+];
+// Functions sending or executing XCM wherever they are called from
+const XCM_FUNCTIONS: [(&str, &str); 7] = [
+    ("send_xcm", "send"),
+    ("validate_send", "send"),
+    ("send", "send"),
+    ("execute_xcm", "execute"),
+    ("execute_xcm_in_credit", "execute"),
+    ("prepare_and_execute", "execute"),
+    ("execute", "execute"),
+];
+// (config item, pattern in the resolved type, rule, message)
+const MISCONFIGURATIONS: [(&str, &str, &str, &str); 5] = [
+    (
+        "Barrier",
+        "AllowUnpaidExecutionFrom<Everything>",
+        "permissive-xcm-barrier",
+        "the barrier lets any origin execute XCM without paying for it",
+    ),
+    (
+        "Barrier",
+        "AllowExplicitUnpaidExecutionFrom<Everything>",
+        "permissive-xcm-barrier",
+        "the barrier lets any origin request unpaid execution",
+    ),
+    (
+        "IsTeleporter",
+        "Everything",
+        "broad-teleport-trust",
+        "assets teleported from any location are trusted, a compromised chain can mint them here",
+    ),
+    (
+        "XcmExecuteFilter",
+        "Everything",
+        "unrestricted-xcm-execute",
+        "any local origin may execute arbitrary XCM through pallet_xcm",
+    ),
+    (
+        "SafeCallFilter",
+        "Everything",
+        "unrestricted-xcm-transact",
+        "`Transact` may dispatch any call of the runtime",
+    ),
+];
+
+/// XCM config item or XCM call found in the source
+struct XcmItem {
+    name: String,
+    // "executor_config", "pallet_xcm_config", "send" or "execute"
+    kind: &'static str,
+    // Bound type for config items, called path for calls
+    value: String,
+    resolved: Option<String>,
+    line: usize,
+}
+
+/// Visitor collecting XCM config impls and XCM calls of a single file
+struct XcmVisitor<'a> {
+    aliases: &'a TypeAliases,
+    imports: HashMap<String, String>, // (name in scope, imported path) from the `use` items of the file
+    items: Vec<XcmItem>,
+    function: Option<String>, // function currently visited, owner of XCM calls
+}
+
+impl<'ast> Visit<'ast> for XcmVisitor<'_> {
+    // `impl xcm_executor::Config for XcmConfig` and `impl pallet_xcm::Config for Runtime`, also when the trait or
+    // its crate was imported: `use xcm_executor::Config; impl Config for XcmConfig`
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        if let Some((_, trait_path, _)) = &node.trait_ {
+            let mut segments: Vec<String> = trait_path.segments.iter().map(|seg| seg.ident.to_string()).collect();
+            if let Some(imported) = segments.first().and_then(|first| self.imports.get(first)) {
+                let imported: Vec<String> = imported.split("::").map(str::to_string).collect();
+                segments.splice(..1, imported);
+            }
+            let (kind, relevant): (&'static str, &[&str]) = match segments.join("::").as_str() {
+                "xcm_executor::Config" => ("executor_config", &EXECUTOR_ITEMS),
+                "pallet_xcm::Config" => ("pallet_xcm_config", &PALLET_XCM_ITEMS),
+                _ => ("", &[]),
+            };
+
+            for item in &node.items {
+                let syn::ImplItem::Type(config_type) = item else {
+                    continue;
+                };
+                let name = config_type.ident.to_string();
+                if !relevant.contains(&name.as_str()) {
+                    continue;
+                }
+                let value = type_string(&config_type.ty);
+                let resolved = self.aliases.resolve(&config_type.ty);
+                self.items.push(XcmItem {
+                    name,
+                    kind,
+                    resolved: (resolved != value).then_some(resolved),
+                    value,
+                    line: config_type.span().start().line,
+                });
+            }
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        let outer = self.function.replace(node.sig.ident.to_string());
+        syn::visit::visit_item_fn(self, node);
+        self.function = outer;
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        let outer = self.function.replace(node.sig.ident.to_string());
+        syn::visit::visit_impl_item_fn(self, node);
+        self.function = outer;
+    }
+
+    // `send_xcm::<T::XcmRouter>(..)`, `T::XcmExecutor::execute(..)`, `pallet_xcm::Pallet::<T>::send(..)`
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if let (syn::Expr::Path(path), Some(function)) = (&*node.func, &self.function) {
+            let called = path.path.segments.last().map(|seg| seg.ident.to_string()).unwrap_or_default();
+            let rendered = path_string(&path.path);
+            // Plain `send` and `execute` are too common a name, they only count on an XCM type
+            let on_xcm_type = rendered.contains("Xcm") || rendered.contains("pallet_xcm");
+            let kind = XCM_FUNCTIONS
+                .iter()
+                .find(|(name, _)| *name == called && (called.contains("xcm") || on_xcm_type))
+                .map(|(_, kind)| *kind);
+
+            if let Some(kind) = kind {
+                self.items.push(XcmItem {
+                    name: function.clone(),
+                    kind,
+                    value: rendered,
+                    resolved: None,
+                    line: node.span().start().line,
+                });
+            }
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+}
+
+/// Add XCM config items and XCM calls as assets, raising findings for known misconfigurations
+pub(crate) fn collect(files: &[ParsedFile], inventory: &mut AssetInventory) {
+    let aliases = TypeAliases::collect(files);
+    for file in files {
+        let mut visitor = XcmVisitor {
+            aliases: &aliases,
+            imports: imports(&file.syntax_tree),
+            items: Vec::new(),
+            function: None,
+        };
+        visitor.visit_file(&file.syntax_tree);
+
+        for item in visitor.items {
+            let effective = item.resolved.as_deref().unwrap_or(&item.value);
+            for (config_item, pattern, rule, message) in MISCONFIGURATIONS {
+                if item.name == config_item && contains_type(effective, pattern) {
+                    inventory.findings.push(Finding {
+                        rule: rule.to_string(),
+                        asset: item.name.clone(),
+                        message: format!("`{}`: {}", item.name, message),
+                        file: Some(file.path.clone()),
+                        line: Some(item.line),
+                    });
+                }
+            }
+
+            let attribute = if item.kind.ends_with("config") { "type" } else { "call" };
+            let mut asset = Asset::new("none", &item.name, AssetCategory::Xcm(item.name.clone(), item.kind.to_string()))
+                .with_attribute(attribute, item.value.as_str())
+                .with_attribute("line", item.line.to_string());
+            if let Some(resolved) = item.resolved {
+                asset = asset.with_attribute("resolved", resolved);
+            }
+            inventory.assets.push(asset);
+        }
+    }
+}
+
+// ----------------------------------------------Helper Functions--------------------------------------------------
+
+// Names brought into scope by the `use` items of a file, e.g. `Config` for `use xcm_executor::Config;` and
+// `Executor` for `use xcm_executor::{self as Executor}`. Glob imports are not followed.
+fn imports(syntax_tree: &syn::File) -> HashMap<String, String> {
+    fn walk(tree: &syn::UseTree, prefix: &mut Vec<String>, imports: &mut HashMap<String, String>) {
+        match tree {
+            syn::UseTree::Path(path) => {
+                prefix.push(path.ident.to_string());
+                walk(&path.tree, prefix, imports);
+                prefix.pop();
+            }
+            syn::UseTree::Name(name) if name.ident == "self" => {
+                if let Some(last) = prefix.last() {
+                    imports.insert(last.clone(), prefix.join("::"));
+                }
+            }
+            syn::UseTree::Name(name) => {
+                let path = prefix.iter().cloned().chain([name.ident.to_string()]).collect::<Vec<_>>();
+                imports.insert(name.ident.to_string(), path.join("::"));
+            }
+            syn::UseTree::Rename(rename) => {
+                let imported = match rename.ident == "self" {
+                    true => prefix.clone(),
+                    false => prefix.iter().cloned().chain([rename.ident.to_string()]).collect(),
+                };
+                imports.insert(rename.rename.to_string(), imported.join("::"));
+            }
+            syn::UseTree::Group(group) => {
+                for tree in &group.items {
+                    walk(tree, prefix, imports);
+                }
+            }
+            syn::UseTree::Glob(_) => {}
+        }
+    }
+
+    struct UseVisitor(HashMap<String, String>);
+    impl<'ast> Visit<'ast> for UseVisitor {
+        fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+            walk(&node.tree, &mut Vec::new(), &mut self.0);
+        }
+    }
+
+    let mut visitor = UseVisitor(HashMap::new());
+    visitor.visit_file(syntax_tree);
+    visitor.0
+}
+
+// Whole type name match, so `Everything` does not match `EverythingBut<..>`
+fn contains_type(ty: &str, pattern: &str) -> bool {
+    ty.match_indices(pattern).any(|(start, matched)| {
+        let before = ty[..start].chars().next_back();
+        let after = ty[start + matched.len()..].chars().next();
+        let is_boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric() && c != '_');
+        is_boundary(before) && (is_boundary(after) || pattern.ends_with('>'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{inventory_of, AssetCategory};
+
+    #[test]
+    fn imported_config_traits_are_recognised() {
+        let inventory = inventory_of(
+            r#"
+            use xcm_executor::{Config, XcmExecutor};
+            use pallet_xcm as xcm_pallet;
+
+            pub type Barrier = (TakeWeightCredit, AllowUnpaidExecutionFrom<Everything>);
+
+            pub struct XcmConfig;
+            impl Config for XcmConfig {
+                type Barrier = Barrier;
+                type IsTeleporter = NativeAsset;
+                type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+            }
+
+            impl xcm_pallet::Config for Runtime {
+                type XcmExecuteFilter = Everything;
+                type XcmRouter = XcmRouter;
+            }
+
+            impl frame_system::Config for Runtime {
+                type Barrier = Everything;
+            }
+            "#,
+        );
+
+        let items: Vec<(&str, &str)> = inventory
+            .assets
+            .iter()
+            .filter_map(|asset| match &asset.category {
+                AssetCategory::Xcm(name, kind) => Some((name.as_str(), kind.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("Barrier", "executor_config"),
+                ("IsTeleporter", "executor_config"),
+                ("XcmExecuteFilter", "pallet_xcm_config"),
+                ("XcmRouter", "pallet_xcm_config"),
+            ]
+        );
+        let barrier = &inventory.assets[0];
+        assert_eq!(
+            barrier.attributes.get("resolved").map(String::as_str),
+            Some("(TakeWeightCredit, AllowUnpaidExecutionFrom<Everything>)")
+        );
+
+        let rules: Vec<&str> = inventory.findings.iter().map(|finding| finding.rule.as_str()).collect();
+        assert_eq!(rules, ["permissive-xcm-barrier", "unrestricted-xcm-execute"]);
+    }
+
+    #[test]
+    fn only_calls_on_xcm_types_count_as_sending_or_executing() {
+        let inventory = inventory_of(
+            r#"
+            #[frame_support::pallet]
+            pub mod pallet {
+                #[pallet::call]
+                impl<T: Config> Pallet<T> {
+                    pub fn notify(origin: OriginFor<T>, dest: Location) -> DispatchResult {
+                        ensure_root(origin)?;
+                        send_xcm::<T::XcmRouter>(dest, Xcm(vec![]))?;
+                        pallet_xcm::Pallet::<T>::send(origin, dest, message)?;
+                        Ok(())
+                    }
+
+                    pub fn run(origin: OriginFor<T>) -> DispatchResult {
+                        ensure_root(origin)?;
+                        T::XcmExecutor::execute(origin, message, &mut hash, weight);
+                        T::Scheduler::execute(task);
+                        Self::send(origin)
+                    }
+                }
+            }
+            "#,
+        );
+
+        let calls: Vec<(&str, &str, &str)> = inventory
+            .assets
+            .iter()
+            .filter_map(|asset| match &asset.category {
+                AssetCategory::Xcm(name, kind) => {
+                    Some((name.as_str(), kind.as_str(), asset.attributes["call"].as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                ("notify", "send", "send_xcm::<T::XcmRouter>"),
+                ("notify", "send", "pallet_xcm::Pallet::<T>::send"),
+                ("run", "execute", "T::XcmExecutor::execute"),
+            ]
+        );
+    }
+}
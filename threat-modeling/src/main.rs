@@ -1,21 +1,135 @@
 use threat_modeling::utils::helpers::*;
-use std::path::Path;
+use threat_modeling::utils::catalogue::Catalogue;
+use threat_modeling::utils::checks::default_checks;
+use threat_modeling::utils::document::ThreatModelDocument;
+use threat_modeling::utils::mapping::{ThreatMapper, ThreatModel};
+use threat_modeling::utils::risk::{RiskScorer, RiskWeights};
+use threat_modeling::utils::rules::load_rule_pack;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    // Malformed assets fail the load unless `--lenient` is given
+    let mode = if std::env::args().any(|arg| arg == "--lenient") {
+        LoadMode::Lenient
+    } else {
+        LoadMode::Strict
+    };
+
     // User input file path
     println!("Please enter the path to the JSON file:");
     let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
+    if let Err(err) = std::io::stdin().read_line(&mut input) {
+        eprintln!("Failed to read the path: {}", err);
+        return ExitCode::FAILURE;
+    }
     let file_path = Path::new(input.trim());
     if !file_path.exists() {
         eprintln!("File does not exist");
+        return ExitCode::FAILURE;
+    }
+
+    // Read the JSON file and parse it into the internal data structure
+    let inventory = match load_asset_inventory(file_path, mode) {
+        Ok(inventory) => inventory,
+        Err(err) => {
+            eprintln!("Failed to load the asset inventory: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    for err in &inventory.skipped {
+        eprintln!("Skipped {}", err);
+    }
+
+    // Checks look into the source given with `--source <path>`, or the one the inventory was discovered in
+    let args: Vec<String> = std::env::args().collect();
+    let source = args
+        .iter()
+        .position(|arg| arg == "--source")
+        .and_then(|i| args.get(i + 1).cloned())
+        .or(inventory.source.clone());
+    let sources = match source.as_deref().map(|source| read_sources(Path::new(source))) {
+        Some(Ok(loaded)) => {
+            for err in &loaded.skipped {
+                eprintln!("Skipped {}", err);
+            }
+            loaded.sources
+        }
+        Some(Err(err)) => {
+            eprintln!("Source not read, checks only use the asset attributes: {}", err);
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+
+    // Rule packs given with `--rules <path>`, and the `threat-rules` directory next to the source if there is one
+    let mut packs: Vec<PathBuf> = args
+        .iter()
+        .zip(args.iter().skip(1))
+        .filter(|(flag, _)| *flag == "--rules")
+        .map(|(_, path)| PathBuf::from(path))
+        .collect();
+    if let Some(source) = source.as_deref().map(Path::new) {
+        let directory = if source.is_dir() { Some(source) } else { source.parent() };
+        packs.extend(directory.map(|dir| dir.join("threat-rules")).filter(|dir| dir.is_dir()));
+    }
+    let mut rules = Vec::new();
+    for pack in &packs {
+        match load_rule_pack(pack) {
+            Ok(pack_rules) => rules.extend(pack_rules),
+            Err(err) => {
+                eprintln!("Failed to load the rule pack: {}", err);
+                return ExitCode::FAILURE;
+            }
+        }
     }
 
-    // Read JSON file
-    let json = read_json(file_path).unwrap();
+    // Risk weights and level thresholds given with `--weights <path>`, the defaults otherwise
+    let weights = match args.iter().position(|arg| arg == "--weights").and_then(|i| args.get(i + 1)) {
+        Some(path) => match RiskWeights::load(Path::new(path)) {
+            Ok(weights) => weights,
+            Err(err) => {
+                eprintln!("Failed to load the risk weights: {}", err);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => RiskWeights::default(),
+    };
 
-    // Parse the JSON file into the internal data structure
-    let assets = parse_asset_inventory_into_asset_model(json);
-    println!("Parsed assets: {:?}", assets);
+    // Attach threats to the assets with the rule packs and the built-in rules, then confirm them with the checks
+    let catalogue = Catalogue::builtin();
+    let mapper = ThreatMapper::with_rule_packs(rules, &catalogue);
+    let mut model = ThreatModel::build(inventory.assets, inventory.skipped.len(), &mapper);
+    model.attach_findings(&inventory.findings);
+    model.check(&default_checks(), &sources);
+    model.score(&RiskScorer::new(weights));
+    println!("Vulnerability catalogue {} ({} entries)", catalogue.version, catalogue.entries.len());
+    print!("{}", model);
+
+    // Save the model with `--save <path>`, merged into the document already there so hand edits are kept
+    if let Some(path) = args.iter().position(|arg| arg == "--save").and_then(|i| args.get(i + 1)) {
+        let path = Path::new(path);
+        let fresh = ThreatModelDocument::new(model, source, Some(catalogue.version.clone()));
+        let document = if path.exists() {
+            match ThreatModelDocument::load(path) {
+                Ok(mut document) => {
+                    document.merge(fresh);
+                    document
+                }
+                Err(err) => {
+                    eprintln!("Failed to load the threat model document: {}", err);
+                    return ExitCode::FAILURE;
+                }
+            }
+        } else {
+            fresh
+        };
+        if let Err(err) = document.save(path) {
+            eprintln!("Failed to save the threat model document: {}", err);
+            return ExitCode::FAILURE;
+        }
+        println!("Threat model saved to {}", path.display());
+    }
+    ExitCode::SUCCESS
 }
@@ -1,10 +1,13 @@
+use crate::utils::risk::RiskScore;
 use crate::utils::threats::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // ----------------------------------------Pallet Model Data Structures-------------------------------------
 // This is the internal model for pallets
 
 /// Asset visibility classification
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Visibility {
     Public,
     Private,
@@ -12,7 +15,7 @@ pub enum Visibility {
 }
 
 /// Asset category classification
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AssetCategory {
     PublicFunction {
         parameters: Vec<Parameter>,
@@ -35,34 +38,105 @@ pub enum AssetCategory {
         name: String,
         fields: Vec<Parameter>,
     },
+    // Pallet reached through a Config type or supertrait, its bounds are in the "bound" attribute
+    Dependency {
+        name: String,
+        kind: String,
+    },
+    // Trait implemented for the pallet that other pallets call without an origin, e.g. `fungibles::Mutate`
+    ExposedTrait {
+        name: String,
+        // (method name, signature)
+        methods: Vec<(String, String)>,
+    },
+    // XCM config item, or function sending or executing XCM
+    Xcm {
+        name: String,
+        // "executor_config", "pallet_xcm_config", "send" or "execute"
+        kind: String,
+    },
+    Benchmark {
+        name: String,
+        // Dispatchables the benchmark measures
+        dispatchables: Vec<String>,
+    },
+    // Version declared through `#[pallet::storage_version]`
+    StorageVersion {
+        version: String,
+    },
+    Migration {
+        name: String,
+        // How it is hooked in, e.g. "OnRuntimeUpgrade" or "VersionedMigration"
+        kind: String,
+    },
+    // Pallet of a `construct_runtime!`
+    RuntimePallet {
+        name: String,
+        // Crate path, e.g. "pallet_balances"
+        path: String,
+        // Explicitly included parts, empty when they are implicit
+        parts: Vec<String>,
+    },
+    // Config item of a pallet in a runtime, with the type or value bound to it
+    ConfigItem {
+        pallet: String,
+        name: String,
+        bound: String,
+    },
+}
+
+impl AssetCategory {
+    /// Category name as written by asset-discovery, e.g. "PublicFunction" or "Events"
+    pub fn name(&self) -> &'static str {
+        match self {
+            AssetCategory::PublicFunction { .. } => "PublicFunction",
+            AssetCategory::Helper { .. } => "Helper",
+            AssetCategory::Storage(_) => "Storage",
+            AssetCategory::Constant { .. } => "Constant",
+            AssetCategory::Event { .. } => "Events",
+            AssetCategory::Error { .. } => "Error",
+            AssetCategory::Dependency { .. } => "Dependency",
+            AssetCategory::ExposedTrait { .. } => "ExposedTrait",
+            AssetCategory::Xcm { .. } => "Xcm",
+            AssetCategory::Benchmark { .. } => "Benchmark",
+            AssetCategory::StorageVersion { .. } => "StorageVersion",
+            AssetCategory::Migration { .. } => "Migration",
+            AssetCategory::RuntimePallet { .. } => "RuntimePallet",
+            AssetCategory::ConfigItem { .. } => "ConfigItem",
+        }
+    }
 }
 
 /// Parameter definition for functions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Parameter {
     pub name: String,
     pub param_type: String,
 }
 
 // Supporting struct for Storage variant
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub visibility: Visibility,
     pub name: String,
 }
 
 /// Core asset representation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Asset {
     pub name: String,
     pub visibility: Visibility,
     pub category: AssetCategory,
+    // Attributes found by asset-discovery, e.g. "origin" -> "signed" or "type" -> "StorageMap<..>"
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    #[serde(default)]
     pub properties: Properties,
 }
 
 /// Risk level is for the asset itself, not the threats mapped to it
 /// This is used to prioritize assets for security analysis
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum RiskLevel {
     Critical,
     High,
@@ -73,12 +147,15 @@ pub enum RiskLevel {
 
 /// Extensible security properties
 /// This structure is generated during the asset <-> threat mapping process and added to the asset model
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Properties {
     // Threats mapped to this asset
     pub threats: Vec<Threat>,
     // Risk level is for the asset itself, not the threats mapped to it
     pub risk_level: RiskLevel,
+    // Score the risk level was derived from, with its breakdown
+    pub risk_score: Option<RiskScore>,
     // Information will be used for symbolic execution and targeted unit tests generation
     pub for_symbolic_execution: ForSymbolicExecution
 }
@@ -0,0 +1,83 @@
+use crate::utils::mapping::MappingRule;
+use crate::utils::rules::Conditions;
+use crate::utils::threats::*;
+use serde::Deserialize;
+
+// -----------------------------------------------Vulnerability Catalogue-----------------------------------------
+// Vulnerability classes of past FRAME audits and advisories, bundled with the tool so every pallet is checked
+// against them. Entries use the conditions of rule packs and are kept in `catalogue/frame.toml`, whose version is
+// reported with the results.
+
+const BUILTIN_CATALOGUE: &str = include_str!("../../catalogue/frame.toml");
+
+/// Versioned catalogue of known vulnerability classes
+#[derive(Debug, Clone)]
+pub struct Catalogue {
+    pub version: String,
+    pub entries: Vec<CatalogueEntry>,
+}
+
+/// Known vulnerability class with the rule matching it
+#[derive(Debug, Clone)]
+pub struct CatalogueEntry {
+    // Stable identifier, e.g. "FRAME-001"
+    pub id: String,
+    pub title: String,
+    // Named after the entry id, its mitigation is the remediation text and its references those of the entry
+    pub rule: MappingRule,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CatalogueFile {
+    version: String,
+    #[serde(rename = "entry")]
+    entries: Vec<EntrySpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EntrySpec {
+    id: String,
+    title: String,
+    threat: ThreatType,
+    check: SecurityCheck,
+    remediation: String,
+    references: Vec<String>,
+    when: Conditions,
+}
+
+impl Catalogue {
+    // Catalogue shipped with the tool, checked when the tool is built and run
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_CATALOGUE).expect("the built-in catalogue is valid")
+    }
+
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        let file: CatalogueFile = toml::from_str(content)?;
+        let entries = file
+            .entries
+            .into_iter()
+            .map(|entry| CatalogueEntry {
+                rule: MappingRule {
+                    name: entry.id.clone(),
+                    predicate: entry.when.into_predicate(),
+                    threat: entry.threat,
+                    how_to_check: entry.check,
+                    mitigation: Some(entry.remediation),
+                    references: entry.references,
+                },
+                id: entry.id,
+                title: entry.title,
+            })
+            .collect();
+        Ok(Self {
+            version: file.version,
+            entries,
+        })
+    }
+
+    pub fn rules(&self) -> Vec<MappingRule> {
+        self.entries.iter().map(|entry| entry.rule.clone()).collect()
+    }
+}
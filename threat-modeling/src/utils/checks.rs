@@ -0,0 +1,468 @@
+use crate::utils::assets::*;
+use crate::utils::mapping::mentions;
+use crate::utils::threats::*;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use syn::{spanned::Spanned, visit::Visit};
+
+// -----------------------------------------------Security Checks-------------------------------------------------
+// Checks run against the asset model and the pallet source it was discovered from, and turn threats into findings
+// with evidence. Functions are looked up in the source by name. FRAME v1 `decl_module!` bodies are macro tokens
+// and are not looked into, checks on them rely on the attributes asset-discovery recorded.
+
+// Functions checking the origin of a dispatchable, `T::ForceOrigin::ensure_origin(origin)?` included
+const ORIGIN_CHECKS: [&str; 6] = [
+    "ensure_signed",
+    "ensure_root",
+    "ensure_none",
+    "ensure_signed_or_root",
+    "ensure_origin",
+    "try_origin",
+];
+// Parameter types whose length the caller chooses
+const UNBOUNDED_INPUTS: [(&str, &str); 2] = [("Vec", "BoundedVec<_, T::MaxLen>"), ("String", "BoundedVec<u8, T::MaxLen>")];
+// Identifiers of a weight expression that do not make it depend on anything
+const CONSTANT_WEIGHT: [&str; 5] = ["Weight", "from_parts", "from_ref_time", "from_all", "zero"];
+
+/// Parsed pallet source file
+#[derive(Clone)]
+pub struct Source {
+    pub path: String,
+    pub syntax_tree: syn::File,
+}
+
+/// Location of the code a finding is about
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidenceSpan {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    // Code at the location, e.g. `balance + amount`
+    pub code: String,
+}
+
+/// How likely a finding is a real issue rather than a false positive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Confidence {
+    High,
+    Medium,
+    Low,
+}
+
+/// Result of a check, a threat confirmed on an asset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub asset: String,
+    pub threat: ThreatType,
+    pub check: SecurityCheck,
+    pub message: String,
+    // Missing when the asset could not be found in the source
+    pub evidence: Option<EvidenceSpan>,
+    pub confidence: Confidence,
+    pub fix: String,
+}
+
+/// Security check run against the asset model and its source
+pub trait Check {
+    fn kind(&self) -> SecurityCheck;
+    fn run(&self, assets: &[Asset], sources: &[Source]) -> Vec<Finding>;
+}
+
+/// Built-in checks
+pub fn default_checks() -> Vec<Box<dyn Check>> {
+    vec![
+        Box::new(OriginCheck),
+        Box::new(CheckedArithmetic),
+        Box::new(BoundedInputs),
+        Box::new(WeightCoverage),
+    ]
+}
+
+pub fn run_checks(checks: &[Box<dyn Check>], assets: &[Asset], sources: &[Source]) -> Vec<Finding> {
+    checks.iter().flat_map(|check| check.run(assets, sources)).collect()
+}
+
+/// Dispatchables without an origin check
+pub struct OriginCheck;
+
+impl Check for OriginCheck {
+    fn kind(&self) -> SecurityCheck {
+        SecurityCheck::OriginCheck
+    }
+
+    fn run(&self, assets: &[Asset], sources: &[Source]) -> Vec<Finding> {
+        let functions = Functions::collect(sources);
+        let mut findings = Vec::new();
+        for asset in dispatchables(assets, &functions) {
+            // asset-discovery records the origin when it found an `ensure_*` check
+            if asset.attributes.contains_key("origin") {
+                continue;
+            }
+            let Some(function) = functions.dispatchable(&asset.name) else {
+                continue;
+            };
+            let mut idents = Vec::new();
+            flatten_idents(function.item.block.to_token_stream(), &mut idents);
+            if idents.iter().any(|ident| ORIGIN_CHECKS.contains(&ident.as_str())) {
+                continue;
+            }
+
+            // Handing the origin to a helper may still check it there
+            let passed_on = idents.iter().any(|ident| ident == "origin");
+            findings.push(Finding {
+                asset: asset.name.clone(),
+                threat: ThreatType::MissingOriginCheck,
+                check: self.kind(),
+                message: if passed_on {
+                    format!("`{}` checks its origin nowhere in its body, only passes it on", asset.name)
+                } else {
+                    format!("`{}` never checks its origin, anyone can call it", asset.name)
+                },
+                evidence: Some(function.evidence(&function.item.sig.ident)),
+                confidence: if passed_on { Confidence::Medium } else { Confidence::High },
+                fix: "Start the call with `ensure_signed(origin)?`, `ensure_root(origin)?` or the origin it requires"
+                    .to_string(),
+            });
+        }
+        findings
+    }
+}
+
+/// Unchecked `+`, `-`, `*`, `/` and `%` in pallet functions
+pub struct CheckedArithmetic;
+
+impl Check for CheckedArithmetic {
+    fn kind(&self) -> SecurityCheck {
+        SecurityCheck::CheckedArithmetic
+    }
+
+    fn run(&self, assets: &[Asset], sources: &[Source]) -> Vec<Finding> {
+        let functions = Functions::collect(sources);
+        let mut findings = Vec::new();
+        for asset in assets {
+            if !matches!(asset.category, AssetCategory::PublicFunction { .. } | AssetCategory::Helper { .. }) {
+                continue;
+            }
+            for function in functions.named(&asset.name) {
+                let mut finder = ArithmeticFinder::default();
+                finder.visit_block(&function.item.block);
+
+                for (expr, threat) in finder.operations {
+                    let (message, confidence, fix) = match threat {
+                        ThreatType::DivisionByZero => (
+                            format!("`{}` divides by a value that can be zero", asset.name),
+                            Confidence::High,
+                            "Use `checked_div` or `checked_rem` and return an error on `None`",
+                        ),
+                        _ => (
+                            format!("`{}` uses unchecked arithmetic, which wraps in release builds", asset.name),
+                            Confidence::Medium,
+                            "Use `checked_*` and return an error on `None`, or `saturating_*` where clamping is intended",
+                        ),
+                    };
+                    findings.push(Finding {
+                        asset: asset.name.clone(),
+                        threat,
+                        check: self.kind(),
+                        message,
+                        evidence: Some(function.evidence(&expr)),
+                        confidence,
+                        fix: fix.to_string(),
+                    });
+                }
+            }
+        }
+        findings
+    }
+}
+
+/// Dispatchable parameters without a length bound
+pub struct BoundedInputs;
+
+impl Check for BoundedInputs {
+    fn kind(&self) -> SecurityCheck {
+        SecurityCheck::InputSanitization
+    }
+
+    fn run(&self, assets: &[Asset], sources: &[Source]) -> Vec<Finding> {
+        let functions = Functions::collect(sources);
+        let mut findings = Vec::new();
+        for asset in dispatchables(assets, &functions) {
+            let AssetCategory::PublicFunction { parameters, .. } = &asset.category else {
+                continue;
+            };
+            for param in parameters {
+                let Some((container, bounded)) = UNBOUNDED_INPUTS
+                    .iter()
+                    .find(|(container, _)| mentions(&param.param_type, container))
+                else {
+                    continue;
+                };
+                let evidence = functions.dispatchable(&asset.name).and_then(|function| {
+                    function.item.sig.inputs.iter().find_map(|input| match input {
+                        syn::FnArg::Typed(pat_type) if pat_type.pat.to_token_stream().to_string() == param.name => {
+                            Some(function.evidence(pat_type))
+                        }
+                        _ => None,
+                    })
+                });
+                findings.push(Finding {
+                    asset: asset.name.clone(),
+                    threat: ThreatType::UserControlledInput,
+                    check: self.kind(),
+                    message: format!(
+                        "`{}` takes `{}: {}`, the caller chooses its length",
+                        asset.name, param.name, param.param_type
+                    ),
+                    evidence,
+                    confidence: Confidence::High,
+                    fix: format!("Take a `{}` instead of the `{}`", bounded, container),
+                });
+            }
+        }
+        findings
+    }
+}
+
+/// Dispatchables whose weight is missing, constant or does not grow with their iterations
+pub struct WeightCoverage;
+
+impl Check for WeightCoverage {
+    fn kind(&self) -> SecurityCheck {
+        SecurityCheck::WeightCoverage
+    }
+
+    fn run(&self, assets: &[Asset], sources: &[Source]) -> Vec<Finding> {
+        let functions = Functions::collect(sources);
+        let mut findings = Vec::new();
+        for asset in dispatchables(assets, &functions) {
+            let function = functions.dispatchable(&asset.name);
+            let evidence = function.map(|function| function.evidence(&function.item.sig.ident));
+            let finding = |threat, message, confidence, fix: &str| Finding {
+                asset: asset.name.clone(),
+                threat,
+                check: SecurityCheck::WeightCoverage,
+                message,
+                evidence: evidence.clone(),
+                confidence,
+                fix: fix.to_string(),
+            };
+
+            // Iterations whose count the weight ignores, found by asset-discovery
+            if asset.attributes.get("weight_accounts").is_some_and(|accounts| accounts != "yes") {
+                let iterations = asset.attributes.get("iterations").cloned().unwrap_or_default();
+                findings.push(finding(
+                    ThreatType::UnboundedIteration,
+                    format!("the weight of `{}` does not account for its iterations ({})", asset.name, iterations),
+                    Confidence::High,
+                    "Pass the iteration count to the `WeightInfo` function and benchmark its worst case",
+                ));
+            }
+
+            let Some(function) = function else {
+                continue;
+            };
+            let weight = function.item.attrs.iter().find_map(|attr| {
+                let is_weight = attr.path().segments.last().is_some_and(|seg| seg.ident == "weight");
+                match (is_weight, &attr.meta) {
+                    (true, syn::Meta::List(list)) => Some(list.tokens.clone()),
+                    _ => None,
+                }
+            });
+            match weight {
+                None if !function.inherits_weight => findings.push(finding(
+                    ThreatType::UnderweightExtrinsic,
+                    format!("`{}` has no `#[pallet::weight]`, it is charged no weight", asset.name),
+                    Confidence::Medium,
+                    "Add `#[pallet::weight(T::WeightInfo::..)]` backed by a benchmark",
+                )),
+                Some(weight) if is_constant(weight.clone()) => findings.push(finding(
+                    ThreatType::UnderweightExtrinsic,
+                    format!("`{}` is charged a constant weight `{}`", asset.name, weight),
+                    Confidence::Medium,
+                    "Replace the constant with a benchmarked `T::WeightInfo` function",
+                )),
+                _ => {}
+            }
+        }
+        findings
+    }
+}
+
+// -----------------------------------------------Helper Functions----------------------------------------------
+
+/// Function found in the source
+struct Function<'a> {
+    file: &'a str,
+    item: FunctionItem,
+    dispatchable: bool,     // in a `#[pallet::call]` impl
+    inherits_weight: bool,  // `#[pallet::call(weight(..))]` provides the weight
+}
+
+/// Free functions and methods share what the checks need
+struct FunctionItem {
+    attrs: Vec<syn::Attribute>,
+    sig: syn::Signature,
+    block: syn::Block,
+}
+
+impl Function<'_> {
+    fn evidence<T: Spanned + ToTokens>(&self, node: &T) -> EvidenceSpan {
+        let start = node.span().start();
+        EvidenceSpan {
+            file: self.file.to_string(),
+            line: start.line,
+            column: start.column,
+            code: node.to_token_stream().to_string(),
+        }
+    }
+}
+
+/// Every function of the sources
+struct Functions<'a> {
+    functions: Vec<Function<'a>>,
+}
+
+impl<'a> Functions<'a> {
+    fn collect(sources: &'a [Source]) -> Self {
+        let mut functions = Vec::new();
+        for source in sources {
+            let mut visitor = FunctionVisitor {
+                file: &source.path,
+                functions: &mut functions,
+            };
+            visitor.visit_file(&source.syntax_tree);
+        }
+        Self { functions }
+    }
+
+    fn named<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s Function<'a>> {
+        self.functions.iter().filter(move |function| function.item.sig.ident == name)
+    }
+
+    fn dispatchable<'s>(&'s self, name: &'s str) -> Option<&'s Function<'a>> {
+        self.named(name).find(|function| function.dispatchable)
+    }
+}
+
+struct FunctionVisitor<'a, 'f> {
+    file: &'a str,
+    functions: &'f mut Vec<Function<'a>>,
+}
+
+impl<'ast> Visit<'ast> for FunctionVisitor<'_, '_> {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.functions.push(Function {
+            file: self.file,
+            item: FunctionItem {
+                attrs: node.attrs.clone(),
+                sig: node.sig.clone(),
+                block: (*node.block).clone(),
+            },
+            dispatchable: false,
+            inherits_weight: false,
+        });
+        syn::visit::visit_item_fn(self, node);
+    }
+
+    // `#[pallet::call]` or `#[pallet::call(weight(<T as Config>::WeightInfo))]`
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let call = node.attrs.iter().find(|attr| {
+            let segments: Vec<String> = attr.path().segments.iter().map(|seg| seg.ident.to_string()).collect();
+            segments == ["pallet", "call"]
+        });
+        let inherits_weight = call.is_some_and(|attr| {
+            attr.meta
+                .require_list()
+                .is_ok_and(|list| list.tokens.clone().into_iter().any(|tt| matches!(tt, TokenTree::Ident(ident) if ident == "weight")))
+        });
+        for item in &node.items {
+            if let syn::ImplItem::Fn(method) = item {
+                self.functions.push(Function {
+                    file: self.file,
+                    item: FunctionItem {
+                        attrs: method.attrs.clone(),
+                        sig: method.sig.clone(),
+                        block: method.block.clone(),
+                    },
+                    dispatchable: call.is_some(),
+                    inherits_weight,
+                });
+            }
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+}
+
+/// Visitor collecting arithmetic that can overflow or divide by zero
+#[derive(Default)]
+struct ArithmeticFinder {
+    operations: Vec<(syn::ExprBinary, ThreatType)>,
+}
+
+impl<'ast> Visit<'ast> for ArithmeticFinder {
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        use syn::BinOp;
+        let threat = match node.op {
+            // A length plus a literal, `items.len() as u32 + 1`, is bounded by the memory holding the items
+            BinOp::Add(_) if is_length(&node.left) && is_literal(&node.right)
+                || is_literal(&node.left) && is_length(&node.right) => None,
+            BinOp::Add(_) | BinOp::Sub(_) | BinOp::Mul(_) | BinOp::AddAssign(_) | BinOp::SubAssign(_)
+            | BinOp::MulAssign(_) => (!is_literal(&node.left) || !is_literal(&node.right))
+                .then_some(ThreatType::ArithmeticOverflow),
+            // Dividing by a non-zero literal cannot fail
+            BinOp::Div(_) | BinOp::Rem(_) | BinOp::DivAssign(_) | BinOp::RemAssign(_) => {
+                let nonzero = matches!(&*node.right, syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. })
+                    if int.base10_digits().chars().any(|c| c != '0'));
+                (!nonzero).then_some(ThreatType::DivisionByZero)
+            }
+            _ => None,
+        };
+        if let Some(threat) = threat {
+            self.operations.push((node.clone(), threat));
+        }
+        syn::visit::visit_expr_binary(self, node);
+    }
+}
+
+fn is_literal(expr: &syn::Expr) -> bool {
+    matches!(expr, syn::Expr::Lit(_))
+}
+
+// `items.len()`, `items.len() as u32` or `(items.len())`
+fn is_length(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::MethodCall(call) => call.method == "len" && call.args.is_empty(),
+        syn::Expr::Cast(cast) => is_length(&cast.expr),
+        syn::Expr::Paren(paren) => is_length(&paren.expr),
+        _ => false,
+    }
+}
+
+// Public functions asset-discovery marked as calls of `#[pallet::call]` or `decl_module!`, or found in a
+// `#[pallet::call]` impl of the sources, other `pub fn` cannot be called by users
+fn dispatchables<'s>(assets: &'s [Asset], functions: &'s Functions<'_>) -> impl Iterator<Item = &'s Asset> {
+    assets.iter().filter(|asset| {
+        matches!(asset.category, AssetCategory::PublicFunction { .. })
+            && (asset.attributes.get("dispatchable").is_some_and(|value| value == "yes")
+                || functions.dispatchable(&asset.name).is_some())
+    })
+}
+
+// `10_000` or `Weight::from_parts(10_000, 0)`, a weight that does not depend on anything
+fn is_constant(weight: TokenStream) -> bool {
+    let mut idents = Vec::new();
+    flatten_idents(weight, &mut idents);
+    idents.iter().all(|ident| CONSTANT_WEIGHT.contains(&ident.as_str()))
+}
+
+fn flatten_idents(tokens: TokenStream, idents: &mut Vec<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => idents.push(ident.to_string()),
+            TokenTree::Group(group) => flatten_idents(group.stream(), idents),
+            _ => {}
+        }
+    }
+}
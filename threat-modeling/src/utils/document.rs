@@ -0,0 +1,232 @@
+use crate::utils::assets::*;
+use crate::utils::checks::Finding;
+use crate::utils::mapping::ThreatModel;
+use crate::utils::threats::MitigationStrategy;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// -----------------------------------------------Threat Model Documents-------------------------------------------
+// A threat model saved as JSON or YAML so it can live in the repository of the pallet. The document is meant to be
+// reviewed and edited by hand: mitigations can be rewritten and marked as implemented, threats added and symbolic
+// execution notes filled in. Running the tool again merges the fresh model into the saved one, refreshing what
+// the tool derives (categories, attributes, threats, scores, findings) and keeping what people wrote. Threats
+// written by hand have no `rule`, assets written by hand have the attribute "manual" -> "yes".
+
+// Format version written by this tool, increased with every incompatible change of the document layout
+pub const DOCUMENT_VERSION: u32 = 1;
+
+/// Versioned threat model document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreatModelDocument {
+    pub version: u32,
+    // Source the assets were discovered in
+    #[serde(default)]
+    pub source: Option<String>,
+    // Version of the vulnerability catalogue the threats were mapped with
+    #[serde(default)]
+    pub catalogue: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+    // Assets of the inventory left out while loading it
+    #[serde(default)]
+    pub skipped: usize,
+}
+
+impl ThreatModelDocument {
+    pub fn new(model: ThreatModel, source: Option<String>, catalogue: Option<String>) -> Self {
+        Self {
+            version: DOCUMENT_VERSION,
+            source,
+            catalogue,
+            assets: model.assets,
+            findings: model.findings,
+            skipped: model.skipped,
+        }
+    }
+
+    pub fn into_model(self) -> ThreatModel {
+        ThreatModel {
+            assets: self.assets,
+            skipped: self.skipped,
+            findings: self.findings,
+        }
+    }
+
+    /// Load a document from a .json, .yaml or .yml file
+    pub fn load(path: &Path) -> Result<Self, DocumentError> {
+        let content = std::fs::read_to_string(path)?;
+        let document: Self = match Format::of(path)? {
+            Format::Json => serde_json::from_str(&content)?,
+            Format::Yaml => serde_yaml::from_str(&content)?,
+        };
+        if document.version > DOCUMENT_VERSION {
+            return Err(DocumentError::UnsupportedVersion(document.version));
+        }
+        Ok(document)
+    }
+
+    /// Save the document as pretty JSON or YAML, by the extension of the path
+    pub fn save(&self, path: &Path) -> Result<(), DocumentError> {
+        let content = match Format::of(path)? {
+            Format::Json => serde_json::to_string_pretty(self)? + "\n",
+            Format::Yaml => serde_yaml::to_string(self)?,
+        };
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    // Merge a freshly built document into this one. Assets are matched by name and category: what the tool derives
+    // is taken from the fresh asset, attributes added by hand are kept, threats whose rule no longer fires are
+    // dropped, edited or implemented mitigations of the others are kept, and saved symbolic execution notes are
+    // kept. Saved assets the tool no longer finds are dropped unless they were written by hand, new ones are appended.
+    pub fn merge(&mut self, fresh: ThreatModelDocument) {
+        self.assets.retain(|saved| {
+            is_manual(saved)
+                || fresh
+                    .assets
+                    .iter()
+                    .any(|asset| asset.name == saved.name && asset.category.name() == saved.category.name())
+        });
+        for fresh_asset in fresh.assets {
+            let saved = self
+                .assets
+                .iter_mut()
+                .find(|asset| asset.name == fresh_asset.name && asset.category.name() == fresh_asset.category.name());
+            match saved {
+                Some(saved) => merge_asset(saved, fresh_asset),
+                None => self.assets.push(fresh_asset),
+            }
+        }
+        self.version = DOCUMENT_VERSION;
+        self.source = fresh.source.or(self.source.take());
+        self.catalogue = fresh.catalogue.or(self.catalogue.take());
+        self.findings = fresh.findings;
+        self.skipped = fresh.skipped;
+    }
+}
+
+// -----------------------------------------------Error Handling------------------------------------------------
+
+/// Error reading or writing a threat model document
+#[derive(Debug)]
+pub enum DocumentError {
+    IoError(std::io::Error),
+    JsonError(serde_json::Error),
+    YamlError(serde_yaml::Error),
+    UnsupportedFormat(String),
+    UnsupportedVersion(u32),
+}
+
+impl std::fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentError::IoError(err) => write!(f, "IO Error: {}", err),
+            DocumentError::JsonError(err) => write!(f, "JSON Error: {}", err),
+            DocumentError::YamlError(err) => write!(f, "YAML Error: {}", err),
+            DocumentError::UnsupportedFormat(file) => write!(f, "{}: expected a .json, .yaml or .yml document", file),
+            DocumentError::UnsupportedVersion(version) => write!(
+                f,
+                "document version {} is newer than the supported version {}",
+                version, DOCUMENT_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DocumentError::IoError(err) => Some(err),
+            DocumentError::JsonError(err) => Some(err),
+            DocumentError::YamlError(err) => Some(err),
+            DocumentError::UnsupportedFormat(_) | DocumentError::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DocumentError {
+    fn from(err: std::io::Error) -> Self {
+        DocumentError::IoError(err)
+    }
+}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(err: serde_json::Error) -> Self {
+        DocumentError::JsonError(err)
+    }
+}
+
+impl From<serde_yaml::Error> for DocumentError {
+    fn from(err: serde_yaml::Error) -> Self {
+        DocumentError::YamlError(err)
+    }
+}
+
+// -----------------------------------------------Helper Functions----------------------------------------------
+
+enum Format {
+    Json,
+    Yaml,
+}
+
+impl Format {
+    fn of(path: &Path) -> Result<Self, DocumentError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Ok(Format::Json),
+            Some("yaml") | Some("yml") => Ok(Format::Yaml),
+            _ => Err(DocumentError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+}
+
+// Asset written into the document by hand rather than discovered
+fn is_manual(asset: &Asset) -> bool {
+    asset.attributes.get("manual").is_some_and(|value| value == "yes")
+}
+
+// Mitigation rewritten or marked as implemented by hand, mitigations from before `generated` count as edited
+fn is_edited(mitigation: &MitigationStrategy) -> bool {
+    mitigation.implementation_status || mitigation.generated.as_ref() != Some(&mitigation.description)
+}
+
+fn merge_asset(saved: &mut Asset, fresh: Asset) {
+    saved.visibility = fresh.visibility;
+    saved.category = fresh.category;
+    // Derived attributes are refreshed, attributes the tool does not derive were added by hand
+    saved.attributes.extend(fresh.attributes);
+
+    let properties = &mut saved.properties;
+    // Threats of rules that no longer fire on the asset go, threats written by hand stay
+    let fresh_threats = &fresh.properties.threats;
+    properties
+        .threats
+        .retain(|known| known.rule.is_empty() || fresh_threats.iter().any(|threat| threat.name == known.name));
+    for threat in fresh.properties.threats {
+        match properties.threats.iter_mut().find(|known| known.name == threat.name) {
+            Some(known) => {
+                known.how_to_check = threat.how_to_check;
+                // A threat written by hand stays one, the tool does not take it over
+                if !known.rule.is_empty() {
+                    known.rule = threat.rule;
+                }
+                // A mitigation edited or marked as implemented by hand wins over the template of the rule
+                if !known.mitigation.as_ref().is_some_and(is_edited) {
+                    known.mitigation = threat.mitigation;
+                }
+                for reference in threat.references {
+                    if !known.references.contains(&reference) {
+                        known.references.push(reference);
+                    }
+                }
+            }
+            None => properties.threats.push(threat),
+        }
+    }
+    properties.risk_level = fresh.properties.risk_level;
+    properties.risk_score = fresh.properties.risk_score;
+    if properties.for_symbolic_execution == Default::default() {
+        properties.for_symbolic_execution = fresh.properties.for_symbolic_execution;
+    }
+}
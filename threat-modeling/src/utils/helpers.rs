@@ -1,100 +1,463 @@
-use std::error::Error;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
+use serde_json::Value;
 use crate::utils::assets::*;
+use crate::utils::checks::Source;
 // -----------------------------------------------Helper Functions----------------------------------------------
 
 // Read JSON file into a JSON object
-pub fn read_json(file_path: &Path) -> Result<serde_json::Value, Box<dyn Error>> {
+pub fn read_json(file_path: &Path) -> Result<Value, InventoryError> {
     let file = File::open(file_path)?;
     let reader = BufReader::new(file);
-    let json: serde_json::Value = serde_json::from_reader(reader)?;
+    let json: Value = serde_json::from_reader(reader)?;
     Ok(json)
 }
 
+/// How malformed assets of an inventory are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadMode {
+    // The first malformed asset fails the whole load
+    #[default]
+    Strict,
+    // Malformed assets are skipped and reported along with the loaded ones
+    Lenient,
+}
+
+/// Asset inventory loaded into the internal data structure
+#[derive(Debug, Default)]
+pub struct LoadedInventory {
+    pub assets: Vec<Asset>,
+    // Assets left out in lenient mode, with the reason
+    pub skipped: Vec<InventoryError>,
+    // Source file or crate directory the assets were discovered in
+    pub source: Option<String>,
+    // Patterns asset-discovery flagged, e.g. unbounded iterations or migrations without try-runtime checks
+    pub findings: Vec<InventoryFinding>,
+}
+
+/// Finding of an asset inventory
+#[derive(Debug, Clone)]
+pub struct InventoryFinding {
+    // Rule that raised it, e.g. "unbounded-iteration"
+    pub rule: String,
+    // Name of the asset it refers to
+    pub asset: String,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+}
+
+// Read and parse an asset inventory written by asset-discovery
+pub fn load_asset_inventory(file_path: &Path, mode: LoadMode) -> Result<LoadedInventory, InventoryError> {
+    let json = read_json(file_path)?;
+    parse_asset_inventory_into_asset_model(json, mode)
+}
+
 // Parse the JSON file into the internal data structure
-pub fn parse_asset_inventory_into_asset_model(assets: serde_json::Value) -> Vec<Asset> {
-    let mut result = Vec::new();
-    
-    // Extract the assets array
-    let assets_array = assets["assets"].as_array().unwrap();
-    
-    for asset in assets_array {
-        // Parse visibility
-        let visibility = match asset["visibility"].as_str().unwrap_or("none") {
-            "public" => Visibility::Public,
-            "private" => Visibility::Private,
-            _ => Visibility::None,
-        };
-
-        let name = asset["name"].as_str().unwrap_or("").to_string();
-        
-        // Parse category
-        let category = match asset["category"].as_object().and_then(|c| c.keys().next()).map(String::as_str) {
-            Some("PublicFunction") => {
-                let params = asset["category"]["PublicFunction"][1]
-                    .as_array()
-                    .unwrap_or(&Vec::new())
-                    .iter()
-                    .map(|p| Parameter {
-                        name: p[0].as_str().unwrap_or("").to_string(),
-                        param_type: p[1].as_str().unwrap_or("").to_string(),
-                    })
-                    .collect();
-                
-                AssetCategory::PublicFunction {
-                    parameters: params,
-                    return_type: None,
-                }
-            },
-            Some("Helper") => {
-                let params = asset["category"]["Helper"][1]
-                    .as_array()
-                    .unwrap_or(&Vec::new())
-                    .iter()
-                    .map(|p| Parameter {
-                        name: p[0].as_str().unwrap_or("").to_string(),
-                        param_type: p[1].as_str().unwrap_or("").to_string(),
-                    })
-                    .collect();
-                
-                AssetCategory::Helper {
-                    parameters: params,
-                    return_type: None,
-                }
-            },
-            Some("Storage") => {
-                AssetCategory::Storage(StorageConfig {
-                    visibility: visibility.clone(),
-                    name: name.clone(),
-                })
-            },
-            Some("Constant") => AssetCategory::Constant {
-                value_type: "".to_string(), // Type information not provided in JSON
-                name: name.clone(),
-            },
-            Some("Events") => AssetCategory::Event {
-                name: name.clone(),
-                fields: Vec::new(), // Event fields not provided in JSON
-            },
-            Some("Error") => AssetCategory::Error {
-                name: name.clone(),
-                fields: Vec::new(), // Error fields not provided in JSON
-            },
-            _ => continue, // Skip invalid categories
-        };
-
-        // Create asset with default properties
-        let asset = Asset {
-            name,
-            visibility,
-            category,
-            properties: Properties::default(),
-        };
-
-        result.push(asset);
+pub fn parse_asset_inventory_into_asset_model(assets: Value, mode: LoadMode) -> Result<LoadedInventory, InventoryError> {
+    let mut result = LoadedInventory {
+        source: assets.get("source").and_then(Value::as_str).map(str::to_string),
+        ..LoadedInventory::default()
+    };
+
+    // Extract the assets array, without it there is nothing to skip
+    let assets_array = assets
+        .get("assets")
+        .ok_or_else(|| InventoryError::MissingField("$.assets".to_string()))?
+        .as_array()
+        .ok_or_else(|| InventoryError::InvalidType {
+            path: "$.assets".to_string(),
+            expected: "array",
+        })?;
+
+    for (index, asset) in assets_array.iter().enumerate() {
+        match parse_asset(asset, &format!("$.assets[{}]", index)) {
+            Ok(asset) => result.assets.push(asset),
+            Err(err) if mode == LoadMode::Lenient => result.skipped.push(err),
+            Err(err) => return Err(err),
+        }
+    }
+
+    // Findings are omitted by asset-discovery when there are none
+    let findings = match assets.get("findings") {
+        None => &Vec::new(),
+        Some(findings) => findings.as_array().ok_or_else(|| InventoryError::InvalidType {
+            path: "$.findings".to_string(),
+            expected: "array",
+        })?,
+    };
+    for (index, finding) in findings.iter().enumerate() {
+        match parse_finding(finding, &format!("$.findings[{}]", index)) {
+            Ok(finding) => result.findings.push(finding),
+            Err(err) if mode == LoadMode::Lenient => result.skipped.push(err),
+            Err(err) => return Err(err),
+        }
     }
 
-    result
-}
\ No newline at end of file
+    Ok(result)
+}
+
+/// Pallet source read for the checks
+#[derive(Default)]
+pub struct LoadedSources {
+    pub sources: Vec<Source>,
+    // Files of a crate directory that did not parse, with the reason
+    pub skipped: Vec<InventoryError>,
+}
+
+// Parse a pallet source file, or every runtime `.rs` file below a crate directory. `target`, `tests`, `tests.rs`
+// and `mock.rs` are test code and left out, files that do not parse are skipped so one cannot hide the others.
+pub fn read_sources(path: &Path) -> Result<LoadedSources, InventoryError> {
+    let mut loaded = LoadedSources::default();
+    if path.is_dir() {
+        read_crate_sources(path, &mut loaded)?;
+    } else {
+        loaded.sources.push(read_source(path)?);
+    }
+    Ok(loaded)
+}
+
+fn read_crate_sources(dir: &Path, loaded: &mut LoadedSources) -> Result<(), InventoryError> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in entries {
+        let entry = entry.path();
+        let name = entry.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        if entry.is_dir() {
+            if !["target", "tests"].contains(&name) {
+                read_crate_sources(&entry, loaded)?;
+            }
+        } else if entry.extension().is_some_and(|ext| ext == "rs") && !["tests.rs", "mock.rs"].contains(&name) {
+            match read_source(&entry) {
+                Ok(source) => loaded.sources.push(source),
+                Err(err) => loaded.skipped.push(err),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_source(path: &Path) -> Result<Source, InventoryError> {
+    let content = std::fs::read_to_string(path)?;
+    let syntax_tree = syn::parse_file(&content).map_err(|err| InventoryError::UnparsedSource {
+        path: path.display().to_string(),
+        err,
+    })?;
+    Ok(Source {
+        path: path.display().to_string(),
+        syntax_tree,
+    })
+}
+
+fn parse_asset(asset: &Value, path: &str) -> Result<Asset, InventoryError> {
+    // Parse visibility
+    let visibility = match str_field(asset, path, "visibility")? {
+        "public" => Visibility::Public,
+        "private" => Visibility::Private,
+        "none" => Visibility::None,
+        other => {
+            return Err(InventoryError::InvalidValue {
+                path: format!("{}.visibility", path),
+                value: other.to_string(),
+            })
+        }
+    };
+
+    let name = str_field(asset, path, "name")?.to_string();
+
+    // Parse category, serialized as `{"Variant": [fields..]}` or `{"Variant": field}`
+    let category_path = format!("{}.category", path);
+    let (kind, fields) = asset
+        .get("category")
+        .ok_or_else(|| InventoryError::MissingField(category_path.clone()))?
+        .as_object()
+        .and_then(|category| category.iter().next())
+        .ok_or_else(|| InventoryError::InvalidType {
+            path: category_path.clone(),
+            expected: "object with a single category",
+        })?;
+    let fields_path = format!("{}.{}", category_path, kind);
+
+    let category = match kind.as_str() {
+        "PublicFunction" => AssetCategory::PublicFunction {
+            parameters: parse_parameters(fields, &fields_path)?,
+            return_type: None,
+        },
+        "Helper" => AssetCategory::Helper {
+            parameters: parse_parameters(fields, &fields_path)?,
+            return_type: None,
+        },
+        "Storage" => AssetCategory::Storage(StorageConfig {
+            visibility: visibility.clone(),
+            name: name.clone(),
+        }),
+        "Constant" => AssetCategory::Constant {
+            value_type: "".to_string(), // Type information not provided in JSON
+            name: name.clone(),
+        },
+        "Events" => AssetCategory::Event {
+            name: name.clone(),
+            fields: Vec::new(), // Event fields not provided in JSON
+        },
+        "Error" => AssetCategory::Error {
+            name: name.clone(),
+            fields: Vec::new(), // Error fields not provided in JSON
+        },
+        // `{"Dependency": ["pallet_balances", "tight"]}`
+        "Dependency" => AssetCategory::Dependency {
+            name: name.clone(),
+            kind: str_item(fields, &fields_path, 1)?.to_string(),
+        },
+        // `{"ExposedTrait": ["fungibles::Mutate<T::AccountId>", [["mint_into", "fn mint_into(..)"]]]}`
+        "ExposedTrait" => AssetCategory::ExposedTrait {
+            name: name.clone(),
+            methods: parse_methods(fields, &fields_path)?,
+        },
+        "Xcm" => AssetCategory::Xcm {
+            name: name.clone(),
+            kind: str_item(fields, &fields_path, 1)?.to_string(),
+        },
+        // `{"Benchmark": ["transfer", "transfer,transfer_all"]}`, empty when no dispatchable was identified
+        "Benchmark" => AssetCategory::Benchmark {
+            name: name.clone(),
+            dispatchables: str_item(fields, &fields_path, 1)?
+                .split(',')
+                .filter(|dispatchable| !dispatchable.is_empty())
+                .map(str::to_string)
+                .collect(),
+        },
+        // `{"StorageVersion": "1"}`
+        "StorageVersion" => AssetCategory::StorageVersion {
+            version: fields
+                .as_str()
+                .ok_or_else(|| InventoryError::InvalidType {
+                    path: fields_path.clone(),
+                    expected: "string",
+                })?
+                .to_string(),
+        },
+        "Migration" => AssetCategory::Migration {
+            name: name.clone(),
+            kind: str_item(fields, &fields_path, 1)?.to_string(),
+        },
+        // `{"RuntimePallet": ["Balances", "pallet_balances", ["Pallet", "Call"]]}`
+        "RuntimePallet" => AssetCategory::RuntimePallet {
+            name: name.clone(),
+            path: str_item(fields, &fields_path, 1)?.to_string(),
+            parts: parse_strings(fields, &fields_path, 2)?,
+        },
+        // `{"ConfigItem": ["pallet_balances", "MaxLocks", "ConstU32<50>"]}`
+        "ConfigItem" => AssetCategory::ConfigItem {
+            pallet: str_item(fields, &fields_path, 0)?.to_string(),
+            name: str_item(fields, &fields_path, 1)?.to_string(),
+            bound: str_item(fields, &fields_path, 2)?.to_string(),
+        },
+        other => {
+            return Err(InventoryError::UnknownCategory {
+                path: category_path,
+                category: other.to_string(),
+            })
+        }
+    };
+
+    // Attributes are omitted by asset-discovery when there are none
+    let attributes_path = format!("{}.attributes", path);
+    let attributes = match asset.get("attributes") {
+        None => BTreeMap::new(),
+        Some(attributes) => attributes
+            .as_object()
+            .ok_or_else(|| InventoryError::InvalidType {
+                path: attributes_path.clone(),
+                expected: "object",
+            })?
+            .keys()
+            .map(|key| Ok((key.clone(), str_field(attributes, &attributes_path, key)?.to_string())))
+            .collect::<Result<_, InventoryError>>()?,
+    };
+
+    // Create asset with default properties, threats are attached by the mapping engine
+    Ok(Asset {
+        name,
+        visibility,
+        category,
+        attributes,
+        properties: Properties::default(),
+    })
+}
+
+// `{"rule": "unbounded-iteration", "asset": "add", "message": "..", "file": "src/lib.rs", "line": 42}`
+fn parse_finding(finding: &Value, path: &str) -> Result<InventoryFinding, InventoryError> {
+    let line_path = format!("{}.line", path);
+    let line = match finding.get("line") {
+        None => None,
+        Some(line) => Some(line.as_u64().ok_or(InventoryError::InvalidType {
+            path: line_path,
+            expected: "line number",
+        })? as usize),
+    };
+    Ok(InventoryFinding {
+        rule: str_field(finding, path, "rule")?.to_string(),
+        asset: str_field(finding, path, "asset")?.to_string(),
+        message: str_field(finding, path, "message")?.to_string(),
+        file: match finding.get("file") {
+            None => None,
+            Some(_) => Some(str_field(finding, path, "file")?.to_string()),
+        },
+        line,
+    })
+}
+
+// `["transfer", [{"name": "dest", "param_type": "T::AccountId"}]]`
+fn parse_parameters(fields: &Value, path: &str) -> Result<Vec<Parameter>, InventoryError> {
+    let params_path = format!("{}[1]", path);
+    let params = fields
+        .get(1)
+        .ok_or_else(|| InventoryError::MissingField(params_path.clone()))?
+        .as_array()
+        .ok_or_else(|| InventoryError::InvalidType {
+            path: params_path.clone(),
+            expected: "array",
+        })?;
+
+    params
+        .iter()
+        .enumerate()
+        .map(|(index, param)| {
+            let param_path = format!("{}[{}]", params_path, index);
+            Ok(Parameter {
+                name: str_field(param, &param_path, "name")?.to_string(),
+                param_type: str_field(param, &param_path, "param_type")?.to_string(),
+            })
+        })
+        .collect()
+}
+
+// `[["mint_into", "fn mint_into(..)"]]` at index 1
+fn parse_methods(fields: &Value, path: &str) -> Result<Vec<(String, String)>, InventoryError> {
+    let methods_path = format!("{}[1]", path);
+    fields
+        .get(1)
+        .ok_or_else(|| InventoryError::MissingField(methods_path.clone()))?
+        .as_array()
+        .ok_or_else(|| InventoryError::InvalidType {
+            path: methods_path.clone(),
+            expected: "array",
+        })?
+        .iter()
+        .enumerate()
+        .map(|(index, method)| {
+            let method_path = format!("{}[{}]", methods_path, index);
+            Ok((
+                str_item(method, &method_path, 0)?.to_string(),
+                str_item(method, &method_path, 1)?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+// Array of strings at an index of the category fields
+fn parse_strings(fields: &Value, path: &str, index: usize) -> Result<Vec<String>, InventoryError> {
+    let strings_path = format!("{}[{}]", path, index);
+    let strings = fields
+        .get(index)
+        .ok_or_else(|| InventoryError::MissingField(strings_path.clone()))?;
+    let count = strings
+        .as_array()
+        .ok_or_else(|| InventoryError::InvalidType {
+            path: strings_path.clone(),
+            expected: "array",
+        })?
+        .len();
+    (0..count)
+        .map(|i| Ok(str_item(strings, &strings_path, i)?.to_string()))
+        .collect()
+}
+
+// String at an index of the category fields
+fn str_item<'a>(fields: &'a Value, path: &str, index: usize) -> Result<&'a str, InventoryError> {
+    let item_path = format!("{}[{}]", path, index);
+    fields
+        .get(index)
+        .ok_or_else(|| InventoryError::MissingField(item_path.clone()))?
+        .as_str()
+        .ok_or(InventoryError::InvalidType {
+            path: item_path,
+            expected: "string",
+        })
+}
+
+fn str_field<'a>(value: &'a Value, path: &str, field: &str) -> Result<&'a str, InventoryError> {
+    let field_path = format!("{}.{}", path, field);
+    value
+        .get(field)
+        .ok_or_else(|| InventoryError::MissingField(field_path.clone()))?
+        .as_str()
+        .ok_or(InventoryError::InvalidType {
+            path: field_path,
+            expected: "string",
+        })
+}
+
+// -----------------------------------------------Error Handling------------------------------------------------
+
+/// Error loading an asset inventory or its source, malformed entries carry their JSON path, e.g. `$.assets[3].category`
+#[derive(Debug)]
+pub enum InventoryError {
+    IoError(std::io::Error),
+    JsonError(serde_json::Error),
+    ParseError(syn::Error),
+    MissingField(String),
+    InvalidType { path: String, expected: &'static str },
+    InvalidValue { path: String, value: String },
+    UnknownCategory { path: String, category: String },
+    UnparsedSource { path: String, err: syn::Error },
+}
+
+impl std::fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InventoryError::IoError(err) => write!(f, "IO Error: {}", err),
+            InventoryError::JsonError(err) => write!(f, "JSON Error: {}", err),
+            InventoryError::ParseError(err) => write!(f, "Parse Error: {}", err),
+            InventoryError::MissingField(path) => write!(f, "{}: missing field", path),
+            InventoryError::InvalidType { path, expected } => write!(f, "{}: expected {}", path, expected),
+            InventoryError::InvalidValue { path, value } => write!(f, "{}: invalid value `{}`", path, value),
+            InventoryError::UnknownCategory { path, category } => {
+                write!(f, "{}: unknown category `{}`", path, category)
+            }
+            InventoryError::UnparsedSource { path, err } => write!(f, "{}: Parse Error: {}", path, err),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InventoryError::IoError(err) => Some(err),
+            InventoryError::JsonError(err) => Some(err),
+            InventoryError::ParseError(err) => Some(err),
+            InventoryError::UnparsedSource { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for InventoryError {
+    fn from(err: std::io::Error) -> Self {
+        InventoryError::IoError(err)
+    }
+}
+
+impl From<serde_json::Error> for InventoryError {
+    fn from(err: serde_json::Error) -> Self {
+        InventoryError::JsonError(err)
+    }
+}
+
+impl From<syn::Error> for InventoryError {
+    fn from(err: syn::Error) -> Self {
+        InventoryError::ParseError(err)
+    }
+}
@@ -0,0 +1,338 @@
+use crate::utils::assets::*;
+use crate::utils::catalogue::Catalogue;
+use crate::utils::checks::{run_checks, Check, Finding, Source};
+use crate::utils::helpers::InventoryFinding;
+use crate::utils::risk::RiskScorer;
+use crate::utils::threats::*;
+use serde::{Deserialize, Serialize};
+
+// -----------------------------------------------Asset <-> Threat Mapping-----------------------------------------
+// Threats are attached to assets by rules: a predicate over the category, the parameters and the attributes
+// asset-discovery recorded, and the threat with the way to check it. An asset matching several rules for the same
+// threat gets it once, from the first rule.
+
+/// Condition an asset has to meet for a rule to apply
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    // Asset name
+    Name(String),
+    // Category name as written by asset-discovery, e.g. "PublicFunction"
+    Category(String),
+    // A parameter type mentions the type, e.g. "Vec" matches `Vec<u32>` but not `BoundedVec<u32, _>`
+    ParameterType(String),
+    // The attribute is present, with the given value if any
+    Attribute { key: String, value: Option<String> },
+    // The attribute mentions the identifier, e.g. a hasher in the storage "type"
+    AttributeMentions { key: String, ident: String },
+    All(Vec<Predicate>),
+    Any(Vec<Predicate>),
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    pub fn matches(&self, asset: &Asset) -> bool {
+        match self {
+            Predicate::Name(name) => asset.name == *name,
+            Predicate::Category(name) => asset.category.name() == name,
+            Predicate::ParameterType(ty) => parameters(asset)
+                .iter()
+                .any(|param| mentions(&param.param_type, ty)),
+            Predicate::Attribute { key, value } => match (asset.attributes.get(key), value) {
+                (Some(actual), Some(expected)) => actual == expected,
+                (Some(_), None) => true,
+                (None, _) => false,
+            },
+            Predicate::AttributeMentions { key, ident } => {
+                asset.attributes.get(key).is_some_and(|value| mentions(value, ident))
+            }
+            Predicate::All(predicates) => predicates.iter().all(|predicate| predicate.matches(asset)),
+            Predicate::Any(predicates) => predicates.iter().any(|predicate| predicate.matches(asset)),
+            Predicate::Not(predicate) => !predicate.matches(asset),
+        }
+    }
+}
+
+/// Rule attaching a threat to every asset matching its predicate
+#[derive(Debug, Clone)]
+pub struct MappingRule {
+    // Rule name, recorded on the threats it attaches, e.g. "vec-parameter"
+    pub name: String,
+    pub predicate: Predicate,
+    pub threat: ThreatType,
+    pub how_to_check: SecurityCheck,
+    // Mitigation template, `{asset}` is replaced by the asset name
+    pub mitigation: Option<String>,
+    // Advisories, audit reports or documentation the rule is based on
+    pub references: Vec<String>,
+}
+
+/// Built-in rules for what the vulnerability catalogue has no entry for
+pub fn default_rules() -> Vec<MappingRule> {
+    let rule = |name: &str, predicate, threat, how_to_check, mitigation: &str| MappingRule {
+        name: name.to_string(),
+        predicate,
+        threat,
+        how_to_check,
+        mitigation: Some(mitigation.to_string()),
+        references: Vec::new(),
+    };
+    let category = |name: &str| Predicate::Category(name.to_string());
+    let attribute = |key: &str, value: Option<&str>| Predicate::Attribute {
+        key: key.to_string(),
+        value: value.map(str::to_string),
+    };
+
+    vec![
+        // Dispatchables taking a `Vec` let the caller choose its length and content
+        rule(
+            "vec-parameter",
+            Predicate::All(vec![category("PublicFunction"), Predicate::ParameterType("Vec".to_string())]),
+            ThreatType::UserControlledInput,
+            SecurityCheck::InputSanitization,
+            "Take a `BoundedVec` in `{asset}` and validate its content before use",
+        ),
+        rule(
+            "unweighted-iteration",
+            Predicate::All(vec![
+                attribute("iterations", None),
+                Predicate::Not(Box::new(attribute("weight_accounts", Some("yes")))),
+            ]),
+            ThreatType::UnboundedIteration,
+            SecurityCheck::WeightCoverage,
+            "Bound the iterations of `{asset}` and charge a weight growing with their count",
+        ),
+    ]
+}
+
+/// Mapping engine attaching threats to assets
+#[derive(Debug, Clone)]
+pub struct ThreatMapper {
+    rules: Vec<MappingRule>,
+}
+
+impl Default for ThreatMapper {
+    fn default() -> Self {
+        Self::with_rule_packs(Vec::new(), &Catalogue::builtin())
+    }
+}
+
+impl ThreatMapper {
+    pub fn new(rules: Vec<MappingRule>) -> Self {
+        Self { rules }
+    }
+
+    // Rules of the packs first, so a pack can override the mitigation of a known threat, then the entries of the
+    // catalogue and the built-in rules
+    pub fn with_rule_packs(packs: Vec<MappingRule>, catalogue: &Catalogue) -> Self {
+        Self::new(packs.into_iter().chain(catalogue.rules()).chain(default_rules()).collect())
+    }
+
+    // Attach the threats of every matching rule to the asset
+    pub fn map_asset(&self, asset: &mut Asset) {
+        for rule in &self.rules {
+            let known = asset.properties.threats.iter().any(|threat| threat.name == rule.threat);
+            if known || !rule.predicate.matches(asset) {
+                continue;
+            }
+            asset.properties.threats.push(Threat {
+                name: rule.threat.clone(),
+                how_to_check: rule.how_to_check.clone(),
+                rule: rule.name.clone(),
+                mitigation: rule.mitigation.as_ref().map(|template| {
+                    let description = template.replace("{asset}", &asset.name);
+                    MitigationStrategy {
+                        strategy_type: format!("{:?}", rule.how_to_check),
+                        description: description.clone(),
+                        implementation_status: false,
+                        generated: Some(description),
+                    }
+                }),
+                references: rule.references.clone(),
+            });
+        }
+    }
+
+    pub fn map(&self, assets: &mut [Asset]) {
+        for asset in assets {
+            self.map_asset(asset);
+        }
+    }
+}
+
+/// Pallet model with the threats mapped to its assets
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThreatModel {
+    pub assets: Vec<Asset>,
+    // Assets of the inventory left out while loading it
+    pub skipped: usize,
+    // Threats confirmed by the checks, with evidence
+    pub findings: Vec<Finding>,
+}
+
+impl ThreatModel {
+    pub fn build(mut assets: Vec<Asset>, skipped: usize, mapper: &ThreatMapper) -> Self {
+        mapper.map(&mut assets);
+        Self {
+            assets,
+            skipped,
+            findings: Vec::new(),
+        }
+    }
+
+    // Attach the threats behind the findings of the inventory to the assets they were raised on
+    pub fn attach_findings(&mut self, findings: &[InventoryFinding]) {
+        for finding in findings {
+            let Some((threat, how_to_check)) = ThreatType::from_finding_rule(&finding.rule) else {
+                continue;
+            };
+            let subjects = finding_subjects(&threat);
+            let rank = |asset: &Asset| {
+                subjects
+                    .iter()
+                    .position(|category| asset.category.name() == *category)
+                    .unwrap_or(subjects.len())
+            };
+            let Some(asset) = self
+                .assets
+                .iter_mut()
+                .filter(|asset| asset.name == finding.asset)
+                .min_by_key(|asset| rank(asset))
+            else {
+                continue;
+            };
+            if asset.properties.threats.iter().any(|known| known.name == threat) {
+                continue;
+            }
+            asset.properties.threats.push(Threat {
+                name: threat,
+                how_to_check,
+                rule: finding.rule.clone(),
+                mitigation: None,
+                references: Vec::new(),
+            });
+        }
+    }
+
+    // Run the checks against the assets and the source they were discovered in
+    pub fn check(&mut self, checks: &[Box<dyn Check>], sources: &[Source]) {
+        self.findings = run_checks(checks, &self.assets, sources);
+    }
+
+    // Score every asset with the threats mapped to it and the findings raised on it
+    pub fn score(&mut self, scorer: &RiskScorer) {
+        for asset in &mut self.assets {
+            let findings: Vec<&Finding> = self.findings.iter().filter(|finding| raised_on(finding, asset)).collect();
+            let score = scorer.score(asset, &findings);
+            asset.properties.risk_level = score.level.clone();
+            asset.properties.risk_score = Some(score);
+        }
+    }
+
+    // Assets with at least one threat mapped to them or raised by a check
+    pub fn threatened(&self) -> impl Iterator<Item = &Asset> {
+        self.assets.iter().filter(|asset| {
+            !asset.properties.threats.is_empty() || self.findings.iter().any(|finding| raised_on(finding, asset))
+        })
+    }
+
+    // Threatened assets, highest score first, ties in inventory order
+    pub fn ranked(&self) -> Vec<&Asset> {
+        let mut ranked: Vec<&Asset> = self.threatened().collect();
+        let score = |asset: &Asset| asset.properties.risk_score.as_ref().map_or(0.0, |risk| risk.score);
+        ranked.sort_by(|a, b| score(b).total_cmp(&score(a)));
+        ranked
+    }
+}
+
+impl std::fmt::Display for ThreatModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let threatened = self.threatened().count();
+        writeln!(
+            f,
+            "Threat model: {} assets, {} with threats, {} skipped",
+            self.assets.len(),
+            threatened,
+            self.skipped
+        )?;
+        for asset in self.ranked() {
+            match &asset.properties.risk_score {
+                Some(risk) => writeln!(
+                    f,
+                    "{} ({}): {:?} {:.1} [{}]",
+                    asset.name,
+                    asset.category.name(),
+                    risk.level,
+                    risk.score,
+                    risk.vector
+                )?,
+                None => writeln!(f, "{} ({})", asset.name, asset.category.name())?,
+            }
+            for threat in &asset.properties.threats {
+                writeln!(
+                    f,
+                    "  - {:?} ({:?}, CWE-{}): check {:?} [{}]",
+                    threat.name,
+                    threat.name.default_severity(),
+                    threat.name.cwe(),
+                    threat.how_to_check,
+                    threat.rule
+                )?;
+                if let Some(mitigation) = &threat.mitigation {
+                    writeln!(f, "    mitigation: {}", mitigation.description)?;
+                }
+                if !threat.references.is_empty() {
+                    writeln!(f, "    references: {}", threat.references.join(", "))?;
+                }
+            }
+        }
+        if !self.findings.is_empty() {
+            writeln!(f, "Findings: {}", self.findings.len())?;
+        }
+        for finding in &self.findings {
+            let location = finding
+                .evidence
+                .as_ref()
+                .map(|evidence| format!("{}:{} `{}`", evidence.file, evidence.line, evidence.code))
+                .unwrap_or_else(|| "no location".to_string());
+            writeln!(
+                f,
+                "  - {:?} in {} ({:?} confidence): {}\n    at {}\n    fix: {}",
+                finding.threat, finding.asset, finding.confidence, finding.message, location, finding.fix
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// -----------------------------------------------Helper Functions----------------------------------------------
+
+fn parameters(asset: &Asset) -> &[Parameter] {
+    match &asset.category {
+        AssetCategory::PublicFunction { parameters, .. } | AssetCategory::Helper { parameters, .. } => parameters,
+        _ => &[],
+    }
+}
+
+// Categories of the assets findings of a threat are raised on, most specific first. Benchmarks and XCM items are
+// often named after the dispatchable they belong to.
+fn finding_subjects(threat: &ThreatType) -> &'static [&'static str] {
+    match threat {
+        ThreatType::UnboundedIteration => &["PublicFunction", "Helper"],
+        ThreatType::UnboundedStorage => &["Storage"],
+        ThreatType::UnderweightExtrinsic => &["PublicFunction", "Benchmark"],
+        ThreatType::BrokenMigration => &["Migration", "Storage"],
+        ThreatType::XcmTrust => &["Xcm"],
+        _ => &[],
+    }
+}
+
+// Checks look at function bodies, a benchmark or XCM asset named after a dispatchable is not what they found
+fn raised_on(finding: &Finding, asset: &Asset) -> bool {
+    finding.asset == asset.name
+        && matches!(asset.category, AssetCategory::PublicFunction { .. } | AssetCategory::Helper { .. })
+}
+
+// Whole identifier match, so `Vec` does not match `BoundedVec`
+pub(crate) fn mentions(text: &str, ident: &str) -> bool {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_').any(|word| word == ident)
+}
@@ -1,4 +1,10 @@
 pub mod llm;
 pub mod assets;
 pub mod threats;
-pub mod helpers;
\ No newline at end of file
+pub mod helpers;
+pub mod mapping;
+pub mod rules;
+pub mod catalogue;
+pub mod checks;
+pub mod risk;
+pub mod document;
\ No newline at end of file
@@ -0,0 +1,291 @@
+use crate::utils::assets::*;
+use crate::utils::checks::{Confidence, Finding};
+use crate::utils::threats::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// -----------------------------------------------Risk Scoring----------------------------------------------------
+// The risk of an asset combines who can reach it, what it can damage, the worst threat mapped to it and how sure
+// the checks are that the threat is real. Each factor is a value between 0 and 1, the weighted sum is scaled to
+// 0-10 like a CVSS base score, and the same inputs always give the same score. Threats only mapped by rules and not
+// confirmed by a finding count with a reduced confidence.
+
+// Identifiers of storage items and calls that move or hold value
+const VALUE_IDENTS: [&str; 5] = ["Balance", "Currency", "Fungible", "Asset", "Account"];
+// Identifiers of storage items deciding who may do what
+const PRIVILEGE_IDENTS: [&str; 6] = ["Owner", "Admin", "Authorit", "Member", "Key", "Sudo"];
+
+/// Weights of the factors and thresholds of the risk levels, fields left out of a weights file keep their default
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskWeights {
+    pub exposure: f64,
+    pub impact: f64,
+    pub threat: f64,
+    // Confidence of threats no check confirmed
+    pub unconfirmed: f64,
+    // Lowest scores of the Critical, High and Medium levels
+    pub critical: f64,
+    pub high: f64,
+    pub medium: f64,
+}
+
+impl Default for RiskWeights {
+    fn default() -> Self {
+        Self {
+            exposure: 0.3,
+            impact: 0.3,
+            threat: 0.4,
+            unconfirmed: 0.6,
+            critical: 9.0,
+            high: 7.0,
+            medium: 4.0,
+        }
+    }
+}
+
+impl RiskWeights {
+    /// Read weights from a `.toml`, `.yaml`, `.yml` or `.json` file
+    pub fn load(path: &Path) -> Result<Self, WeightsError> {
+        let content = std::fs::read_to_string(path)?;
+        let file = path.display().to_string();
+        let weights: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content).map_err(|err| WeightsError::TomlError(file.clone(), err))?,
+            Some("yaml") | Some("yml") => {
+                serde_yaml::from_str(&content).map_err(|err| WeightsError::YamlError(file.clone(), err))?
+            }
+            Some("json") => serde_json::from_str(&content).map_err(|err| WeightsError::JsonError(file.clone(), err))?,
+            _ => return Err(WeightsError::UnsupportedFormat(file)),
+        };
+
+        // Negative weights would turn a factor into a mitigation, thresholds out of order would skip levels
+        let factors = [weights.exposure, weights.impact, weights.threat, weights.unconfirmed];
+        if factors.iter().any(|weight| *weight < 0.0) {
+            return Err(WeightsError::InvalidWeights(file, "weights must not be negative".to_string()));
+        }
+        if !(weights.critical >= weights.high && weights.high >= weights.medium) {
+            return Err(WeightsError::InvalidWeights(
+                file,
+                "thresholds must satisfy critical >= high >= medium".to_string(),
+            ));
+        }
+        Ok(weights)
+    }
+}
+
+/// Score of an asset with the factors it was computed from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskScore {
+    pub exposure: f64,
+    pub impact: f64,
+    pub threat: f64,
+    pub confidence: f64,
+    // 0.0 to 10.0, rounded to one decimal
+    pub score: f64,
+    pub level: RiskLevel,
+    // CVSS-like summary of the factors, e.g. "E:U/I:H/T:C/C:H"
+    pub vector: String,
+}
+
+/// Scoring engine turning assets and findings into risk scores
+#[derive(Debug, Clone, Default)]
+pub struct RiskScorer {
+    pub weights: RiskWeights,
+}
+
+impl RiskScorer {
+    pub fn new(weights: RiskWeights) -> Self {
+        Self { weights }
+    }
+
+    // Score an asset with the findings the checks raised on it
+    pub fn score(&self, asset: &Asset, findings: &[&Finding]) -> RiskScore {
+        let (exposure, exposure_code) = exposure(asset);
+        let (impact, impact_code) = impact(asset);
+
+        // Worst threat, mapped by a rule or confirmed by a finding
+        let severities = asset
+            .properties
+            .threats
+            .iter()
+            .map(|threat| threat.name.default_severity())
+            .chain(findings.iter().map(|finding| finding.threat.default_severity()));
+        let severity = severities.min_by_key(|severity| severity_rank(*severity));
+        let threat = severity.map_or(0.0, severity_value);
+
+        let confidence = findings
+            .iter()
+            .map(|finding| confidence_value(finding.confidence))
+            .fold(None, |best: Option<f64>, value| Some(best.map_or(value, |best| best.max(value))))
+            .unwrap_or(self.weights.unconfirmed);
+
+        let weights = &self.weights;
+        let total = weights.exposure + weights.impact + weights.threat;
+        let weighted = weights.exposure * exposure + weights.impact * impact + weights.threat * threat;
+        // Without a threat there is nothing to exploit
+        let score = if severity.is_none() || total <= 0.0 {
+            0.0
+        } else {
+            (10.0 * weighted / total * confidence * 10.0).round() / 10.0
+        };
+
+        let level = if score >= weights.critical {
+            RiskLevel::Critical
+        } else if score >= weights.high {
+            RiskLevel::High
+        } else if score >= weights.medium {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        };
+        let threat_code = match severity {
+            Some(Severity::Critical) => "C",
+            Some(Severity::High) => "H",
+            Some(Severity::Medium) => "M",
+            Some(Severity::Low) => "L",
+            None => "N",
+        };
+        let best = findings.iter().map(|finding| finding.confidence).min_by_key(|c| confidence_rank(*c));
+        let confidence_code = match best {
+            Some(Confidence::High) => "H",
+            Some(Confidence::Medium) => "M",
+            Some(Confidence::Low) => "L",
+            None => "U",
+        };
+
+        RiskScore {
+            exposure,
+            impact,
+            threat,
+            confidence,
+            score,
+            level,
+            vector: format!("E:{}/I:{}/T:{}/C:{}", exposure_code, impact_code, threat_code, confidence_code),
+        }
+    }
+}
+
+// -----------------------------------------------Error Handling------------------------------------------------
+
+/// Error reading a risk weights file
+#[derive(Debug)]
+pub enum WeightsError {
+    IoError(std::io::Error),
+    TomlError(String, toml::de::Error),
+    YamlError(String, serde_yaml::Error),
+    JsonError(String, serde_json::Error),
+    UnsupportedFormat(String),
+    InvalidWeights(String, String),
+}
+
+impl std::fmt::Display for WeightsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WeightsError::IoError(err) => write!(f, "IO Error: {}", err),
+            WeightsError::TomlError(file, err) => write!(f, "{}: TOML Error: {}", file, err),
+            WeightsError::YamlError(file, err) => write!(f, "{}: YAML Error: {}", file, err),
+            WeightsError::JsonError(file, err) => write!(f, "{}: JSON Error: {}", file, err),
+            WeightsError::UnsupportedFormat(file) => {
+                write!(f, "{}: expected a .toml, .yaml, .yml or .json weights file", file)
+            }
+            WeightsError::InvalidWeights(file, message) => write!(f, "{}: {}", file, message),
+        }
+    }
+}
+
+impl std::error::Error for WeightsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WeightsError::IoError(err) => Some(err),
+            WeightsError::TomlError(_, err) => Some(err),
+            WeightsError::YamlError(_, err) => Some(err),
+            WeightsError::JsonError(_, err) => Some(err),
+            WeightsError::UnsupportedFormat(_) | WeightsError::InvalidWeights(..) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for WeightsError {
+    fn from(err: std::io::Error) -> Self {
+        WeightsError::IoError(err)
+    }
+}
+
+// -----------------------------------------------Helper Functions----------------------------------------------
+
+// Who can reach the asset: unsigned or unchecked calls are open to anyone, root-only calls to governance. Public
+// functions that are not dispatchable are only reached through the pallet, like its helpers.
+fn exposure(asset: &Asset) -> (f64, &'static str) {
+    let dispatchable = asset.attributes.get("dispatchable").is_some_and(|value| value == "yes");
+    match &asset.category {
+        AssetCategory::PublicFunction { .. } if !dispatchable => (0.3, "I"),
+        AssetCategory::PublicFunction { .. } => match asset.attributes.get("origin").map(String::as_str) {
+            None | Some("none") => (1.0, "U"),
+            Some("signed") | Some("signed_or_root") => (0.8, "S"),
+            Some("root") => (0.2, "R"),
+            Some(_) => (0.5, "C"),
+        },
+        AssetCategory::Storage(_) => (0.4, "I"),
+        AssetCategory::Helper { .. } => (0.3, "I"),
+        _ => (0.1, "N"),
+    }
+}
+
+// What the asset can damage: value first, then privileges, then any other state
+fn impact(asset: &Asset) -> (f64, &'static str) {
+    let mut touched = vec![asset.name.as_str()];
+    for key in ["writes", "external_calls"] {
+        touched.extend(asset.attributes.get(key).map(String::as_str));
+    }
+    // `Accounts` and `BalanceOf` count as well as `Account` and `Balance`
+    let touches = |idents: &[&str]| {
+        touched.iter().any(|text| {
+            text.split(|c: char| !c.is_alphanumeric() && c != '_')
+                .any(|word| idents.iter().any(|ident| word.starts_with(ident)))
+        })
+    };
+
+    if touches(&VALUE_IDENTS) {
+        (1.0, "H")
+    } else if touches(&PRIVILEGE_IDENTS) {
+        (0.8, "P")
+    } else if asset.attributes.contains_key("writes") || matches!(asset.category, AssetCategory::Storage(_)) {
+        (0.5, "M")
+    } else {
+        (0.2, "L")
+    }
+}
+
+fn severity_value(severity: Severity) -> f64 {
+    match severity {
+        Severity::Critical => 1.0,
+        Severity::High => 0.75,
+        Severity::Medium => 0.5,
+        Severity::Low => 0.25,
+    }
+}
+
+fn severity_rank(severity: Severity) -> u8 {
+    match severity {
+        Severity::Critical => 0,
+        Severity::High => 1,
+        Severity::Medium => 2,
+        Severity::Low => 3,
+    }
+}
+
+fn confidence_value(confidence: Confidence) -> f64 {
+    match confidence {
+        Confidence::High => 1.0,
+        Confidence::Medium => 0.85,
+        Confidence::Low => 0.7,
+    }
+}
+
+fn confidence_rank(confidence: Confidence) -> u8 {
+    match confidence {
+        Confidence::High => 0,
+        Confidence::Medium => 1,
+        Confidence::Low => 2,
+    }
+}
@@ -0,0 +1,219 @@
+use crate::utils::mapping::{MappingRule, Predicate};
+use crate::utils::threats::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// -----------------------------------------------Rule Packs------------------------------------------------------
+// Threat rules written in TOML or YAML, loaded at runtime so adding a rule needs no rebuild. A rule pack is a file,
+// or a directory of files, usually kept next to the pallets it is written for:
+//
+// [[rule]]
+// name = "treasury-spend"
+// threat = "MissingOriginCheck"
+// check = "OriginCheck"
+// mitigation = "Require `T::SpendOrigin` in `{asset}`"
+// when = { category = "PublicFunction", origin = "unchecked", mentions = { writes = "Treasury" } }
+//
+// Every condition given in `when` has to hold. `any` holds when one of its conditions does, `not` when its
+// condition does not.
+
+// Rule pack file extensions
+const RULE_EXTENSIONS: [&str; 3] = ["toml", "yaml", "yml"];
+
+/// Rule pack file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleSpec>,
+}
+
+/// Rule as written in a rule pack
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    name: String,
+    threat: ThreatType,
+    check: SecurityCheck,
+    mitigation: Option<String>,
+    #[serde(default)]
+    references: Vec<String>,
+    when: Conditions,
+}
+
+/// Asset predicates of a rule
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Conditions {
+    // Asset name, e.g. "pallet_insecure_randomness_collective_flip"
+    name: Option<String>,
+    // Category as written by asset-discovery, e.g. "PublicFunction" or "Storage"
+    category: Option<String>,
+    // Identifier in a parameter type, e.g. "Vec" or "T::AccountId"
+    parameter_type: Option<String>,
+    // Origin checked by a dispatchable: "signed", "root", "none", "signed_or_root", "custom" or "unchecked"
+    origin: Option<String>,
+    // Hasher of a storage map, e.g. "Twox64Concat"
+    hasher: Option<String>,
+    // Trait in the bound of a Config type, e.g. "ReservableCurrency"
+    config_bound: Option<String>,
+    // (attribute, exact value)
+    attributes: BTreeMap<String, String>,
+    // (attribute, identifier the value mentions)
+    mentions: BTreeMap<String, String>,
+    // Attributes that have to be present
+    present: Vec<String>,
+    any: Vec<Conditions>,
+    not: Option<Box<Conditions>>,
+}
+
+impl Conditions {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.category.is_none()
+            && self.parameter_type.is_none()
+            && self.origin.is_none()
+            && self.hasher.is_none()
+            && self.config_bound.is_none()
+            && self.attributes.is_empty()
+            && self.mentions.is_empty()
+            && self.present.is_empty()
+            && self.any.is_empty()
+            && self.not.is_none()
+    }
+
+    pub(crate) fn into_predicate(self) -> Predicate {
+        let attribute = |key: &str, value: Option<String>| Predicate::Attribute {
+            key: key.to_string(),
+            value,
+        };
+        let mentions = |key: &str, ident: &str| Predicate::AttributeMentions {
+            key: key.to_string(),
+            ident: ident.to_string(),
+        };
+
+        let mut predicates = Vec::new();
+        predicates.extend(self.name.map(Predicate::Name));
+        predicates.extend(self.category.map(Predicate::Category));
+        predicates.extend(self.parameter_type.map(Predicate::ParameterType));
+        predicates.extend(self.origin.map(|origin| match origin.as_str() {
+            // asset-discovery records no origin when the dispatchable checks none, other `pub fn` take no origin.
+            // ink! messages have a caller rather than an origin, older inventories marked them as dispatchables
+            "unchecked" => Predicate::All(vec![
+                Predicate::Category("PublicFunction".to_string()),
+                attribute("dispatchable", Some("yes".to_string())),
+                Predicate::Not(Box::new(attribute("origin", None))),
+                Predicate::Not(Box::new(attribute("ink", None))),
+            ]),
+            _ => attribute("origin", Some(origin)),
+        }));
+        predicates.extend(self.hasher.map(|hasher| {
+            Predicate::All(vec![Predicate::Category("Storage".to_string()), mentions("type", &hasher)])
+        }));
+        // asset-discovery records the bound of a Config type on its `Dependency` asset
+        predicates.extend(self.config_bound.map(|bound| mentions("bound", &bound)));
+        predicates.extend(self.attributes.into_iter().map(|(key, value)| attribute(&key, Some(value))));
+        predicates.extend(self.mentions.iter().map(|(key, ident)| mentions(key, ident)));
+        predicates.extend(self.present.iter().map(|key| attribute(key, None)));
+        if !self.any.is_empty() {
+            predicates.push(Predicate::Any(self.any.into_iter().map(Conditions::into_predicate).collect()));
+        }
+        predicates.extend(self.not.map(|not| Predicate::Not(Box::new(not.into_predicate()))));
+        Predicate::All(predicates)
+    }
+}
+
+/// Load the rules of a rule pack file, or of every rule pack file in a directory in name order
+pub fn load_rule_pack(path: &Path) -> Result<Vec<MappingRule>, RuleError> {
+    if path.is_dir() {
+        let mut files: Vec<_> = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        files.sort();
+        let mut rules = Vec::new();
+        for file in files {
+            let is_rule_pack = file
+                .extension()
+                .is_some_and(|ext| RULE_EXTENSIONS.iter().any(|known| ext == *known));
+            if file.is_file() && is_rule_pack {
+                rules.extend(load_rule_pack(&file)?);
+            }
+        }
+        return Ok(rules);
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    let file = path.display().to_string();
+    let rule_file: RuleFile = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|err| RuleError::TomlError(file.clone(), err))?,
+        Some("yaml") | Some("yml") => {
+            serde_yaml::from_str(&content).map_err(|err| RuleError::YamlError(file.clone(), err))?
+        }
+        _ => return Err(RuleError::UnsupportedFormat(file)),
+    };
+
+    rule_file
+        .rules
+        .into_iter()
+        .map(|spec| {
+            // A rule without conditions would attach its threat to every asset
+            if spec.when.is_empty() {
+                return Err(RuleError::InvalidRule {
+                    file: file.clone(),
+                    rule: spec.name,
+                    message: "`when` has no condition".to_string(),
+                });
+            }
+            Ok(MappingRule {
+                name: spec.name,
+                predicate: spec.when.into_predicate(),
+                threat: spec.threat,
+                how_to_check: spec.check,
+                mitigation: spec.mitigation,
+                references: spec.references,
+            })
+        })
+        .collect()
+}
+
+// -----------------------------------------------Error Handling------------------------------------------------
+
+/// Error loading a rule pack, with the file it happened in
+#[derive(Debug)]
+pub enum RuleError {
+    IoError(std::io::Error),
+    TomlError(String, toml::de::Error),
+    YamlError(String, serde_yaml::Error),
+    UnsupportedFormat(String),
+    InvalidRule { file: String, rule: String, message: String },
+}
+
+impl std::fmt::Display for RuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleError::IoError(err) => write!(f, "IO Error: {}", err),
+            RuleError::TomlError(file, err) => write!(f, "{}: TOML Error: {}", file, err),
+            RuleError::YamlError(file, err) => write!(f, "{}: YAML Error: {}", file, err),
+            RuleError::UnsupportedFormat(file) => write!(f, "{}: expected a .toml, .yaml or .yml rule pack", file),
+            RuleError::InvalidRule { file, rule, message } => write!(f, "{}: rule `{}`: {}", file, rule, message),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RuleError::IoError(err) => Some(err),
+            RuleError::TomlError(_, err) => Some(err),
+            RuleError::YamlError(_, err) => Some(err),
+            RuleError::UnsupportedFormat(_) | RuleError::InvalidRule { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RuleError {
+    fn from(err: std::io::Error) -> Self {
+        RuleError::IoError(err)
+    }
+}
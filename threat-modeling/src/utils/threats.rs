@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // -----------------------------------------------Threat Model Data Structures-------------------------------------
 
@@ -8,57 +9,254 @@ use std::collections::HashMap;
 /// 2. Store translated threats from natural language using LLM
 /// 
 /// Basically, this represent the question "what needs to be checked and how to check it"
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Threat {
     // The name of the threat, e.g. "User controled input"
     pub name: ThreatType,
     // How to check the threat, e.g. "Input Sanitization".
     // This will only be the category of checking, the actual checking happens via symbolic execution, maybe through LLM tool calling and etc.
     pub how_to_check: SecurityCheck,
+    // Mapping rule that attached the threat to the asset, e.g. "vec-parameter", empty for threats added by hand
+    #[serde(default)]
+    pub rule: String,
+    // How to fix it, from the mitigation template of the rule
+    #[serde(default)]
+    pub mitigation: Option<MitigationStrategy>,
+    // Advisories, audit reports or documentation behind the rule
+    #[serde(default)]
+    pub references: Vec<String>,
     // TODO: Add other fields
 }
 
-#[derive(Debug, Clone)]
+/// Category of checking, the checks themselves implement `checks::Check`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SecurityCheck {
+    // Caller supplied data must be bounded and validated before use
     InputSanitization,
+    // Dispatchables must check their origin before doing anything else
+    OriginCheck,
+    // Arithmetic on runtime values must use `checked_*` or `saturating_*` operations
+    CheckedArithmetic,
+    // Storage keys an attacker controls need a cryptographic hasher
+    HasherSelection,
+    // `ValidateUnsigned` must reject invalid and repeated unsigned transactions
+    UnsignedValidation,
+    // Weight must grow with the work done
+    WeightCoverage,
+    // Storage layout changes must bump the storage version and ship a migration that try-runtime can verify
+    StorageVersioning,
 }
 
-impl SecurityCheck {
-    pub fn validate_input(&self) -> Option<()> {
+/// Substrate-specific threats audited for, each with a description, a CWE and a default severity
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThreatType {
+    // Access control
+    MissingOriginCheck,
+    NestedCallEscalation,
+    // Input and arithmetic
+    UserControlledInput,
+    ArithmeticOverflow,
+    RuntimePanic,
+    DivisionByZero,
+    // Resource consumption
+    UnboundedStorage,
+    UnboundedIteration,
+    UnderweightExtrinsic,
+    KeyCollision,
+    UnsignedSpam,
+    // Economic and ordering
+    InsecureRandomness,
+    FrontRunning,
+    StorageDepositGriefing,
+    AccountReaping,
+    // Upgrades, cross-chain and information flow
+    BrokenMigration,
+    XcmTrust,
+    EventInformationLeak,
+}
+
+/// Severity of a threat before the asset it is mapped to is taken into account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Severity {
+    Critical,
+    High,
+    Medium,
+    Low,
+}
+
+impl ThreatType {
+    pub const ALL: [ThreatType; 18] = [
+        ThreatType::MissingOriginCheck,
+        ThreatType::NestedCallEscalation,
+        ThreatType::UserControlledInput,
+        ThreatType::ArithmeticOverflow,
+        ThreatType::RuntimePanic,
+        ThreatType::DivisionByZero,
+        ThreatType::UnboundedStorage,
+        ThreatType::UnboundedIteration,
+        ThreatType::UnderweightExtrinsic,
+        ThreatType::KeyCollision,
+        ThreatType::UnsignedSpam,
+        ThreatType::InsecureRandomness,
+        ThreatType::FrontRunning,
+        ThreatType::StorageDepositGriefing,
+        ThreatType::AccountReaping,
+        ThreatType::BrokenMigration,
+        ThreatType::XcmTrust,
+        ThreatType::EventInformationLeak,
+    ];
+
+    pub fn description(&self) -> &'static str {
         match self {
-            SecurityCheck::InputSanitization => {
-                println!("Validating input.");
-                Some(())
+            ThreatType::MissingOriginCheck => {
+                "Dispatchable without `ensure_signed`, `ensure_root` or a custom origin check, anyone can call it"
+            }
+            ThreatType::NestedCallEscalation => {
+                "A boxed `RuntimeCall` is dispatched with an origin more privileged than the caller's"
+            }
+            ThreatType::UserControlledInput => "Caller supplied data reaches logic that assumes it was validated",
+            ThreatType::ArithmeticOverflow => {
+                "Unchecked `+`, `-` or `*` on balances or counters wraps in release builds"
+            }
+            ThreatType::RuntimePanic => {
+                "`unwrap`, `expect` or out of bounds indexing panics in the runtime, the block cannot be built"
+            }
+            ThreatType::DivisionByZero => "Division or remainder by a value that can be zero panics in the runtime",
+            ThreatType::UnboundedStorage => {
+                "Storage grows without a bound, bloating the state and the proof of validity"
+            }
+            ThreatType::UnboundedIteration => {
+                "Storage iteration or a loop over a caller supplied `Vec` whose cost the weight does not grow with"
+            }
+            ThreatType::UnderweightExtrinsic => {
+                "The weight charged is below the work done, blocks can be filled for a fixed fee"
+            }
+            ThreatType::KeyCollision => {
+                "Storage map keyed with `Twox64Concat` or `Identity`, attackers choosing keys can unbalance the trie"
+            }
+            ThreatType::UnsignedSpam => {
+                "Unsigned transactions pay no fee, whatever is not rejected by validation can be spammed for free"
+            }
+            ThreatType::InsecureRandomness => {
+                "Randomness from block data or the collective coin flip can be predicted or influenced by block authors"
+            }
+            ThreatType::FrontRunning => {
+                "The outcome depends on transaction ordering, which block authors and observers of the pool control"
+            }
+            ThreatType::StorageDepositGriefing => {
+                "Storage is created on behalf of others without a deposit, or deposits can be locked by a third party"
+            }
+            ThreatType::AccountReaping => {
+                "Transfers drop an account below the existential deposit, reaping it and its references"
+            }
+            ThreatType::BrokenMigration => {
+                "Storage layout changes without a versioned, tested migration, old data decodes wrongly after upgrade"
             }
+            ThreatType::XcmTrust => {
+                "The XCM configuration trusts too many locations, foreign chains can mint assets or dispatch calls"
+            }
+            ThreatType::EventInformationLeak => {
+                "Events publish data that should stay private, every node and indexer sees them"
+            }
+        }
+    }
+
+    /// CWE the threat is an instance of
+    pub fn cwe(&self) -> u32 {
+        match self {
+            ThreatType::MissingOriginCheck => 862,
+            ThreatType::NestedCallEscalation => 269,
+            ThreatType::UserControlledInput => 20,
+            ThreatType::ArithmeticOverflow => 190,
+            ThreatType::RuntimePanic => 248,
+            ThreatType::DivisionByZero => 369,
+            ThreatType::UnboundedStorage => 770,
+            ThreatType::UnboundedIteration => 834,
+            ThreatType::UnderweightExtrinsic => 400,
+            ThreatType::KeyCollision => 328,
+            ThreatType::UnsignedSpam => 799,
+            ThreatType::InsecureRandomness => 330,
+            ThreatType::FrontRunning => 362,
+            ThreatType::StorageDepositGriefing => 405,
+            ThreatType::AccountReaping => 672,
+            ThreatType::BrokenMigration => 665,
+            ThreatType::XcmTrust => 346,
+            ThreatType::EventInformationLeak => 200,
+        }
+    }
+
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            ThreatType::MissingOriginCheck | ThreatType::NestedCallEscalation | ThreatType::XcmTrust => {
+                Severity::Critical
+            }
+            ThreatType::ArithmeticOverflow
+            | ThreatType::RuntimePanic
+            | ThreatType::DivisionByZero
+            | ThreatType::UnboundedStorage
+            | ThreatType::UnboundedIteration
+            | ThreatType::UnderweightExtrinsic
+            | ThreatType::UnsignedSpam
+            | ThreatType::InsecureRandomness
+            | ThreatType::BrokenMigration => Severity::High,
+            ThreatType::UserControlledInput
+            | ThreatType::KeyCollision
+            | ThreatType::FrontRunning
+            | ThreatType::StorageDepositGriefing
+            | ThreatType::AccountReaping => Severity::Medium,
+            ThreatType::EventInformationLeak => Severity::Low,
+        }
+    }
+
+    /// Threat behind a finding of the asset inventory, by the rule that raised it, with the way to check it
+    pub fn from_finding_rule(rule: &str) -> Option<(Self, SecurityCheck)> {
+        match rule {
+            "unbounded-iteration" => Some((ThreatType::UnboundedIteration, SecurityCheck::WeightCoverage)),
+            "without-storage-info" | "unbounded-storage-attribute" | "unbounded-storage-type"
+            | "missing-max-encoded-len" => Some((ThreatType::UnboundedStorage, SecurityCheck::InputSanitization)),
+            "missing-benchmark" | "benchmark-range-mismatch" | "benchmark-misses-worst-case" => {
+                Some((ThreatType::UnderweightExtrinsic, SecurityCheck::WeightCoverage))
+            }
+            "storage-changed-without-migration"
+            | "unversioned-migration"
+            | "migration-without-try-runtime-checks" => {
+                Some((ThreatType::BrokenMigration, SecurityCheck::StorageVersioning))
+            }
+            "permissive-xcm-barrier"
+            | "broad-teleport-trust"
+            | "unrestricted-xcm-execute"
+            | "unrestricted-xcm-transact" => Some((ThreatType::XcmTrust, SecurityCheck::OriginCheck)),
             _ => None,
         }
     }
-}
 
-#[derive(Debug, Clone)]
-pub enum ThreatType {
-    UserControlledInput,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ForSymbolicExecution {
     // Pallet-specific semantic meaning of the asset, e.g. "control staking operation"
-    semantic_meaning: String,
+    pub semantic_meaning: String,
     // Compliance requirements for the asset, e.g. "must be compliant with xxx regulations"
-    compliance_requirements: String,
+    pub compliance_requirements: String,
     // Business requirements for the asset, e.g. "only owner can call this function"
     // This field also includes domain-specific error behavior, e.g. "receipient must be a valid ss58 address"
-    business_requirements: Vec<String>,
+    pub business_requirements: Vec<String>,
     // Valid assumptions regarding what preconditions should lead to what postconditions
     // e.g. "If sender is deducted 1 token, then the receipient must be credited with 1 token"
     // TODO: The type for pre- and post-conditions should not be String
     // If the asset is an external function, this represents the abstract behavior of the function, e.g. "given A the function will return B"
-    valid_assumptions: HashMap<String, String>,
+    pub valid_assumptions: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MitigationStrategy {
-    strategy_type: String,
-    description: String,
-    implementation_status: bool,
+    // Check the mitigation answers, e.g. "OriginCheck"
+    pub strategy_type: String,
+    pub description: String,
+    pub implementation_status: bool,
+    // Description generated from the template of the rule, unset for mitigations written by hand. A description
+    // that still equals it was not edited and is refreshed with the template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated: Option<String>,
 }
\ No newline at end of file
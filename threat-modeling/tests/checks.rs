@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use threat_modeling::utils::assets::{Asset, AssetCategory, Parameter, Properties, Visibility};
+use threat_modeling::utils::checks::{
+    BoundedInputs, Check, CheckedArithmetic, Finding, OriginCheck, Source, WeightCoverage,
+};
+use threat_modeling::utils::threats::ThreatType;
+
+const PALLET: &str = r#"
+#[frame_support::pallet]
+pub mod pallet {
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        #[pallet::weight(T::WeightInfo::store(items.len() as u32))]
+        pub fn store(origin: OriginFor<T>, items: Vec<u32>) -> DispatchResult {
+            ensure_signed(origin)?;
+            Ok(())
+        }
+
+        #[pallet::weight(10_000)]
+        pub fn fixed(origin: OriginFor<T>, amount: u32) -> DispatchResult {
+            ensure_root(origin)?;
+            Total::<T>::put(amount + 1);
+            Ok(())
+        }
+
+        pub fn open(origin: OriginFor<T>, items: Vec<u32>) -> DispatchResult {
+            Count::<T>::put(items.len() as u32 + 1);
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        pub fn helper(items: Vec<u32>) -> u32 {
+            items.len() as u32
+        }
+    }
+}
+"#;
+
+fn function(name: &str, attributes: &[(&str, &str)]) -> Asset {
+    Asset {
+        name: name.to_string(),
+        visibility: Visibility::Public,
+        category: AssetCategory::PublicFunction {
+            parameters: vec![Parameter {
+                name: "items".to_string(),
+                param_type: "Vec<u32>".to_string(),
+            }],
+            return_type: None,
+        },
+        attributes: attributes
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+        properties: Properties::default(),
+    }
+}
+
+fn sources() -> Vec<Source> {
+    vec![Source {
+        path: "src/lib.rs".to_string(),
+        syntax_tree: syn::parse_file(PALLET).unwrap(),
+    }]
+}
+
+#[test]
+fn only_dispatchables_take_user_input() {
+    let assets = [function("store", &[]), function("helper", &[]), function("mock_public_fn", &[])];
+    let findings = BoundedInputs.run(&assets, &sources());
+
+    let assets: Vec<&str> = findings.iter().map(|finding| finding.asset.as_str()).collect();
+    assert_eq!(assets, ["store"]);
+    let evidence = findings[0].evidence.as_ref().expect("the parameter is located");
+    assert_eq!((evidence.file.as_str(), evidence.line), ("src/lib.rs", 7));
+}
+
+#[test]
+fn dispatchables_marked_by_asset_discovery_are_checked_without_sources() {
+    let assets = [function("store", &[("dispatchable", "yes")]), function("helper", &[])];
+    let findings = BoundedInputs.run(&assets, &[]);
+
+    let assets: Vec<&str> = findings.iter().map(|finding| finding.asset.as_str()).collect();
+    assert_eq!(assets, ["store"]);
+}
+
+// Asset names and threats of the findings
+fn found(findings: &[Finding]) -> Vec<(&str, ThreatType)> {
+    findings.iter().map(|finding| (finding.asset.as_str(), finding.threat.clone())).collect()
+}
+
+#[test]
+fn dispatchables_checking_no_origin_are_found() {
+    let assets = [
+        function("store", &[]),
+        function("fixed", &[]),
+        function("open", &[]),
+        function("helper", &[]),
+    ];
+    let findings = OriginCheck.run(&assets, &sources());
+    assert_eq!(found(&findings), [("open", ThreatType::MissingOriginCheck)]);
+
+    // asset-discovery already found the origin check
+    let assets = [function("open", &[("origin", "signed")])];
+    assert!(OriginCheck.run(&assets, &sources()).is_empty());
+}
+
+#[test]
+fn unchecked_arithmetic_is_found_but_length_arithmetic_is_not() {
+    let assets = [function("store", &[]), function("fixed", &[]), function("open", &[]), function("helper", &[])];
+    let findings = CheckedArithmetic.run(&assets, &sources());
+
+    assert_eq!(found(&findings), [("fixed", ThreatType::ArithmeticOverflow)]);
+    assert_eq!(findings[0].evidence.as_ref().map(|evidence| evidence.code.as_str()), Some("amount + 1"));
+}
+
+#[test]
+fn missing_constant_and_unaccounted_weights_are_found() {
+    let assets = [
+        function("store", &[("weight_accounts", "yes")]),
+        function("fixed", &[]),
+        function("open", &[]),
+        function("helper", &[]),
+    ];
+    let findings = WeightCoverage.run(&assets, &sources());
+    assert_eq!(
+        found(&findings),
+        [("fixed", ThreatType::UnderweightExtrinsic), ("open", ThreatType::UnderweightExtrinsic)]
+    );
+
+    let assets = [function("store", &[("weight_accounts", "no"), ("iterations", "items")])];
+    let findings = WeightCoverage.run(&assets, &sources());
+    assert_eq!(found(&findings), [("store", ThreatType::UnboundedIteration)]);
+}
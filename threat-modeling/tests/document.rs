@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use threat_modeling::utils::assets::{Asset, AssetCategory, Properties, Visibility};
+use threat_modeling::utils::document::{ThreatModelDocument, DOCUMENT_VERSION};
+use threat_modeling::utils::threats::{MitigationStrategy, SecurityCheck, Threat, ThreatType};
+
+fn threat(name: ThreatType, how_to_check: SecurityCheck, rule: &str) -> Threat {
+    Threat {
+        name,
+        how_to_check,
+        rule: rule.to_string(),
+        mitigation: None,
+        references: Vec::new(),
+    }
+}
+
+fn function(name: &str, attributes: &[(&str, &str)], threats: Vec<Threat>) -> Asset {
+    Asset {
+        name: name.to_string(),
+        visibility: Visibility::Public,
+        category: AssetCategory::PublicFunction {
+            parameters: Vec::new(),
+            return_type: None,
+        },
+        attributes: attributes
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+        properties: Properties {
+            threats,
+            ..Properties::default()
+        },
+    }
+}
+
+fn document(assets: Vec<Asset>) -> ThreatModelDocument {
+    ThreatModelDocument {
+        version: DOCUMENT_VERSION,
+        source: Some("src".to_string()),
+        catalogue: Some("1.0.1".to_string()),
+        assets,
+        findings: Vec::new(),
+        skipped: 0,
+    }
+}
+
+#[test]
+fn merge_drops_what_the_tool_no_longer_derives() {
+    let mut input = threat(ThreatType::UserControlledInput, SecurityCheck::InputSanitization, "vec-parameter");
+    input.mitigation = Some(MitigationStrategy {
+        strategy_type: "InputSanitization".to_string(),
+        description: "Items are capped at `T::MaxItems` before the loop".to_string(),
+        implementation_status: true,
+        generated: Some("Bound `items` in `store`".to_string()),
+    });
+    let mut saved = document(vec![
+        function(
+            "store",
+            &[("origin", "none")],
+            vec![
+                input,
+                threat(ThreatType::MissingOriginCheck, SecurityCheck::OriginCheck, "FRAME-003"),
+                threat(ThreatType::FrontRunning, SecurityCheck::InputSanitization, ""),
+            ],
+        ),
+        function("removed", &[], vec![threat(ThreatType::MissingOriginCheck, SecurityCheck::OriginCheck, "FRAME-003")]),
+        function("audit_finding", &[("manual", "yes")], Vec::new()),
+    ]);
+    let fresh = document(vec![
+        function(
+            "store",
+            &[("origin", "signed")],
+            vec![
+                threat(ThreatType::UserControlledInput, SecurityCheck::InputSanitization, "vec-parameter"),
+                threat(ThreatType::FrontRunning, SecurityCheck::InputSanitization, "FRAME-006"),
+            ],
+        ),
+        function("added", &[], Vec::new()),
+    ]);
+
+    saved.merge(fresh);
+
+    let names: Vec<&str> = saved.assets.iter().map(|asset| asset.name.as_str()).collect();
+    assert_eq!(names, ["store", "audit_finding", "added"]);
+
+    let store = &saved.assets[0];
+    assert_eq!(store.attributes.get("origin").map(String::as_str), Some("signed"));
+    let threats: Vec<(&ThreatType, &str)> = store
+        .properties
+        .threats
+        .iter()
+        .map(|threat| (&threat.name, threat.rule.as_str()))
+        .collect();
+    // `store` checks its origin now, the threat written by hand stays one
+    assert_eq!(
+        threats,
+        [(&ThreatType::UserControlledInput, "vec-parameter"), (&ThreatType::FrontRunning, "")]
+    );
+    let mitigation = store.properties.threats[0].mitigation.as_ref().unwrap();
+    assert!(mitigation.implementation_status);
+}
+
+#[test]
+fn threats_without_a_rule_load() {
+    let json = r#"{
+        "version": 1,
+        "assets": [{
+            "name": "store",
+            "visibility": "Public",
+            "category": { "PublicFunction": { "parameters": [], "return_type": null } },
+            "properties": { "threats": [{ "name": "FrontRunning", "how_to_check": "InputSanitization" }] }
+        }]
+    }"#;
+    let document: ThreatModelDocument = serde_json::from_str(json).unwrap();
+    assert_eq!(document.assets[0].properties.threats[0].rule, "");
+}
+
+fn mitigated(rule: &str, description: &str, generated: Option<&str>) -> Threat {
+    let mut threat = threat(ThreatType::MissingOriginCheck, SecurityCheck::OriginCheck, rule);
+    threat.mitigation = Some(MitigationStrategy {
+        strategy_type: "OriginCheck".to_string(),
+        description: description.to_string(),
+        implementation_status: false,
+        generated: generated.map(str::to_string),
+    });
+    threat
+}
+
+#[test]
+fn merge_refreshes_templates_and_keeps_what_was_written_by_hand() {
+    let old = "Check the origin of `{asset}`";
+    let new = "Require `T::AdminOrigin` in `{asset}`";
+    let template = |template: &str, asset: &str| template.replace("{asset}", asset);
+    let mut saved = document(vec![
+        function(
+            "store",
+            &[("origin", "none"), ("manual", "yes"), ("audited", "2024-05")],
+            vec![mitigated("FRAME-003", &template(old, "store"), Some(&template(old, "store")))],
+        ),
+        function(
+            "remove",
+            &[],
+            vec![mitigated("FRAME-003", "Only the owner may remove", Some(&template(old, "remove")))],
+        ),
+        // Written before mitigations recorded their template
+        function("clear", &[], vec![mitigated("FRAME-003", &template(old, "clear"), None)]),
+    ]);
+    let fresh = document(
+        ["store", "remove", "clear"]
+            .into_iter()
+            .map(|name| {
+                let description = template(new, name);
+                function(name, &[("origin", "signed")], vec![mitigated("FRAME-003", &description, Some(&description))])
+            })
+            .collect(),
+    );
+
+    saved.merge(fresh);
+
+    let descriptions: Vec<&str> = saved
+        .assets
+        .iter()
+        .map(|asset| asset.properties.threats[0].mitigation.as_ref().unwrap().description.as_str())
+        .collect();
+    assert_eq!(
+        descriptions,
+        ["Require `T::AdminOrigin` in `store`", "Only the owner may remove", "Check the origin of `clear`"]
+    );
+    let attributes: Vec<(&str, &str)> = saved.assets[0]
+        .attributes
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+    assert_eq!(attributes, [("audited", "2024-05"), ("manual", "yes"), ("origin", "signed")]);
+}
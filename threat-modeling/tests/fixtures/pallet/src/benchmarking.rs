@@ -0,0 +1,17 @@
+use super::*;
+use frame_benchmarking::v2::*;
+use frame_system::RawOrigin;
+
+#[benchmarks]
+mod benchmarks {
+    use super::*;
+
+    #[benchmark]
+    fn store(n: Linear<1, 100>) {
+        let caller: T::AccountId = whitelisted_caller();
+        let items = vec![0u32; n as usize];
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), items);
+    }
+}
@@ -0,0 +1,76 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod migrations;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use frame_support::pallet_prelude::*;
+    use frame_support::traits::{Currency, ReservableCurrency};
+    use frame_system::pallet_prelude::*;
+    use xcm::latest::prelude::*;
+
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+    #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config + pallet_xcm::Config {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+        type Currency: ReservableCurrency<Self::AccountId>;
+        #[pallet::constant]
+        type MaxItems: Get<u32>;
+    }
+
+    #[pallet::storage]
+    pub type Items<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, Vec<u32>, ValueQuery>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        Stored { who: T::AccountId },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        TooMany,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        #[pallet::call_index(0)]
+        #[pallet::weight(T::WeightInfo::store(items.len() as u32))]
+        pub fn store(origin: OriginFor<T>, items: Vec<u32>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            T::Currency::reserve(&who, 10u32.into())?;
+            Items::<T>::insert(&who, items);
+            Self::deposit_event(Event::Stored { who });
+            Ok(())
+        }
+
+        #[pallet::call_index(1)]
+        #[pallet::weight(10_000)]
+        pub fn notify(origin: OriginFor<T>, dest: Location, message: Xcm<()>) -> DispatchResult {
+            ensure_root(origin)?;
+            pallet_xcm::Pallet::<T>::send_xcm(Here, dest, message).map_err(|_| Error::<T>::TooMany)?;
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        pub fn count(who: &T::AccountId) -> u32 {
+            Items::<T>::get(who).len() as u32
+        }
+    }
+
+    impl<T: Config> frame_support::traits::Contains<T::AccountId> for Pallet<T> {
+        fn contains(who: &T::AccountId) -> bool {
+            Items::<T>::contains_key(who)
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use super::*;
+use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+pub struct MigrateToV1<T>(core::marker::PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+    fn on_runtime_upgrade() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() == 0 {
+            StorageVersion::new(1).put::<Pallet<T>>();
+        }
+        T::DbWeight::get().reads_writes(1, 1)
+    }
+}
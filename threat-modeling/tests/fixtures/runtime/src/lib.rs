@@ -0,0 +1,28 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{construct_runtime, parameter_types, traits::ConstU32};
+
+parameter_types! {
+    pub const ExistentialDeposit: u128 = 500;
+}
+
+impl pallet_balances::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = u128;
+    type ExistentialDeposit = ExistentialDeposit;
+    type MaxLocks = ConstU32<50>;
+}
+
+impl pallet_items::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    type MaxItems = ConstU32<64>;
+}
+
+construct_runtime!(
+    pub enum Runtime {
+        System: frame_system = 0,
+        Balances: pallet_balances = 10,
+        Items: pallet_items::{Pallet, Call, Storage, Event<T>} = 20,
+    }
+);
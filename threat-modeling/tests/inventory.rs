@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use threat_modeling::utils::catalogue::Catalogue;
+use threat_modeling::utils::assets::{Asset, AssetCategory};
+use threat_modeling::utils::helpers::{load_asset_inventory, read_sources, LoadMode, LoadedInventory};
+use threat_modeling::utils::mapping::{ThreatMapper, ThreatModel};
+use threat_modeling::utils::threats::ThreatType;
+
+// Inventories written by asset-discovery for the pallet and the runtime in `tests/fixtures`:
+//   asset-discovery tests/fixtures/pallet --format json -o tests/fixtures/pallet.json
+//   asset-discovery tests/fixtures/runtime --runtime --format json -o tests/fixtures/runtime.json
+fn load(name: &str) -> LoadedInventory {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    load_asset_inventory(&path, LoadMode::Strict).expect("asset-discovery output loads in strict mode")
+}
+
+fn find<'a>(assets: &'a [Asset], category: &str, name: &str) -> &'a Asset {
+    assets
+        .iter()
+        .find(|asset| asset.category.name() == category && asset.name == name)
+        .unwrap_or_else(|| panic!("no {} asset `{}`", category, name))
+}
+
+#[test]
+fn every_category_of_asset_discovery_loads() {
+    let pallet = load("pallet.json");
+    let runtime = load("runtime.json");
+    assert!(pallet.skipped.is_empty() && runtime.skipped.is_empty());
+
+    let categories: BTreeSet<&str> = pallet
+        .assets
+        .iter()
+        .chain(&runtime.assets)
+        .map(|asset| asset.category.name())
+        .collect();
+    let expected = [
+        "PublicFunction",
+        "Helper",
+        "Storage",
+        "Constant",
+        "Events",
+        "Error",
+        "Dependency",
+        "ExposedTrait",
+        "Xcm",
+        "Benchmark",
+        "StorageVersion",
+        "Migration",
+        "RuntimePallet",
+        "ConfigItem",
+    ];
+    assert_eq!(categories, expected.into_iter().collect());
+}
+
+#[test]
+fn category_fields_are_kept() {
+    let pallet = load("pallet.json");
+    let runtime = load("runtime.json");
+
+    match &find(&pallet.assets, "ExposedTrait", "frame_support::traits::Contains<T::AccountId>").category {
+        AssetCategory::ExposedTrait { methods, .. } => assert_eq!(methods[0].0, "contains"),
+        other => panic!("unexpected category {:?}", other),
+    }
+    match &find(&pallet.assets, "Benchmark", "store").category {
+        AssetCategory::Benchmark { dispatchables, .. } => assert_eq!(dispatchables, &["store"]),
+        other => panic!("unexpected category {:?}", other),
+    }
+    match &find(&pallet.assets, "StorageVersion", "STORAGE_VERSION").category {
+        AssetCategory::StorageVersion { version } => assert_eq!(version, "1"),
+        other => panic!("unexpected category {:?}", other),
+    }
+    match &find(&runtime.assets, "RuntimePallet", "Items").category {
+        AssetCategory::RuntimePallet { path, parts, .. } => {
+            assert_eq!(path, "pallet_items");
+            assert_eq!(parts, &["Pallet", "Call", "Storage", "Event"]);
+        }
+        other => panic!("unexpected category {:?}", other),
+    }
+    let deposit = find(&runtime.assets, "ConfigItem", "pallet_balances::ExistentialDeposit");
+    match &deposit.category {
+        AssetCategory::ConfigItem { pallet, name, bound } => {
+            assert_eq!(pallet, "pallet_balances");
+            assert_eq!(name, "ExistentialDeposit");
+            assert_eq!(bound, "ExistentialDeposit");
+        }
+        other => panic!("unexpected category {:?}", other),
+    }
+    assert_eq!(deposit.attributes.get("value").map(String::as_str), Some("500"));
+}
+
+#[test]
+fn assets_survive_serialization() {
+    let pallet = load("pallet.json");
+    let json = serde_json::to_string(&pallet.assets).unwrap();
+    let assets: Vec<Asset> = serde_json::from_str(&json).unwrap();
+
+    let names = |assets: &[Asset]| -> Vec<(String, &'static str)> {
+        assets.iter().map(|asset| (asset.name.clone(), asset.category.name())).collect()
+    };
+    assert_eq!(names(&assets), names(&pallet.assets));
+}
+
+#[test]
+fn source_of_the_inventory_is_read() {
+    let pallet = load("pallet.json");
+    let source = pallet.source.expect("asset-discovery records the source");
+    let loaded = read_sources(&Path::new(env!("CARGO_MANIFEST_DIR")).join(source)).unwrap();
+    assert_eq!(loaded.sources.len(), 3);
+    assert!(loaded.skipped.is_empty());
+}
+
+#[test]
+fn test_code_and_unparsable_files_are_left_out_of_the_source() {
+    let root = std::env::temp_dir().join(format!("threat-modeling-{}-{}", std::process::id(), "crate"));
+    let files = [
+        ("src/lib.rs", "pub fn store() {}"),
+        ("src/broken.rs", "pub fn store( {"),
+        ("src/mock.rs", "pub fn mock() {}"),
+        ("src/tests.rs", "fn works() {}"),
+        ("tests/integration.rs", "fn works() {}"),
+    ];
+    for (path, content) in files {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    let loaded = read_sources(&root).unwrap();
+    let paths: Vec<&str> = loaded.sources.iter().map(|source| source.path.as_str()).collect();
+    assert_eq!(paths, [root.join("src/lib.rs").display().to_string()]);
+    assert_eq!(loaded.skipped.len(), 1);
+    assert!(loaded.skipped[0].to_string().contains("broken.rs"));
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn findings_attach_threats_to_their_assets() {
+    let pallet = load("pallet.json");
+    assert_eq!(pallet.findings.len(), 3);
+
+    let mut model = ThreatModel::build(pallet.assets, 0, &ThreatMapper::new(Vec::new()));
+    model.attach_findings(&pallet.findings);
+    let threats = |category: &str, name: &str| -> Vec<(ThreatType, String)> {
+        find(&model.assets, category, name)
+            .properties
+            .threats
+            .iter()
+            .map(|threat| (threat.name.clone(), threat.rule.clone()))
+            .collect()
+    };
+
+    assert_eq!(
+        threats("Migration", "MigrateToV1"),
+        [(ThreatType::BrokenMigration, "migration-without-try-runtime-checks".to_string())]
+    );
+    assert_eq!(
+        threats("Storage", "Items"),
+        [(ThreatType::UnboundedStorage, "unbounded-storage-type".to_string())]
+    );
+    // `notify` is also an XCM asset, the missing benchmark is about the dispatchable
+    assert_eq!(
+        threats("PublicFunction", "notify"),
+        [(ThreatType::UnderweightExtrinsic, "missing-benchmark".to_string())]
+    );
+    assert!(threats("Xcm", "notify").is_empty());
+}
+
+#[test]
+fn only_dispatchables_miss_an_origin_check() {
+    let mut pallet = load("pallet.json");
+    // `store` as an inventory would list it if its origin check were removed
+    pallet.assets[0].attributes.remove("origin");
+
+    let mapper = ThreatMapper::with_rule_packs(Vec::new(), &Catalogue::builtin());
+    let model = ThreatModel::build(pallet.assets, 0, &mapper);
+    let unchecked: Vec<&str> = model
+        .assets
+        .iter()
+        .filter(|asset| {
+            let threats = &asset.properties.threats;
+            threats.iter().any(|threat| threat.name == ThreatType::MissingOriginCheck)
+        })
+        .map(|asset| asset.name.as_str())
+        .collect();
+    // `count` is a `pub fn` of the pallet, not a call
+    assert_eq!(unchecked, ["store"]);
+}
@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+use threat_modeling::utils::assets::{Asset, AssetCategory, Properties, Visibility};
+use threat_modeling::utils::risk::{RiskScorer, RiskWeights, WeightsError};
+
+fn write(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("threat-modeling-{}-{}", std::process::id(), name));
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn weights_left_out_keep_their_default() {
+    let path = write("weights.toml", "impact = 0.5\ncritical = 8.5\n");
+    let weights = RiskWeights::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let default = RiskWeights::default();
+    assert_eq!((weights.impact, weights.critical), (0.5, 8.5));
+    assert_eq!((weights.exposure, weights.threat, weights.high), (default.exposure, default.threat, default.high));
+}
+
+#[test]
+fn weights_load_from_yaml_and_json() {
+    let yaml = write("weights.yaml", "threat: 0.6\nmedium: 3.0\n");
+    let json = write("weights.json", r#"{ "unconfirmed": 0.5 }"#);
+    let (from_yaml, from_json) = (RiskWeights::load(&yaml), RiskWeights::load(&json));
+    std::fs::remove_file(&yaml).unwrap();
+    std::fs::remove_file(&json).unwrap();
+
+    let from_yaml = from_yaml.unwrap();
+    assert_eq!((from_yaml.threat, from_yaml.medium), (0.6, 3.0));
+    assert_eq!(from_json.unwrap().unconfirmed, 0.5);
+}
+
+#[test]
+fn invalid_weights_are_rejected() {
+    let unknown = write("unknown.toml", "exposur = 0.5\n");
+    let negative = write("negative.toml", "impact = -1.0\n");
+    let unordered = write("unordered.toml", "high = 9.5\n");
+    let ini = write("weights.ini", "impact = 0.5\n");
+    let results = [&unknown, &negative, &unordered, &ini].map(|path| RiskWeights::load(path));
+    for path in [&unknown, &negative, &unordered, &ini] {
+        std::fs::remove_file(path).unwrap();
+    }
+
+    assert!(matches!(results[0], Err(WeightsError::TomlError(..))));
+    assert!(matches!(results[1], Err(WeightsError::InvalidWeights(..))));
+    assert!(matches!(results[2], Err(WeightsError::InvalidWeights(..))));
+    assert!(matches!(results[3], Err(WeightsError::UnsupportedFormat(_))));
+}
+
+#[test]
+fn only_dispatchables_are_exposed_to_anyone() {
+    let function = |attributes: &[(&str, &str)]| Asset {
+        name: "store".to_string(),
+        visibility: Visibility::Public,
+        category: AssetCategory::PublicFunction {
+            parameters: Vec::new(),
+            return_type: None,
+        },
+        attributes: attributes.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+        properties: Properties::default(),
+    };
+    let exposure = |attributes: &[(&str, &str)]| {
+        let score = RiskScorer::default().score(&function(attributes), &[]);
+        (score.exposure, score.vector[..3].to_string())
+    };
+
+    assert_eq!(exposure(&[("dispatchable", "yes")]), (1.0, "E:U".to_string()));
+    assert_eq!(exposure(&[("dispatchable", "yes"), ("origin", "none")]), (1.0, "E:U".to_string()));
+    assert_eq!(exposure(&[("dispatchable", "yes"), ("origin", "root")]), (0.2, "E:R".to_string()));
+    // A `pub fn` outside the calls is only reached through the pallet
+    assert_eq!(exposure(&[]), (0.3, "E:I".to_string()));
+}
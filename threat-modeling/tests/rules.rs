@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use threat_modeling::utils::assets::{Asset, AssetCategory, Parameter, Properties, StorageConfig, Visibility};
+use threat_modeling::utils::mapping::ThreatMapper;
+use threat_modeling::utils::rules::{load_rule_pack, RuleError};
+use threat_modeling::utils::threats::{SecurityCheck, ThreatType};
+
+fn write(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("threat-modeling-{}-{}", std::process::id(), name));
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+fn asset(name: &str, category: AssetCategory, attributes: &[(&str, &str)]) -> Asset {
+    Asset {
+        name: name.to_string(),
+        visibility: Visibility::Public,
+        category,
+        attributes: attributes
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+        properties: Properties::default(),
+    }
+}
+
+fn transfer(attributes: &[(&str, &str)]) -> Asset {
+    let parameters = vec![Parameter {
+        name: "amount".to_string(),
+        param_type: "BalanceOf<T>".to_string(),
+    }];
+    let category = AssetCategory::PublicFunction {
+        parameters,
+        return_type: None,
+    };
+    asset("transfer", category, attributes)
+}
+
+#[test]
+fn toml_rule_pack_is_parsed() {
+    let path = write(
+        "pack.toml",
+        r#"
+[[rule]]
+name = "unchecked-balance-write"
+threat = "MissingOriginCheck"
+check = "OriginCheck"
+mitigation = "Require `T::MintOrigin` in `{asset}`"
+references = ["https://cwe.mitre.org/data/definitions/862.html"]
+when = { category = "PublicFunction", parameter_type = "BalanceOf", mentions = { writes = "Balances" } }
+"#,
+    );
+    let rules = load_rule_pack(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let rules = rules.unwrap();
+    assert_eq!(rules.len(), 1);
+    let rule = &rules[0];
+    assert_eq!(rule.name, "unchecked-balance-write");
+    assert_eq!((&rule.threat, &rule.how_to_check), (&ThreatType::MissingOriginCheck, &SecurityCheck::OriginCheck));
+    assert_eq!(rule.mitigation.as_deref(), Some("Require `T::MintOrigin` in `{asset}`"));
+    assert_eq!(rule.references.len(), 1);
+    assert!(rule.predicate.matches(&transfer(&[("writes", "Balances, Totals")])));
+    assert!(!rule.predicate.matches(&transfer(&[("writes", "Totals")])));
+}
+
+#[test]
+fn yaml_rule_pack_is_parsed() {
+    let path = write(
+        "pack.yaml",
+        r#"
+rule:
+  - name: randomness-source
+    threat: InsecureRandomness
+    check: InputSanitization
+    when:
+      any:
+        - name: pallet_insecure_randomness_collective_flip
+        - config_bound: Randomness
+      not:
+        name: TestRandomness
+"#,
+    );
+    let rules = load_rule_pack(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let rules = rules.unwrap();
+    assert_eq!(rules.len(), 1);
+    let rule = &rules[0];
+    assert_eq!(rule.threat, ThreatType::InsecureRandomness);
+    assert!(rule.mitigation.is_none() && rule.references.is_empty());
+
+    let dependency = |name: &str, attributes: &[(&str, &str)]| {
+        let category = AssetCategory::Dependency {
+            name: name.to_string(),
+            kind: "loose".to_string(),
+        };
+        asset(name, category, attributes)
+    };
+    let bound = [("bound", "Randomness<Self::Hash, BlockNumberFor<Self>>")];
+    assert!(rule.predicate.matches(&dependency("MyRandomness", &bound)));
+    assert!(!rule.predicate.matches(&dependency("TestRandomness", &bound)));
+    assert!(!rule.predicate.matches(&dependency("MyRandomness", &[("value_type", "Randomness")])));
+}
+
+#[test]
+fn rule_without_conditions_is_rejected() {
+    let path = write(
+        "empty-when.toml",
+        r#"
+[[rule]]
+name = "everything"
+threat = "UserControlledInput"
+check = "InputSanitization"
+when = {}
+"#,
+    );
+    let rules = load_rule_pack(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    match rules {
+        Err(RuleError::InvalidRule { rule, message, .. }) => {
+            assert_eq!(rule, "everything");
+            assert_eq!(message, "`when` has no condition");
+        }
+        other => panic!("expected an invalid rule, got {:?}", other),
+    }
+}
+
+#[test]
+fn malformed_rule_packs_are_rejected() {
+    let unknown = write(
+        "unknown.toml",
+        "[[rule]]\nname = \"x\"\nthreat = \"Nope\"\ncheck = \"OriginCheck\"\nwhen = { name = \"x\" }\n",
+    );
+    let yaml = write("broken.yml", "rule:\n  - name: x\n    when: [\n");
+    let json = write("pack.json", "{}");
+    let results = [&unknown, &yaml, &json].map(|path| load_rule_pack(path));
+    for path in [&unknown, &yaml, &json] {
+        std::fs::remove_file(path).unwrap();
+    }
+
+    assert!(matches!(results[0], Err(RuleError::TomlError(..))));
+    assert!(matches!(results[1], Err(RuleError::YamlError(..))));
+    assert!(matches!(results[2], Err(RuleError::UnsupportedFormat(_))));
+}
+
+// Threats the built-in catalogue attaches to an asset
+fn threats(mut asset: Asset) -> Vec<(ThreatType, String)> {
+    ThreatMapper::default().map_asset(&mut asset);
+    asset.properties.threats.into_iter().map(|threat| (threat.name, threat.rule)).collect()
+}
+
+#[test]
+fn only_v2_calls_without_an_origin_check_are_unchecked() {
+    let unchecked = (ThreatType::MissingOriginCheck, "FRAME-003".to_string());
+    assert!(threats(transfer(&[("dispatchable", "yes")])).contains(&unchecked));
+    // A `decl_module!` call starting with `ensure_signed(origin)?`
+    assert!(!threats(transfer(&[("dispatchable", "yes"), ("origin", "signed")])).contains(&unchecked));
+    // ink! messages check `self.env().caller()`, inventories before `reads_caller` also marked them as dispatchable
+    assert!(!threats(transfer(&[("ink", "message"), ("reads_caller", "false")])).contains(&unchecked));
+    assert!(!threats(transfer(&[("ink", "constructor"), ("dispatchable", "yes")])).contains(&unchecked));
+    // A `pub fn` helper of the pallet
+    assert!(!threats(transfer(&[])).contains(&unchecked));
+}
+
+#[test]
+fn only_transfers_allowing_death_reap_accounts() {
+    let reaping = (ThreatType::AccountReaping, "FRAME-001".to_string());
+    let keep_alive = [("dispatchable", "yes"), ("origin", "signed"), ("external_calls", "Currency::transfer")];
+    assert!(!threats(transfer(&keep_alive)).contains(&reaping));
+    let allow_death = [
+        ("dispatchable", "yes"),
+        ("origin", "signed"),
+        ("external_calls", "Currency::transfer"),
+        ("allow_death", "Currency::transfer"),
+    ];
+    assert!(threats(transfer(&allow_death)).contains(&reaping));
+}
+
+#[test]
+fn weak_hashers_come_from_the_catalogue() {
+    let storage = |ty: &str| {
+        let category = AssetCategory::Storage(StorageConfig {
+            visibility: Visibility::Public,
+            name: "Accounts".to_string(),
+        });
+        asset("Accounts", category, &[("type", ty)])
+    };
+    let collision = vec![(ThreatType::KeyCollision, "FRAME-006".to_string())];
+    assert_eq!(threats(storage("StorageMap<_, Twox64Concat, T::AccountId, u64>")), collision);
+    assert_eq!(threats(storage("map hasher(twox_64_concat) T::AccountId => u64")), collision);
+    assert!(threats(storage("StorageMap<_, Blake2_128Concat, T::AccountId, u64>")).is_empty());
+}